@@ -2,6 +2,7 @@ mod parser;
 mod seek_sequence;
 
 use std::collections::HashMap;
+use std::io::Read;
 use std::path::Path;
 use std::path::PathBuf;
 use std::str::Utf8Error;
@@ -53,18 +54,33 @@ impl PartialEq for IoError {
     }
 }
 
+/// Argument that makes a Codex binary apply the patch read from stdin to the
+/// files in its working directory and then exit. See
+/// [`maybe_run_apply_patch_helper`].
+pub const CODEX_APPLY_PATCH_ARG1: &str = "--codex-run-as-apply-patch";
+
 #[derive(Debug, PartialEq)]
 pub enum MaybeApplyPatch {
-    Body(Vec<Hunk>),
+    Body(ApplyPatchArgs),
     ShellParseError(ExtractHeredocError),
     PatchParseError(ParseError),
     NotApplyPatch,
 }
 
+/// The patch passed to `apply_patch` along with its parsed hunks.
+#[derive(Debug, PartialEq)]
+pub struct ApplyPatchArgs {
+    pub patch: String,
+    pub hunks: Vec<Hunk>,
+}
+
 pub fn maybe_parse_apply_patch(argv: &[String]) -> MaybeApplyPatch {
     match argv {
         [cmd, body] if cmd == "apply_patch" => match parse_patch(body) {
-            Ok(hunks) => MaybeApplyPatch::Body(hunks),
+            Ok(hunks) => MaybeApplyPatch::Body(ApplyPatchArgs {
+                patch: body.clone(),
+                hunks,
+            }),
             Err(e) => MaybeApplyPatch::PatchParseError(e),
         },
         [bash, flag, script]
@@ -74,7 +90,7 @@ pub fn maybe_parse_apply_patch(argv: &[String]) -> MaybeApplyPatch {
         {
             match extract_heredoc_body_from_apply_patch_command(script) {
                 Ok(body) => match parse_patch(&body) {
-                    Ok(hunks) => MaybeApplyPatch::Body(hunks),
+                    Ok(hunks) => MaybeApplyPatch::Body(ApplyPatchArgs { patch: body, hunks }),
                     Err(e) => MaybeApplyPatch::PatchParseError(e),
                 },
                 Err(e) => MaybeApplyPatch::ShellParseError(e),
@@ -118,6 +134,13 @@ pub enum MaybeApplyPatchVerified {
 /// construction, all paths should be absolute paths.
 pub struct ApplyPatchAction {
     changes: HashMap<PathBuf, ApplyPatchFileChange>,
+
+    /// The patch itself, for applying it in a separate process. Relative
+    /// paths in it must be resolved against `cwd`.
+    patch: String,
+
+    /// The `cwd` that was passed to [`maybe_parse_apply_patch_verified`].
+    cwd: PathBuf,
 }
 
 impl ApplyPatchAction {
@@ -130,6 +153,14 @@ impl ApplyPatchAction {
         &self.changes
     }
 
+    pub fn patch(&self) -> &str {
+        &self.patch
+    }
+
+    pub fn cwd(&self) -> &Path {
+        &self.cwd
+    }
+
     /// Should be used exclusively for testing. (Not worth the overhead of
     /// creating a feature flag for this.)
     pub fn new_add_for_test(path: &Path, content: String) -> Self {
@@ -138,7 +169,11 @@ impl ApplyPatchAction {
         }
 
         let changes = HashMap::from([(path.to_path_buf(), ApplyPatchFileChange::Add { content })]);
-        Self { changes }
+        Self {
+            changes,
+            patch: String::new(),
+            cwd: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+        }
     }
 }
 
@@ -146,7 +181,7 @@ impl ApplyPatchAction {
 /// patch.
 pub fn maybe_parse_apply_patch_verified(argv: &[String], cwd: &Path) -> MaybeApplyPatchVerified {
     match maybe_parse_apply_patch(argv) {
-        MaybeApplyPatch::Body(hunks) => {
+        MaybeApplyPatch::Body(ApplyPatchArgs { patch, hunks }) => {
             let mut changes = HashMap::new();
             for hunk in hunks {
                 let path = hunk.resolve_path(cwd);
@@ -180,7 +215,11 @@ pub fn maybe_parse_apply_patch_verified(argv: &[String], cwd: &Path) -> MaybeApp
                     }
                 }
             }
-            MaybeApplyPatchVerified::Body(ApplyPatchAction { changes })
+            MaybeApplyPatchVerified::Body(ApplyPatchAction {
+                changes,
+                patch,
+                cwd: cwd.to_path_buf(),
+            })
         }
         MaybeApplyPatch::ShellParseError(e) => MaybeApplyPatchVerified::ShellParseError(e),
         MaybeApplyPatch::PatchParseError(e) => MaybeApplyPatchVerified::CorrectnessError(e.into()),
@@ -287,6 +326,63 @@ pub fn apply_patch(
     Ok(())
 }
 
+/// If this process was started as `<exe> --codex-run-as-apply-patch`, apply
+/// the patch read from stdin to the files in the current directory and exit
+/// with a non-zero status if that fails. Codex re-executes itself this way so
+/// that patches are applied under the same sandbox as shell commands, so every
+/// binary that runs Codex sessions must call this at the start of `main()`.
+/// The patch is not passed as an argument because a single argument is
+/// limited to 128 KiB on Linux.
+pub fn maybe_run_apply_patch_helper() {
+    let mut args = std::env::args_os().skip(1);
+    if args.next().as_deref() != Some(std::ffi::OsStr::new(CODEX_APPLY_PATCH_ARG1)) {
+        return;
+    }
+
+    let mut patch = String::new();
+    let exit_code = match std::io::stdin().read_to_string(&mut patch) {
+        Ok(_) => {
+            let mut stdout = std::io::stdout();
+            let mut stderr = std::io::stderr();
+            match apply_patch_checked(&patch, &mut stdout, &mut stderr) {
+                Ok(()) => 0,
+                Err(_) => 1,
+            }
+        }
+        Err(e) => {
+            eprintln!("{CODEX_APPLY_PATCH_ARG1}: failed to read patch from stdin: {e}");
+            2
+        }
+    };
+    std::process::exit(exit_code);
+}
+
+/// Like [`apply_patch`], but returns an error if the hunks could not be
+/// applied rather than only reporting it on `stderr`.
+fn apply_patch_checked(
+    patch: &str,
+    stdout: &mut impl std::io::Write,
+    stderr: &mut impl std::io::Write,
+) -> anyhow::Result<()> {
+    let hunks = match parse_patch(patch) {
+        Ok(hunks) => hunks,
+        Err(e) => {
+            writeln!(stderr, "{e}")?;
+            return Err(e.into());
+        }
+    };
+    match apply_hunks_to_files(&hunks) {
+        Ok(affected) => {
+            print_summary(&affected, stdout)?;
+            Ok(())
+        }
+        Err(e) => {
+            writeln!(stderr, "{e:#}")?;
+            Err(e)
+        }
+    }
+}
+
 /// Applies hunks and continues to update stdout/stderr
 pub fn apply_hunks(
     hunks: &[Hunk],
@@ -649,7 +745,7 @@ mod tests {
         ]);
 
         match maybe_parse_apply_patch(&args) {
-            MaybeApplyPatch::Body(hunks) => {
+            MaybeApplyPatch::Body(ApplyPatchArgs { hunks, .. }) => {
                 assert_eq!(
                     hunks,
                     vec![Hunk::AddFile {
@@ -676,7 +772,7 @@ PATCH"#,
         ]);
 
         match maybe_parse_apply_patch(&args) {
-            MaybeApplyPatch::Body(hunks) => {
+            MaybeApplyPatch::Body(ApplyPatchArgs { hunks, .. }) => {
                 assert_eq!(
                     hunks,
                     vec![Hunk::AddFile {
@@ -1190,7 +1286,34 @@ g
                         new_content: "updated session directory content\n".to_string(),
                     },
                 )]),
+                patch: argv[1].clone(),
+                cwd: session_dir.path().to_path_buf(),
             })
         );
     }
+
+    #[test]
+    fn test_apply_patch_checked_reports_failures() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("missing.txt");
+        let patch = wrap_patch(&format!(
+            r#"*** Update File: {}
+@@
+-old
++new"#,
+            path.display()
+        ));
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        // `apply_patch` only reports the failure on stderr.
+        assert!(apply_patch(&patch, &mut stdout, &mut stderr).is_ok());
+        assert!(!stderr.is_empty());
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        assert!(apply_patch_checked(&patch, &mut stdout, &mut stderr).is_err());
+        assert!(stdout.is_empty());
+        assert!(!stderr.is_empty());
+    }
 }
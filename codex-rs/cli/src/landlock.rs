@@ -3,8 +3,10 @@
 //! On Linux the command is executed inside a Landlock + seccomp sandbox by
 //! calling the low-level `exec_linux` helper from `codex_core::linux`.
 
+use codex_core::config::ShellEnvironmentPolicy;
 use codex_core::exec::StdioPolicy;
 use codex_core::exec::spawn_child_sync;
+use codex_core::exec_env::create_env;
 use codex_core::exec_linux::apply_sandbox_policy_to_current_thread;
 use codex_core::protocol::SandboxPolicy;
 use std::process::ExitStatus;
@@ -23,7 +25,8 @@ pub fn run_landlock(command: Vec<String>, sandbox_policy: SandboxPolicy) -> anyh
         let cwd = std::env::current_dir()?;
 
         apply_sandbox_policy_to_current_thread(&sandbox_policy, &cwd)?;
-        let mut child = spawn_child_sync(command, cwd, &sandbox_policy, StdioPolicy::Inherit)?;
+        let env = create_env(&ShellEnvironmentPolicy::default());
+        let mut child = spawn_child_sync(command, cwd, &sandbox_policy, StdioPolicy::Inherit, env)?;
         let status = child.wait()?;
         Ok(status)
     });
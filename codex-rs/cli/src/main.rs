@@ -35,6 +35,9 @@ enum Subcommand {
     #[clap(visible_alias = "e")]
     Exec(ExecCli),
 
+    /// Resume a previous interactive session from its rollout file.
+    Resume(ResumeCommand),
+
     /// Experimental: run Codex as an MCP server.
     Mcp,
 
@@ -46,6 +49,16 @@ enum Subcommand {
     Debug(DebugArgs),
 }
 
+#[derive(Debug, Parser)]
+struct ResumeCommand {
+    /// Session id or path to the rollout file (`~/.codex/sessions/rollout-*.jsonl`).
+    #[arg(value_name = "SESSION_ID|PATH")]
+    session: String,
+
+    #[clap(flatten)]
+    interactive: TuiCli,
+}
+
 #[derive(Debug, Parser)]
 struct DebugArgs {
     #[command(subcommand)]
@@ -65,7 +78,11 @@ enum DebugCommand {
 struct ReplProto {}
 
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {    // Set up logging first
+async fn main() -> anyhow::Result<()> {
+    // Codex applies patches by re-executing this binary under the sandbox.
+    codex_core::maybe_run_apply_patch_helper();
+
+    // Set up logging first
     if env::var("RUST_LOG").is_err() {
         unsafe { env::set_var("RUST_LOG", "info"); }
     }
@@ -106,6 +123,13 @@ async fn main() -> anyhow::Result<()> {    // Set up logging first
         Some(Subcommand::Exec(exec_cli)) => {
             codex_exec::run_main(exec_cli).await?;
         }
+        Some(Subcommand::Resume(ResumeCommand {
+            session,
+            mut interactive,
+        })) => {
+            interactive.resume = Some(session);
+            codex_tui::run_main(interactive)?;
+        }
         Some(Subcommand::Mcp) => {
             codex_mcp_server::run_main().await?;
         }
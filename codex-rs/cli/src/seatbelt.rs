@@ -1,5 +1,7 @@
+use codex_core::config::ShellEnvironmentPolicy;
 use codex_core::exec::StdioPolicy;
 use codex_core::exec::spawn_command_under_seatbelt;
+use codex_core::exec_env::create_env;
 use codex_core::protocol::SandboxPolicy;
 
 use crate::exit_status::handle_exit_status;
@@ -9,8 +11,10 @@ pub async fn run_seatbelt(
     sandbox_policy: SandboxPolicy,
 ) -> anyhow::Result<()> {
     let cwd = std::env::current_dir()?;
+    let env = create_env(&ShellEnvironmentPolicy::default());
     let mut child =
-        spawn_command_under_seatbelt(command, &sandbox_policy, cwd, StdioPolicy::Inherit).await?;
+        spawn_command_under_seatbelt(command, &sandbox_policy, cwd, StdioPolicy::Inherit, env)
+            .await?;
     let status = child.wait().await?;
     handle_exit_status(status);
 }
@@ -55,6 +55,14 @@ pub struct SandboxPermissionOption {
     ///     -s disk-write-folder=$HOME/.pyenv/shims
     /// ```
     ///
+    /// Without disk-full-read-access, reads are limited to the cwd, the
+    /// writable folders, and system paths on Linux; disk-read-folder grants
+    /// read access to additional folders:
+    ///
+    /// ```shell
+    ///     -s disk-read-folder=$HOME/.cargo
+    /// ```
+    ///
     /// These permissions are quite broad and should be used with caution:
     ///
     /// ```shell
@@ -66,7 +74,8 @@ pub struct SandboxPermissionOption {
 }
 
 /// Custom value-parser so we can keep the CLI surface small *and*
-/// still handle the parameterised `disk-write-folder` case.
+/// still handle the parameterised `disk-read-folder` and `disk-write-folder`
+/// cases.
 fn parse_sandbox_permission(raw: &str) -> std::io::Result<SandboxPermission> {
     let base_path = std::env::current_dir()?;
     parse_sandbox_permission_with_base_path(raw, base_path)
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::time::Duration;
 
 use bytes::Bytes;
@@ -26,10 +27,14 @@ use crate::flags::OPENAI_REQUEST_MAX_RETRIES;
 use crate::flags::OPENAI_STREAM_IDLE_TIMEOUT_MS;
 use crate::models::ContentItem;
 use crate::models::ResponseItem;
+use crate::openai_tools::create_tools_json_for_chat_completions_api;
+use crate::protocol::TokenUsage;
 use crate::util::backoff;
 
-/// Implementation for the classic Chat Completions API. This is intentionally
-/// minimal: we only stream back plain assistant text.
+/// Implementation for the classic Chat Completions API. Assistant text is
+/// streamed back as-is and `tool_calls` are mapped onto
+/// [`ResponseItem::FunctionCall`] so the agent loop can run them like it does
+/// for the Responses API.
 pub(crate) async fn stream_chat_completions(
     prompt: &Prompt,
     model: &str,
@@ -41,32 +46,19 @@ pub(crate) async fn stream_chat_completions(
         return stream_github_copilot_completions(prompt, model, client, provider).await;
     }
 
-    // Build messages array
-    let mut messages = Vec::<serde_json::Value>::new();
+    let messages = build_chat_messages(prompt);
+    let tools_json = create_tools_json_for_chat_completions_api(prompt)?;
 
-    let full_instructions = prompt.get_full_instructions();
-    messages.push(json!({"role": "system", "content": full_instructions}));
-
-    for item in &prompt.input {
-        if let ResponseItem::Message { role, content } = item {
-            let mut text = String::new();
-            for c in content {
-                match c {
-                    ContentItem::InputText { text: t } | ContentItem::OutputText { text: t } => {
-                        text.push_str(t);
-                    }
-                    _ => {}
-                }
-            }
-            messages.push(json!({"role": role, "content": text}));
-        }
-    }
-
-    let payload = json!({
+    let mut payload = json!({
         "model": model,
         "messages": messages,
-        "stream": true
+        "stream": true,
+        "stream_options": {"include_usage": true},
     });
+    // Many OpenAI-compatible servers reject an empty `tools` array.
+    if !tools_json.is_empty() {
+        payload["tools"] = json!(tools_json);
+    }
 
     let base_url = provider.base_url.trim_end_matches('/');
     let url = format!("{}/chat/completions", base_url);
@@ -136,38 +128,23 @@ pub(crate) async fn stream_github_copilot_completions(
     client: &reqwest::Client,
     provider: &ModelProviderInfo,
 ) -> Result<ResponseStream> {
-    // Build messages array with GitHub Copilot specific format
-    let mut messages = Vec::<serde_json::Value>::new();
-
-    let full_instructions = prompt.get_full_instructions();
-    messages.push(json!({"role": "system", "content": full_instructions}));
-
-    for item in &prompt.input {
-        if let ResponseItem::Message { role, content } = item {
-            let mut text = String::new();
-            for c in content {
-                match c {
-                    ContentItem::InputText { text: t } | ContentItem::OutputText { text: t } => {
-                        text.push_str(t);
-                    }
-                    _ => {}
-                }
-            }
-            messages.push(json!({"role": role, "content": text}));
-        }
-    }
+    let messages = build_chat_messages(prompt);
 
     // GitHub Copilot prefers gpt-4 if no model is specified
     let model_to_use = if model.is_empty() { "gpt-4" } else { model };
+    let tools_json = create_tools_json_for_chat_completions_api(prompt)?;
     
-    let payload = json!({
+    let mut payload = json!({
         "intent": true,
         "model": model_to_use,
         "messages": messages,
         "stream": true,
         "temperature": 0.1,
-        "n": 1
+        "n": 1,
     });
+    if !tools_json.is_empty() {
+        payload["tools"] = json!(tools_json);
+    }
 
     let base_url = provider.base_url.trim_end_matches('/');
     let url = format!("{}/chat/completions", base_url);
@@ -251,6 +228,165 @@ pub(crate) async fn stream_github_copilot_completions(
     }
 }
 
+/// Converts the conversation in `prompt` into the `messages` array expected by
+/// the Chat Completions API. Function calls issued by the model are replayed as
+/// assistant `tool_calls` and their outputs as `tool` messages so that the
+/// model can see the results of the tools it invoked on previous turns.
+fn build_chat_messages(prompt: &Prompt) -> Vec<serde_json::Value> {
+    let mut messages = Vec::<serde_json::Value>::new();
+
+    let full_instructions = prompt.get_full_instructions();
+    messages.push(json!({"role": "system", "content": full_instructions}));
+
+    for item in &prompt.input {
+        match item {
+            ResponseItem::Message { role, content } => {
+                let mut text = String::new();
+                for c in content {
+                    match c {
+                        ContentItem::InputText { text: t }
+                        | ContentItem::OutputText { text: t } => {
+                            text.push_str(t);
+                        }
+                        _ => {}
+                    }
+                }
+                messages.push(json!({"role": role, "content": text}));
+            }
+            ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+            } => {
+                let tool_call = json!({
+                    "id": call_id,
+                    "type": "function",
+                    "function": {
+                        "name": name,
+                        "arguments": arguments,
+                    }
+                });
+
+                // All tool calls (and any text) produced by the model in a
+                // single turn must be part of the same assistant message, so
+                // merge into the previous assistant message when there is one.
+                match messages.last_mut() {
+                    Some(last) if last["role"] == "assistant" => {
+                        match last.get_mut("tool_calls").and_then(|t| t.as_array_mut()) {
+                            Some(tool_calls) => tool_calls.push(tool_call),
+                            None => last["tool_calls"] = json!([tool_call]),
+                        }
+                    }
+                    _ => messages.push(json!({
+                        "role": "assistant",
+                        "content": null,
+                        "tool_calls": [tool_call],
+                    })),
+                }
+            }
+            ResponseItem::FunctionCallOutput { call_id, output } => {
+                messages.push(json!({
+                    "role": "tool",
+                    "tool_call_id": call_id,
+                    "content": output.content,
+                }));
+            }
+            // `local_shell` is only offered over the Responses API, and
+            // reasoning items have no Chat Completions equivalent.
+            ResponseItem::LocalShellCall { .. } | ResponseItem::Reasoning { .. } => {}
+            ResponseItem::Other => {}
+        }
+    }
+
+    messages
+}
+
+/// Accumulates the `tool_calls` deltas streamed by the Chat Completions API.
+/// The name and call id arrive in the first delta for a given `index`, while
+/// the JSON arguments are spread across the subsequent deltas.
+#[derive(Default)]
+struct ToolCallAccumulator {
+    calls: BTreeMap<u64, PendingToolCall>,
+}
+
+#[derive(Default)]
+struct PendingToolCall {
+    name: String,
+    arguments: String,
+    call_id: String,
+}
+
+impl ToolCallAccumulator {
+    /// Record the `tool_calls` array (if any) from a `choices[0].delta`.
+    fn ingest(&mut self, delta: &serde_json::Value) {
+        let Some(tool_calls) = delta.get("tool_calls").and_then(|t| t.as_array()) else {
+            return;
+        };
+
+        for tool_call in tool_calls {
+            let index = tool_call.get("index").and_then(|i| i.as_u64()).unwrap_or(0);
+            let pending = self.calls.entry(index).or_default();
+
+            if let Some(id) = tool_call.get("id").and_then(|i| i.as_str()) {
+                pending.call_id = id.to_string();
+            }
+
+            if let Some(function) = tool_call.get("function") {
+                if let Some(name) = function.get("name").and_then(|n| n.as_str()) {
+                    pending.name.push_str(name);
+                }
+                if let Some(arguments) = function.get("arguments").and_then(|a| a.as_str()) {
+                    pending.arguments.push_str(arguments);
+                }
+            }
+        }
+    }
+
+    /// Drain every tool call seen so far as `ResponseItem::FunctionCall`s,
+    /// ordered by their `index`.
+    fn take_items(&mut self) -> Vec<ResponseItem> {
+        std::mem::take(&mut self.calls)
+            .into_values()
+            .map(|call| ResponseItem::FunctionCall {
+                name: call.name,
+                arguments: call.arguments,
+                call_id: call.call_id,
+            })
+            .collect()
+    }
+
+    /// Forward any accumulated tool calls to `tx_event`. Returns `false` if the
+    /// receiver hung up.
+    async fn flush(&mut self, tx_event: &mpsc::Sender<Result<ResponseEvent>>) -> bool {
+        for item in self.take_items() {
+            if tx_event
+                .send(Ok(ResponseEvent::OutputItemDone(item)))
+                .await
+                .is_err()
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Extracts the `usage` block that is sent with the final chunk of a stream
+/// (when `stream_options.include_usage` is set).
+fn parse_chat_usage(chunk: &serde_json::Value) -> Option<TokenUsage> {
+    let usage = chunk.get("usage").filter(|u| !u.is_null())?;
+    let count = |value: Option<&serde_json::Value>| value.and_then(|v| v.as_u64()).unwrap_or(0);
+    Some(TokenUsage {
+        input_tokens: count(usage.get("prompt_tokens")),
+        cached_input_tokens: count(usage.pointer("/prompt_tokens_details/cached_tokens")),
+        output_tokens: count(usage.get("completion_tokens")),
+        reasoning_output_tokens: count(
+            usage.pointer("/completion_tokens_details/reasoning_tokens"),
+        ),
+        total_tokens: count(usage.get("total_tokens")),
+    })
+}
+
 /// Lightweight SSE processor for the Chat Completions streaming format. The
 /// output is mapped onto Codex's internal [`ResponseEvent`] so that the rest
 /// of the pipeline can stay agnostic of the underlying wire format.
@@ -261,6 +397,8 @@ where
     let mut stream = stream.eventsource();
 
     let idle_timeout = *OPENAI_STREAM_IDLE_TIMEOUT_MS;
+    let mut tool_calls = ToolCallAccumulator::default();
+    let mut token_usage = None;
 
     loop {
         let sse = match timeout(idle_timeout, stream.next()).await {
@@ -271,9 +409,13 @@ where
             }
             Ok(None) => {
                 // Stream closed gracefully – emit Completed with dummy id.
+                if !tool_calls.flush(&tx_event).await {
+                    return;
+                }
                 let _ = tx_event
                     .send(Ok(ResponseEvent::Completed {
                         response_id: String::new(),
+                        token_usage,
                     }))
                     .await;
                 return;
@@ -288,9 +430,13 @@ where
 
         // OpenAI Chat streaming sends a literal string "[DONE]" when finished.
         if sse.data.trim() == "[DONE]" {
+            if !tool_calls.flush(&tx_event).await {
+                return;
+            }
             let _ = tx_event
                 .send(Ok(ResponseEvent::Completed {
                     response_id: String::new(),
+                    token_usage,
                 }))
                 .await;
             return;
@@ -302,22 +448,35 @@ where
             Err(_) => continue,
         };
 
-        let content_opt = chunk
-            .get("choices")
-            .and_then(|c| c.get(0))
-            .and_then(|c| c.get("delta"))
+        if let Some(usage) = parse_chat_usage(&chunk) {
+            token_usage = Some(usage);
+        }
+
+        let choice = chunk.get("choices").and_then(|c| c.get(0));
+        let delta = choice.and_then(|c| c.get("delta"));
+
+        let content_opt = delta
             .and_then(|d| d.get("content"))
             .and_then(|c| c.as_str());
 
         if let Some(content) = content_opt {
-            let item = ResponseItem::Message {
-                role: "assistant".to_string(),
-                content: vec![ContentItem::OutputText {
-                    text: content.to_string(),
-                }],
-            };
+            let _ = tx_event
+                .send(Ok(ResponseEvent::OutputTextDelta(content.to_string())))
+                .await;
+        }
 
-            let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
+        if let Some(delta) = delta {
+            tool_calls.ingest(delta);
+        }
+
+        // The model is done with this choice (`finish_reason` is typically
+        // "tool_calls" when it wants us to run a tool), so the accumulated
+        // arguments are complete.
+        let finished = choice
+            .and_then(|c| c.get("finish_reason"))
+            .is_some_and(|f| !f.is_null());
+        if finished && !tool_calls.flush(&tx_event).await {
+            return;
         }
     }
 }
@@ -329,6 +488,8 @@ where
 {
     let mut stream = stream.eventsource();
     let idle_timeout = *OPENAI_STREAM_IDLE_TIMEOUT_MS;
+    let mut tool_calls = ToolCallAccumulator::default();
+    let mut token_usage = None;
 
     loop {
         let sse = match timeout(idle_timeout, stream.next()).await {
@@ -339,9 +500,13 @@ where
             }
             Ok(None) => {
                 // Stream closed gracefully – emit Completed with dummy id.
+                if !tool_calls.flush(&tx_event).await {
+                    return;
+                }
                 let _ = tx_event
                     .send(Ok(ResponseEvent::Completed {
                         response_id: String::new(),
+                        token_usage,
                     }))
                     .await;
                 return;
@@ -356,9 +521,13 @@ where
 
         // GitHub Copilot sends a literal string "[DONE]" when finished just like OpenAI
         if sse.data.trim() == "[DONE]" {
+            if !tool_calls.flush(&tx_event).await {
+                return;
+            }
             let _ = tx_event
                 .send(Ok(ResponseEvent::Completed {
                     response_id: String::new(),
+                    token_usage,
                 }))
                 .await;
             return;
@@ -370,11 +539,14 @@ where
             Err(_) => continue,
         };
 
+        if let Some(usage) = parse_chat_usage(&chunk) {
+            token_usage = Some(usage);
+        }
+
         // Find content in the GitHub Copilot response structure
-        let content_opt = chunk
-            .get("choices")
-            .and_then(|c| c.get(0))
-            .and_then(|c| c.get("delta"))
+        let choice = chunk.get("choices").and_then(|c| c.get(0));
+        let delta = choice.and_then(|c| c.get("delta"));
+        let content_opt = delta
             .and_then(|d| {
                 // Check if we have simple text content
                 if let Some(content) = d.get("content") {
@@ -387,27 +559,38 @@ where
             });
 
         if let Some(content) = content_opt {
-            let item = ResponseItem::Message {
-                role: "assistant".to_string(),
-                content: vec![ContentItem::OutputText { text: content }],
-            };
+            let _ = tx_event
+                .send(Ok(ResponseEvent::OutputTextDelta(content)))
+                .await;
+        }
+
+        if let Some(delta) = delta {
+            tool_calls.ingest(delta);
+        }
 
-            let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
+        let finished = choice
+            .and_then(|c| c.get("finish_reason"))
+            .is_some_and(|f| !f.is_null());
+        if finished && !tool_calls.flush(&tx_event).await {
+            return;
         }
     }
 }
 
 /// Optional client-side aggregation helper
 ///
-/// Stream adapter that merges the incremental `OutputItemDone` chunks coming from
-/// [`process_chat_sse`] into a *running* assistant message, **suppressing the
-/// per-token deltas**.  The stream stays silent while the model is thinking
-/// and only emits two events per turn:
+/// Stream adapter that merges the incremental `OutputTextDelta` chunks coming
+/// from [`process_chat_sse`] into a *running* assistant message. The deltas
+/// themselves are forwarded as they arrive so front-ends can render the text
+/// live, and once the model is done the adapter emits:
 ///
 ///   1. `ResponseEvent::OutputItemDone` with the *complete* assistant message
 ///      (fully concatenated).
 ///   2. The original `ResponseEvent::Completed` right after it.
 ///
+/// Non-message items such as `FunctionCall` are passed through unchanged
+/// (after any text that preceded them).
+///
 /// This mirrors the behaviour the TypeScript CLI exposes to its higher layers.
 ///
 /// The adapter is intentionally *lossless*: callers who do **not** opt in via
@@ -416,7 +599,8 @@ where
 pub(crate) struct AggregatedChatStream<S> {
     inner: S,
     cumulative: String,
-    pending_completed: Option<ResponseEvent>,
+    /// Event held back so that it is returned *after* the aggregated message.
+    pending: Option<ResponseEvent>,
 }
 
 impl<S> Stream for AggregatedChatStream<S>
@@ -428,52 +612,44 @@ where
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let this = self.get_mut();
 
-        // First, flush any buffered Completed event from the previous call.
-        if let Some(ev) = this.pending_completed.take() {
+        // First, flush any buffered event from the previous call.
+        if let Some(ev) = this.pending.take() {
             return Poll::Ready(Some(Ok(ev)));
         }
 
-        loop {
-            match Pin::new(&mut this.inner).poll_next(cx) {
-                Poll::Pending => return Poll::Pending,
-                Poll::Ready(None) => return Poll::Ready(None),
-                Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
-                Poll::Ready(Some(Ok(ResponseEvent::OutputItemDone(item)))) => {
-                    // Accumulate *assistant* text but do not emit yet.
-                    if let crate::models::ResponseItem::Message { role, content } = &item {
-                        if role == "assistant" {
-                            if let Some(text) = content.iter().find_map(|c| match c {
-                                crate::models::ContentItem::OutputText { text } => Some(text),
-                                _ => None,
-                            }) {
-                                this.cumulative.push_str(text);
-                            }
-                        }
-                    }
+        let event = match Pin::new(&mut this.inner).poll_next(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+            Poll::Ready(Some(Ok(event))) => event,
+        };
 
-                    // Swallow partial event; keep polling.
-                    continue;
+        match event {
+            ResponseEvent::OutputTextDelta(delta) => {
+                // Accumulate the text for the final message, but still let
+                // the caller see the delta right away.
+                this.cumulative.push_str(&delta);
+                Poll::Ready(Some(Ok(ResponseEvent::OutputTextDelta(delta))))
+            }
+            ResponseEvent::ReasoningSummaryDelta(_) => Poll::Ready(Some(Ok(event))),
+            // Items (e.g. a function call) and `Completed` are forwarded
+            // as-is, but only after the text the model produced before them.
+            ResponseEvent::OutputItemDone(_) | ResponseEvent::Completed { .. } => {
+                if this.cumulative.is_empty() {
+                    return Poll::Ready(Some(Ok(event)));
                 }
-                Poll::Ready(Some(Ok(ResponseEvent::Completed { response_id }))) => {
-                    if !this.cumulative.is_empty() {
-                        let aggregated_item = crate::models::ResponseItem::Message {
-                            role: "assistant".to_string(),
-                            content: vec![crate::models::ContentItem::OutputText {
-                                text: std::mem::take(&mut this.cumulative),
-                            }],
-                        };
-
-                        // Buffer Completed so it is returned *after* the aggregated message.
-                        this.pending_completed = Some(ResponseEvent::Completed { response_id });
-
-                        return Poll::Ready(Some(Ok(ResponseEvent::OutputItemDone(
-                            aggregated_item,
-                        ))));
-                    }
 
-                    // Nothing aggregated – forward Completed directly.
-                    return Poll::Ready(Some(Ok(ResponseEvent::Completed { response_id })));
-                } // No other `Ok` variants exist at the moment, continue polling.
+                let aggregated_item = crate::models::ResponseItem::Message {
+                    role: "assistant".to_string(),
+                    content: vec![crate::models::ContentItem::OutputText {
+                        text: std::mem::take(&mut this.cumulative),
+                    }],
+                };
+
+                // Buffer the event so it is returned *after* the aggregated message.
+                this.pending = Some(event);
+
+                Poll::Ready(Some(Ok(ResponseEvent::OutputItemDone(aggregated_item))))
             }
         }
     }
@@ -481,32 +657,187 @@ where
 
 /// Extension trait that activates aggregation on any stream of [`ResponseEvent`].
 pub(crate) trait AggregateStreamExt: Stream<Item = Result<ResponseEvent>> + Sized {
-    /// Returns a new stream that emits **only** the final assistant message
-    /// per turn instead of every incremental delta.  The produced
+    /// Returns a new stream that, in addition to the incremental deltas,
+    /// emits the final assistant message per turn.  The produced
     /// `ResponseEvent` sequence for a typical text turn looks like:
     ///
     /// ```ignore
+    ///     OutputTextDelta(<chunk>)
+    ///     OutputTextDelta(<chunk>)
     ///     OutputItemDone(<full message>)
     ///     Completed { .. }
     /// ```
     ///
-    /// No other `OutputItemDone` events will be seen by the caller.
+    /// The only other `OutputItemDone` events seen by the caller are
+    /// non-message items such as function calls.
     ///
     /// Usage:
     ///
     /// ```ignore
     /// let agg_stream = client.stream(&prompt).await?.aggregate();
     /// while let Some(event) = agg_stream.next().await {
-    ///     // event now contains cumulative text
+    ///     // `OutputItemDone` events now contain cumulative text
     /// }
     /// ```
     fn aggregate(self) -> AggregatedChatStream<Self> {
         AggregatedChatStream {
             inner: self,
             cumulative: String::new(),
-            pending_completed: None,
+            pending: None,
         }
     }
 }
 
 impl<T> AggregateStreamExt for T where T: Stream<Item = Result<ResponseEvent>> + Sized {}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::models::FunctionCallOutputPayload;
+    use pretty_assertions::assert_eq;
+
+    async fn collect_chat_sse(chunks: &[&str]) -> Vec<ResponseEvent> {
+        let mut body = String::new();
+        for chunk in chunks {
+            body.push_str(&format!("data: {chunk}\n\n"));
+        }
+        let stream = futures::stream::iter(vec![Ok(Bytes::from(body))]);
+        let (tx_event, mut rx_event) = mpsc::channel::<Result<ResponseEvent>>(16);
+        tokio::spawn(process_chat_sse(stream, tx_event));
+
+        let mut events = Vec::new();
+        while let Some(event) = rx_event.recv().await {
+            events.push(event.unwrap());
+        }
+        events
+    }
+
+    #[tokio::test]
+    async fn streamed_tool_call_becomes_function_call() {
+        let events = collect_chat_sse(&[
+            r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"id":"call_1","type":"function","function":{"name":"shell","arguments":""}}]}}]}"#,
+            r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"{\"command\":"}}]}}]}"#,
+            r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"[\"ls\"]}"}}]}}]}"#,
+            r#"{"choices":[{"delta":{},"finish_reason":"tool_calls"}]}"#,
+            "[DONE]",
+        ])
+        .await;
+
+        assert_eq!(events.len(), 2);
+        match &events[0] {
+            ResponseEvent::OutputItemDone(ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+            }) => {
+                assert_eq!(name, "shell");
+                assert_eq!(arguments, r#"{"command":["ls"]}"#);
+                assert_eq!(call_id, "call_1");
+            }
+            other => panic!("unexpected event: {other:?}"),
+        }
+        assert!(matches!(events[1], ResponseEvent::Completed { .. }));
+    }
+
+    #[tokio::test]
+    async fn usage_chunk_is_reported_on_completed() {
+        let events = collect_chat_sse(&[
+            r#"{"choices":[{"delta":{"content":"Hi"},"finish_reason":"stop"}]}"#,
+            r#"{"choices":[],"usage":{"prompt_tokens":12,"completion_tokens":3,"total_tokens":15,"prompt_tokens_details":{"cached_tokens":8}}}"#,
+            "[DONE]",
+        ])
+        .await;
+
+        match events.last() {
+            Some(ResponseEvent::Completed { token_usage, .. }) => assert_eq!(
+                *token_usage,
+                Some(TokenUsage {
+                    input_tokens: 12,
+                    cached_input_tokens: 8,
+                    output_tokens: 3,
+                    reasoning_output_tokens: 0,
+                    total_tokens: 15,
+                })
+            ),
+            other => panic!("unexpected event: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn aggregated_stream_forwards_deltas_before_full_message() {
+        let events = collect_chat_sse(&[
+            r#"{"choices":[{"delta":{"content":"Hello"}}]}"#,
+            r#"{"choices":[{"delta":{"content":", world"}}]}"#,
+            r#"{"choices":[{"delta":{},"finish_reason":"stop"}]}"#,
+            "[DONE]",
+        ])
+        .await;
+        let events: Vec<ResponseEvent> = futures::stream::iter(events.into_iter().map(Ok))
+            .aggregate()
+            .map(|event| event.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(events.len(), 4);
+        assert!(matches!(&events[0], ResponseEvent::OutputTextDelta(d) if d == "Hello"));
+        assert!(matches!(&events[1], ResponseEvent::OutputTextDelta(d) if d == ", world"));
+        match &events[2] {
+            ResponseEvent::OutputItemDone(ResponseItem::Message { role, content }) => {
+                assert_eq!(role, "assistant");
+                assert!(matches!(
+                    &content[..],
+                    [ContentItem::OutputText { text }] if text == "Hello, world"
+                ));
+            }
+            other => panic!("unexpected event: {other:?}"),
+        }
+        assert!(matches!(events[3], ResponseEvent::Completed { .. }));
+    }
+
+    #[test]
+    fn function_calls_are_replayed_as_tool_messages() {
+        let prompt = Prompt {
+            input: vec![
+                ResponseItem::user_text("list files"),
+                ResponseItem::FunctionCall {
+                    name: "shell".to_string(),
+                    arguments: r#"{"command":["ls"]}"#.to_string(),
+                    call_id: "call_1".to_string(),
+                },
+                ResponseItem::FunctionCallOutput {
+                    call_id: "call_1".to_string(),
+                    output: FunctionCallOutputPayload {
+                        content: "README.md".to_string(),
+                        success: Some(true),
+                    },
+                },
+            ],
+            ..Default::default()
+        };
+
+        let messages = build_chat_messages(&prompt);
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[1], json!({"role": "user", "content": "list files"}));
+        assert_eq!(
+            messages[2],
+            json!({
+                "role": "assistant",
+                "content": null,
+                "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": {
+                        "name": "shell",
+                        "arguments": r#"{"command":["ls"]}"#,
+                    }
+                }]
+            })
+        );
+        assert_eq!(
+            messages[3],
+            json!({"role": "tool", "tool_call_id": "call_1", "content": "README.md"})
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use crate::error::Result;
 use crate::models::ResponseItem;
+use crate::protocol::TokenUsage;
 use futures::Stream;
 use serde::Serialize;
 use std::borrow::Cow;
@@ -30,6 +31,13 @@ pub struct Prompt {
     /// the "fully qualified" tool name (i.e., prefixed with the server name),
     /// which should be reported to the model in place of Tool::name.
     pub extra_tools: HashMap<String, mcp_types::Tool>,
+
+    /// Whether to offer the built-in tools for listing and reading the
+    /// resources of the configured MCP servers.
+    pub include_mcp_resource_tools: bool,
+
+    /// Whether to offer the built-in tools for persistent shell sessions.
+    pub include_shell_session_tools: bool,
 }
 
 impl Prompt {
@@ -40,6 +48,8 @@ impl Prompt {
             prev_id: None,
             store: false,
             extra_tools: HashMap::new(),
+            include_mcp_resource_tools: false,
+            include_shell_session_tools: false,
         }
     }
 
@@ -57,7 +67,17 @@ impl Prompt {
 #[derive(Debug)]
 pub enum ResponseEvent {
     OutputItemDone(ResponseItem),
-    Completed { response_id: String },
+    /// Incremental chunk of assistant text. The complete message is still
+    /// delivered via `OutputItemDone` once the model finishes it.
+    OutputTextDelta(String),
+    /// Incremental chunk of a reasoning summary. As with `OutputTextDelta`,
+    /// the full summary arrives later via `OutputItemDone`.
+    ReasoningSummaryDelta(String),
+    Completed {
+        response_id: String,
+        /// Tokens used by the response, when the provider reports them.
+        token_usage: Option<TokenUsage>,
+    },
 }
 
 #[derive(Debug, Serialize)]
@@ -10,15 +10,14 @@ use std::sync::Mutex;
 use std::sync::atomic::AtomicU64;
 use std::time::Duration;
 
-use anyhow::Context;
 use async_channel::Receiver;
 use async_channel::Sender;
-use codex_apply_patch::AffectedPaths;
 use codex_apply_patch::ApplyPatchAction;
 use codex_apply_patch::ApplyPatchFileChange;
+use codex_apply_patch::CODEX_APPLY_PATCH_ARG1;
 use codex_apply_patch::MaybeApplyPatchVerified;
 use codex_apply_patch::maybe_parse_apply_patch_verified;
-use codex_apply_patch::print_summary;
+use codex_execpolicy::ExecvChecker;
 use futures::prelude::*;
 use serde::Serialize;
 use serde_json;
@@ -37,17 +36,30 @@ use crate::client::ModelClient;
 use crate::client_common::Prompt;
 use crate::client_common::ResponseEvent;
 use crate::config::Config;
+use crate::config::LinuxNetworkSandbox;
+use crate::config::ModelPrice;
+use crate::config::ShellEnvironmentPolicy;
+use crate::config::ShellOutputLimits;
 use crate::conversation_history::ConversationHistory;
 use crate::error::CodexErr;
 use crate::error::Result as CodexResult;
 use crate::error::SandboxErr;
+use crate::exec::ExecOutputSink;
 use crate::exec::ExecParams;
 use crate::exec::ExecToolCallOutput;
+use crate::exec::FullOutputDir;
 use crate::exec::SandboxType;
 use crate::exec::process_exec_tool_call;
+use crate::exec_env::create_env;
 use crate::flags::OPENAI_STREAM_MAX_RETRIES;
 use crate::mcp_connection_manager::McpConnectionManager;
 use crate::mcp_connection_manager::try_parse_fully_qualified_tool_name;
+use crate::mcp_resources::LIST_MCP_RESOURCES_TOOL_NAME;
+use crate::mcp_resources::READ_MCP_RESOURCE_TOOL_NAME;
+use crate::mcp_resources::handle_list_mcp_resources;
+use crate::mcp_resources::handle_read_mcp_resource;
+use crate::mcp_server_config::McpToolApproval;
+use crate::mcp_tool_call::effective_mcp_tool_approval;
 use crate::mcp_tool_call::handle_mcp_tool_call;
 use crate::models::ContentItem;
 use crate::models::FunctionCallOutputPayload;
@@ -57,7 +69,9 @@ use crate::models::ResponseInputItem;
 use crate::models::ResponseItem;
 use crate::models::ShellToolCallParams;
 use crate::project_doc::create_full_instructions;
+use crate::protocol::AgentMessageDeltaEvent;
 use crate::protocol::AgentMessageEvent;
+use crate::protocol::AgentReasoningDeltaEvent;
 use crate::protocol::AgentReasoningEvent;
 use crate::protocol::ApplyPatchApprovalRequestEvent;
 use crate::protocol::AskForApproval;
@@ -70,17 +84,32 @@ use crate::protocol::ExecCommandBeginEvent;
 use crate::protocol::ExecCommandEndEvent;
 use crate::protocol::FileChange;
 use crate::protocol::InputItem;
+use crate::protocol::McpToolCallApprovalRequestEvent;
 use crate::protocol::Op;
 use crate::protocol::PatchApplyBeginEvent;
 use crate::protocol::PatchApplyEndEvent;
+use crate::protocol::ResumedMessage;
 use crate::protocol::ReviewDecision;
 use crate::protocol::SandboxPolicy;
 use crate::protocol::SessionConfiguredEvent;
 use crate::protocol::Submission;
+use crate::protocol::TokenCountEvent;
+use crate::protocol::TokenUsage;
 use crate::rollout::RolloutRecorder;
 use crate::safety::SafetyCheck;
 use crate::safety::assess_command_safety;
 use crate::safety::assess_patch_safety;
+use crate::safety::get_platform_sandbox;
+use crate::shell_session::SHELL_SESSION_KILL_TOOL_NAME;
+use crate::shell_session::SHELL_SESSION_READ_TOOL_NAME;
+use crate::shell_session::SHELL_SESSION_START_TOOL_NAME;
+use crate::shell_session::SHELL_SESSION_WRITE_TOOL_NAME;
+use crate::shell_session::ShellSessionKillArgs;
+use crate::shell_session::ShellSessionManager;
+use crate::shell_session::ShellSessionOutput;
+use crate::shell_session::ShellSessionReadArgs;
+use crate::shell_session::ShellSessionWriteArgs;
+use crate::shell_session::output_timeout;
 use crate::user_notification::UserNotification;
 use crate::util::backoff;
 
@@ -182,6 +211,34 @@ pub(crate) struct Session {
     /// Optional rollout recorder for persisting the conversation transcript so
     /// sessions can be replayed or inspected later.
     rollout: Mutex<Option<crate::rollout::RolloutRecorder>>,
+
+    /// Estimated transcript size (in tokens) above which the conversation is
+    /// compacted before starting a new task. `0` disables this.
+    auto_compact_token_limit: usize,
+
+    /// Policy loaded from `Config::exec_policy`, used to auto-approve or
+    /// reject shell commands.
+    exec_policy: Option<ExecvChecker>,
+
+    /// Price of the session's model from `Config::model_prices`, used to
+    /// estimate the cost reported in `TokenCount` events.
+    model_price: Option<ModelPrice>,
+
+    /// How the Linux sandbox blocks network access for commands.
+    linux_network_sandbox: LinuxNetworkSandbox,
+
+    /// Determines the environment of the commands run for the model.
+    shell_environment_policy: ShellEnvironmentPolicy,
+    shell_output_limits: ShellOutputLimits,
+    /// Where the complete output of truncated commands is saved.
+    full_output_dir: FullOutputDir,
+    exec_default_timeout_ms: u64,
+    exec_max_timeout_ms: Option<u64>,
+
+    /// Persistent shell sessions, or `None` if `Config::shell_sessions` is
+    /// off.
+    shell_sessions: Option<ShellSessionManager>,
+
     state: Mutex<State>,
 }
 
@@ -191,17 +248,81 @@ impl Session {
             .map(PathBuf::from)
             .map_or_else(|| self.cwd.clone(), |p| self.cwd.join(p))
     }
+
+    fn assess_command_safety(&self, params: &ExecParams) -> SafetyCheck {
+        let state = self.state.lock().unwrap();
+        assess_command_safety(
+            &params.command,
+            self.approval_policy,
+            &self.sandbox_policy,
+            &state.approved_commands,
+            self.exec_policy.as_ref(),
+            &params.cwd,
+        )
+    }
+
+    /// `get_platform_sandbox()` always picks the seccomp backend on Linux;
+    /// this applies `linux_network_sandbox` to `sandbox_type`.
+    fn configured_sandbox_type(&self, sandbox_type: SandboxType) -> SandboxType {
+        match (sandbox_type, self.linux_network_sandbox) {
+            (SandboxType::LinuxSeccomp, LinuxNetworkSandbox::Namespace) => {
+                SandboxType::LinuxNetworkNamespace
+            }
+            (sandbox_type, _) => sandbox_type,
+        }
+    }
+
+    /// The timeout of a command for which the model requested `timeout_ms`,
+    /// capped at `exec_max_timeout_ms`.
+    fn exec_timeout_ms(&self, timeout_ms: Option<u64>) -> u64 {
+        let timeout_ms = timeout_ms.unwrap_or(self.exec_default_timeout_ms);
+        match self.exec_max_timeout_ms {
+            Some(max_timeout_ms) => timeout_ms.min(max_timeout_ms),
+            None => timeout_ms,
+        }
+    }
+
+    /// Tells the model that a command timed out after `timeout_ms` and how it
+    /// can give the command more time.
+    fn exec_timeout_message(&self, timeout_ms: u64) -> String {
+        match self.exec_max_timeout_ms {
+            Some(max_timeout_ms) if timeout_ms >= max_timeout_ms => format!(
+                "command timed out after {timeout_ms} ms, the maximum timeout allowed. \
+                 Split the work into smaller commands, or run it in the background with its \
+                 output redirected to a file."
+            ),
+            Some(max_timeout_ms) => format!(
+                "command timed out after {timeout_ms} ms. If it needs more time, run it again \
+                 with a larger `timeout` in milliseconds, up to {max_timeout_ms}."
+            ),
+            None => format!(
+                "command timed out after {timeout_ms} ms. If it needs more time, run it again \
+                 with a larger `timeout` in milliseconds."
+            ),
+        }
+    }
 }
 
 /// Mutable state of the agent
 #[derive(Default)]
 struct State {
     approved_commands: HashSet<Vec<String>>,
+    /// (server, tool) pairs the user approved for the rest of the session.
+    approved_mcp_tools: HashSet<(String, String)>,
     current_task: Option<AgentTask>,
     previous_response_id: Option<String>,
     pending_approvals: HashMap<String, oneshot::Sender<ReviewDecision>>,
     pending_input: Vec<ResponseInputItem>,
     zdr_transcript: Option<ConversationHistory>,
+    /// The most recent turns of the conversation when `zdr_transcript` is
+    /// `None` and the server keeps its state, so that compaction can keep the
+    /// most recent turns. See `Session::max_server_side_history_tokens()`.
+    server_side_history: ConversationHistory,
+    /// Input tokens of the latest model request, i.e., how much of the
+    /// context window the conversation took up, if the model reported it.
+    last_input_tokens: Option<u64>,
+    /// Tokens used by all the model responses of this session so far.
+    token_usage: TokenUsage,
 }
 
 impl Session {
@@ -278,6 +399,34 @@ impl Session {
         rx_approve
     }
 
+    pub async fn request_mcp_tool_call_approval(
+        &self,
+        sub_id: String,
+        call_id: String,
+        server: String,
+        tool: String,
+        arguments: Option<serde_json::Value>,
+    ) -> oneshot::Receiver<ReviewDecision> {
+        let (tx_approve, rx_approve) = oneshot::channel();
+        // Register the pending approval before the request goes out, so that
+        // a decision that arrives right away is not dropped.
+        {
+            let mut state = self.state.lock().unwrap();
+            state.pending_approvals.insert(sub_id.clone(), tx_approve);
+        }
+        let event = Event {
+            id: sub_id,
+            msg: EventMsg::McpToolCallApprovalRequest(McpToolCallApprovalRequestEvent {
+                call_id,
+                server,
+                tool,
+                arguments,
+            }),
+        };
+        let _ = self.tx_event.send(event).await;
+        rx_approve
+    }
+
     pub fn notify_approval(&self, sub_id: &str, decision: ReviewDecision) {
         let mut state = self.state.lock().unwrap();
         if let Some(tx_approve) = state.pending_approvals.remove(sub_id) {
@@ -290,6 +439,29 @@ impl Session {
         state.approved_commands.insert(cmd);
     }
 
+    pub fn add_approved_mcp_tool(&self, server: String, tool: String) {
+        let mut state = self.state.lock().unwrap();
+        state.approved_mcp_tools.insert((server, tool));
+    }
+
+    pub fn is_mcp_tool_approved(&self, server: &str, tool: &str) -> bool {
+        let state = self.state.lock().unwrap();
+        state
+            .approved_mcp_tools
+            .contains(&(server.to_string(), tool.to_string()))
+    }
+
+    /// Whether a call to the given MCP tool may run without asking the user.
+    /// Tools without a configured policy require approval unless the
+    /// session's `approval_policy` is `Never`, in which case a tool that is
+    /// configured to `Ask` cannot run at all.
+    pub fn mcp_tool_approval(&self, server: &str, tool: &str) -> McpToolApproval {
+        effective_mcp_tool_approval(
+            self.mcp_connection_manager.tool_approval(server, tool),
+            self.approval_policy,
+        )
+    }
+
     /// Append the given items to the session's rollout transcript (if enabled)
     /// and persist them to disk.
     async fn record_rollout_items(&self, items: &[ResponseItem]) {
@@ -319,6 +491,16 @@ impl Session {
         let _ = self.tx_event.send(event).await;
     }
 
+    /// Sink that forwards the output of the command for `call_id` to the
+    /// client while it runs.
+    fn exec_output_sink(&self, sub_id: &str, call_id: &str) -> ExecOutputSink {
+        ExecOutputSink {
+            sub_id: sub_id.to_string(),
+            call_id: call_id.to_string(),
+            tx_event: self.tx_event.clone(),
+        }
+    }
+
     async fn notify_exec_command_end(
         &self,
         sub_id: &str,
@@ -355,6 +537,49 @@ impl Session {
         let _ = self.tx_event.send(event).await;
     }
 
+    /// Add `usage` to the session totals and report both to the client.
+    async fn record_token_usage(&self, sub_id: &str, usage: TokenUsage) {
+        let session_total = {
+            let mut state = self.state.lock().unwrap();
+            state.token_usage += usage;
+            state.token_usage
+        };
+        let event = Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::TokenCount(TokenCountEvent {
+                last_turn: usage,
+                session_total,
+                estimated_cost_usd: self.model_price.map(|p| p.cost_usd(&session_total)),
+            }),
+        };
+        let _ = self.tx_event.send(event).await;
+    }
+
+    /// Whether the conversation takes up more of the context window than the
+    /// configured limit and should be compacted. Uses the token count the
+    /// model reported for the latest request, or else an estimate.
+    fn should_auto_compact(&self) -> bool {
+        if self.auto_compact_token_limit == 0 {
+            return false;
+        }
+        let state = self.state.lock().unwrap();
+        let tokens = match state.last_input_tokens {
+            Some(tokens) => usize::try_from(tokens).unwrap_or(usize::MAX),
+            None => state.history().estimated_token_count(),
+        };
+        tokens > self.auto_compact_token_limit
+    }
+
+    /// How much of the conversation to keep in `State::server_side_history`:
+    /// twice the auto-compaction limit, so that compaction still sees all of
+    /// it, or about the largest context window if auto-compaction is off.
+    fn max_server_side_history_tokens(&self) -> usize {
+        match self.auto_compact_token_limit {
+            0 => SERVER_SIDE_HISTORY_MAX_TOKENS,
+            limit => limit.saturating_mul(2),
+        }
+    }
+
     /// Returns the input if there was no task running to inject into
     pub fn inject_input(&self, input: Vec<InputItem>) -> Result<(), Vec<InputItem>> {
         let mut state = self.state.lock().unwrap();
@@ -389,6 +614,18 @@ impl Session {
             .await
     }
 
+    pub fn list_mcp_resources(&self) -> HashMap<String, Vec<mcp_types::Resource>> {
+        self.mcp_connection_manager.list_all_resources()
+    }
+
+    pub async fn read_mcp_resource(
+        &self,
+        server: &str,
+        uri: &str,
+    ) -> anyhow::Result<mcp_types::ReadResourceResult> {
+        self.mcp_connection_manager.read_resource(server, uri).await
+    }
+
     pub fn abort(&self) {
         info!("Aborting existing session");
         let mut state = self.state.lock().unwrap();
@@ -439,15 +676,44 @@ impl State {
     pub fn partial_clone(&self, retain_zdr_transcript: bool) -> Self {
         Self {
             approved_commands: self.approved_commands.clone(),
+            approved_mcp_tools: self.approved_mcp_tools.clone(),
             previous_response_id: self.previous_response_id.clone(),
+            token_usage: self.token_usage,
+            last_input_tokens: self.last_input_tokens,
             zdr_transcript: if retain_zdr_transcript {
                 self.zdr_transcript.clone()
             } else {
                 None
             },
+            server_side_history: if retain_zdr_transcript {
+                ConversationHistory::new()
+            } else {
+                self.history().clone()
+            },
             ..Default::default()
         }
     }
+
+    /// The conversation so far, as far as it is known locally.
+    fn history(&self) -> &ConversationHistory {
+        self.zdr_transcript
+            .as_ref()
+            .unwrap_or(&self.server_side_history)
+    }
+
+    /// Record `items` in the transcript or, if the server keeps the state of
+    /// the conversation, in the server-side history, of which only the most
+    /// recent turns within `max_server_side_tokens` are kept.
+    fn record_items(&mut self, items: Vec<ResponseItem>, max_server_side_tokens: usize) {
+        match self.zdr_transcript.as_mut() {
+            Some(transcript) => transcript.record_items(items),
+            None => {
+                self.server_side_history.record_items(items);
+                self.server_side_history
+                    .drop_oldest_turns_over(max_server_side_tokens);
+            }
+        }
+    }
 }
 
 /// A series of Turns in response to user input.
@@ -468,6 +734,16 @@ impl AgentTask {
         }
     }
 
+    fn compact(sess: Arc<Session>, sub_id: String) -> Self {
+        let handle =
+            tokio::spawn(run_compact_task(Arc::clone(&sess), sub_id.clone())).abort_handle();
+        Self {
+            sess,
+            sub_id,
+            handle,
+        }
+    }
+
     fn abort(self) {
         if !self.handle.is_finished() {
             self.handle.abort();
@@ -491,8 +767,9 @@ async fn submission_loop(
     tx_event: Sender<Event>,
     ctrl_c: Arc<Notify>,
 ) {
-    // Generate a unique ID for the lifetime of this Codex session.
-    let session_id = Uuid::new_v4();
+    // Generate a unique ID for the lifetime of this Codex session. When a
+    // previous session is resumed, its ID is reused instead.
+    let mut session_id = Uuid::new_v4();
 
     let mut sess: Option<Arc<Session>> = None;
     // shorthand - send an event when there is no active session
@@ -560,6 +837,37 @@ async fn submission_loop(
 
                 let client = ModelClient::new(model.clone(), provider.clone());
 
+                // Only the very first ConfigureSession can resume a previous
+                // session: later ones carry over the state of the current one.
+                let mut resumed = None;
+                if let (Some(path), None) = (&config.resume_path, &sess) {
+                    match RolloutRecorder::resume(path).await {
+                        Ok((recorder, saved)) => {
+                            match Uuid::parse_str(&saved.session.id) {
+                                Ok(id) => session_id = id,
+                                Err(e) => warn!("invalid session id in rollout: {e}"),
+                            }
+                            resumed = Some((recorder, saved.items));
+                        }
+                        Err(e) => {
+                            let message = format!("failed to resume session from {path:?}: {e}");
+                            error!(message);
+                            let event = Event {
+                                id: sub.id,
+                                msg: EventMsg::Error(ErrorEvent { message }),
+                            };
+                            if let Err(e) = tx_event.send(event).await {
+                                error!("failed to send error message: {e:?}");
+                            }
+                            return;
+                        }
+                    }
+                }
+                let (resumed_recorder, resumed_items) = match resumed {
+                    Some((recorder, items)) => (Some(recorder), Some(items)),
+                    None => (None, None),
+                };
+
                 // abort any current running session and clone its state
                 let retain_zdr_transcript =
                     record_conversation_history(disable_response_storage, provider.wire_api);
@@ -571,11 +879,17 @@ async fn submission_loop(
                             .unwrap()
                             .partial_clone(retain_zdr_transcript)
                     }
+                    // There is no previous_response_id to continue from when
+                    // resuming, so the restored transcript is always resent.
                     None => State {
-                        zdr_transcript: if retain_zdr_transcript {
-                            Some(ConversationHistory::new())
-                        } else {
-                            None
+                        zdr_transcript: match &resumed_items {
+                            Some(items) => {
+                                let mut transcript = ConversationHistory::new();
+                                transcript.record_items(items.iter().cloned());
+                                Some(transcript)
+                            }
+                            None if retain_zdr_transcript => Some(ConversationHistory::new()),
+                            None => None,
                         },
                         ..Default::default()
                     },
@@ -584,20 +898,25 @@ async fn submission_loop(
                 let writable_roots = Mutex::new(get_writable_roots(&cwd));
 
                 // Error messages to dispatch after SessionConfigured is sent.
-                let mut mcp_connection_errors = Vec::<Event>::new();
-                let (mcp_connection_manager, failed_clients) =
-                    match McpConnectionManager::new(config.mcp_servers.clone()).await {
-                        Ok((mgr, failures)) => (mgr, failures),
-                        Err(e) => {
-                            let message = format!("Failed to create MCP connection manager: {e:#}");
-                            error!("{message}");
-                            mcp_connection_errors.push(Event {
-                                id: sub.id.clone(),
-                                msg: EventMsg::Error(ErrorEvent { message }),
-                            });
-                            (McpConnectionManager::default(), Default::default())
-                        }
-                    };
+                let mut configure_errors = Vec::<Event>::new();
+                let (mcp_connection_manager, failed_clients) = match McpConnectionManager::new(
+                    config.mcp_servers.clone(),
+                    tx_event.clone(),
+                    sub.id.clone(),
+                )
+                .await
+                {
+                    Ok((mgr, failures)) => (mgr, failures),
+                    Err(e) => {
+                        let message = format!("Failed to create MCP connection manager: {e:#}");
+                        error!("{message}");
+                        configure_errors.push(Event {
+                            id: sub.id.clone(),
+                            msg: EventMsg::Error(ErrorEvent { message }),
+                        });
+                        (McpConnectionManager::default(), Default::default())
+                    }
+                };
 
                 // Surface individual client start-up failures to the user.
                 if !failed_clients.is_empty() {
@@ -605,26 +924,48 @@ async fn submission_loop(
                         let message =
                             format!("MCP client for `{server_name}` failed to start: {err:#}");
                         error!("{message}");
-                        mcp_connection_errors.push(Event {
+                        configure_errors.push(Event {
                             id: sub.id.clone(),
                             msg: EventMsg::Error(ErrorEvent { message }),
                         });
                     }
                 }
 
+                let exec_policy = match &config.exec_policy {
+                    Some(path) => match crate::safety::load_exec_policy(path) {
+                        Ok(checker) => Some(checker),
+                        Err(e) => {
+                            let message =
+                                format!("Failed to load exec policy from {path:?}: {e:#}");
+                            error!("{message}");
+                            configure_errors.push(Event {
+                                id: sub.id.clone(),
+                                msg: EventMsg::Error(ErrorEvent { message }),
+                            });
+                            None
+                        }
+                    },
+                    None => None,
+                };
+
                 // Attempt to create a RolloutRecorder *before* moving the
                 // `instructions` value into the Session struct.
                 // TODO: if ConfigureSession is sent twice, we will create an
                 // overlapping rollout file. Consider passing RolloutRecorder
                 // from above.
-                let rollout_recorder =
-                    match RolloutRecorder::new(&config, session_id, instructions.clone()).await {
-                        Ok(r) => Some(r),
-                        Err(e) => {
-                            tracing::warn!("failed to initialise rollout recorder: {e}");
-                            None
+                let rollout_recorder = match resumed_recorder {
+                    Some(recorder) => Some(recorder),
+                    None => {
+                        match RolloutRecorder::new(&config, session_id, instructions.clone()).await
+                        {
+                            Ok(r) => Some(r),
+                            Err(e) => {
+                                tracing::warn!("failed to initialise rollout recorder: {e}");
+                                None
+                            }
                         }
-                    };
+                    }
+                };
 
                 sess = Some(Arc::new(Session {
                     client,
@@ -639,6 +980,16 @@ async fn submission_loop(
                     notify,
                     state: Mutex::new(state),
                     rollout: Mutex::new(rollout_recorder),
+                    auto_compact_token_limit: config.auto_compact_token_limit,
+                    exec_policy,
+                    model_price: config.model_prices.get(&model).copied(),
+                    linux_network_sandbox: config.linux_network_sandbox,
+                    shell_environment_policy: config.shell_environment_policy.clone(),
+                    shell_output_limits: config.shell_output_limits,
+                    full_output_dir: FullOutputDir::new(),
+                    exec_default_timeout_ms: config.exec_default_timeout_ms,
+                    exec_max_timeout_ms: config.exec_max_timeout_ms,
+                    shell_sessions: config.shell_sessions.then(ShellSessionManager::default),
                 }));
 
                 // Gather history metadata for SessionConfiguredEvent.
@@ -653,9 +1004,14 @@ async fn submission_loop(
                         model,
                         history_log_id,
                         history_entry_count,
+                        resumed_messages: resumed_items.as_deref().map(resumed_messages),
+                        mcp_prompts: sess
+                            .as_ref()
+                            .map(|sess| sess.mcp_connection_manager.list_all_prompts())
+                            .unwrap_or_default(),
                     }),
                 })
-                .chain(mcp_connection_errors.into_iter());
+                .chain(configure_errors.into_iter());
                 for event in events {
                     if let Err(e) = tx_event.send(event).await {
                         error!("failed to send event: {e:?}");
@@ -708,6 +1064,21 @@ async fn submission_loop(
                     other => sess.notify_approval(&id, other),
                 }
             }
+            Op::McpToolCallApproval { id, decision } => {
+                let sess = match sess.as_ref() {
+                    Some(sess) => sess,
+                    None => {
+                        send_no_session_event(sub.id).await;
+                        continue;
+                    }
+                };
+                match decision {
+                    ReviewDecision::Abort => {
+                        sess.abort();
+                    }
+                    other => sess.notify_approval(&id, other),
+                }
+            }
             Op::AddToHistory { text } => {
                 let id = session_id;
                 let config = config.clone();
@@ -748,6 +1119,64 @@ async fn submission_loop(
                     }
                 });
             }
+            Op::GetMcpPromptRequest {
+                server,
+                name,
+                arguments,
+            } => {
+                let sess = match sess.as_ref() {
+                    Some(sess) => sess.clone(),
+                    None => {
+                        send_no_session_event(sub.id).await;
+                        continue;
+                    }
+                };
+                let sub_id = sub.id.clone();
+
+                tokio::spawn(async move {
+                    let msg = match sess
+                        .mcp_connection_manager
+                        .get_prompt(&server, &name, arguments)
+                        .await
+                    {
+                        Ok(result) => EventMsg::GetMcpPromptResponse(
+                            crate::protocol::GetMcpPromptResponseEvent {
+                                server,
+                                name,
+                                result,
+                            },
+                        ),
+                        Err(e) => EventMsg::Error(ErrorEvent {
+                            message: format!("{e:#}"),
+                        }),
+                    };
+                    sess.send_event(Event { id: sub_id, msg }).await;
+                });
+            }
+            Op::Compact => {
+                let sess = match sess.as_ref() {
+                    Some(sess) => sess,
+                    None => {
+                        send_no_session_event(sub.id).await;
+                        continue;
+                    }
+                };
+
+                if sess.state.lock().unwrap().current_task.is_some() {
+                    let event = Event {
+                        id: sub.id,
+                        msg: EventMsg::Error(ErrorEvent {
+                            message: "Cannot compact the conversation while a task is running"
+                                .to_string(),
+                        }),
+                    };
+                    tx_event.send(event).await.ok();
+                    continue;
+                }
+
+                let task = AgentTask::compact(Arc::clone(sess), sub.id);
+                sess.set_task(task);
+            }
         }
     }
     debug!("Agent loop exited");
@@ -765,6 +1194,19 @@ async fn run_task(sess: Arc<Session>, sub_id: String, input: Vec<InputItem>) {
         return;
     }
 
+    // Compact before the new input is added so that the user's request is
+    // never summarized away. This is only done at the start of a task: later
+    // turns carry function call outputs that must follow their calls.
+    if sess.should_auto_compact() {
+        sess.notify_background_event(&sub_id, "Conversation is getting long; compacting it…")
+            .await;
+        if let Err(e) = compact_conversation(&sess, &sub_id).await {
+            warn!("failed to compact conversation: {e:#}");
+            sess.notify_background_event(&sub_id, format!("Failed to compact conversation: {e}"))
+                .await;
+        }
+    }
+
     let mut pending_response_input: Vec<ResponseInputItem> = vec![ResponseInputItem::from(input)];
     loop {
         let mut net_new_turn_input = pending_response_input
@@ -786,8 +1228,9 @@ async fn run_task(sess: Arc<Session>, sub_id: String, input: Vec<InputItem>) {
         // conversation history on each turn. The rollout file, however, should
         // only record the new items that originated in this turn so that it
         // represents an append-only log without duplicates.
-        let turn_input: Vec<ResponseItem> =
-            if let Some(transcript) = sess.state.lock().unwrap().zdr_transcript.as_mut() {
+        let turn_input: Vec<ResponseItem> = {
+            let mut state = sess.state.lock().unwrap();
+            if let Some(transcript) = state.zdr_transcript.as_mut() {
                 // If we are using Chat/ZDR, we need to send the transcript with every turn.
 
                 // 1. Build up the conversation history for the next turn.
@@ -804,8 +1247,13 @@ async fn run_task(sess: Arc<Session>, sub_id: String, input: Vec<InputItem>) {
             } else {
                 // Responses API path – we can just send the new items and
                 // record the same.
+                state.record_items(
+                    net_new_turn_input.clone(),
+                    sess.max_server_side_history_tokens(),
+                );
                 net_new_turn_input
-            };
+            }
+        };
 
         let turn_input_messages: Vec<String> = turn_input
             .iter()
@@ -837,10 +1285,11 @@ async fn run_task(sess: Arc<Session>, sub_id: String, input: Vec<InputItem>) {
                     // First persist model-generated output to the rollout file – this only borrows.
                     sess.record_rollout_items(&items).await;
 
-                    // For ZDR we also need to keep a transcript clone.
-                    if let Some(transcript) = sess.state.lock().unwrap().zdr_transcript.as_mut() {
-                        transcript.record_items(items);
-                    }
+                    // Keep a copy for the transcript (ZDR) or for compaction.
+                    sess.state
+                        .lock()
+                        .unwrap()
+                        .record_items(items, sess.max_server_side_history_tokens());
                 }
 
                 if responses.is_empty() {
@@ -876,6 +1325,142 @@ async fn run_task(sess: Arc<Session>, sub_id: String, input: Vec<InputItem>) {
     sess.tx_event.send(event).await.ok();
 }
 
+/// Instructions sent to the model when the conversation is compacted. Mirrors
+/// the `/compact` command of the TypeScript CLI.
+const COMPACT_PROMPT: &str = "You are about to run out of context. Summarize the conversation so far \
+so that the work can continue from the summary alone. Include the user's goals and requests, \
+key decisions, the files and commands involved, what has been done and what remains. \
+Do not call any tools; respond with the summary only.";
+
+/// Number of most recent turns that compaction keeps verbatim after the
+/// summary of the earlier ones.
+const COMPACT_KEEP_RECENT_TURNS: usize = 2;
+
+/// Size, in estimated tokens, up to which the conversation is kept for manual
+/// compaction when the server keeps its state and auto-compaction is off.
+/// This is already more than the context window of most models.
+const SERVER_SIDE_HISTORY_MAX_TOKENS: usize = 200_000;
+
+/// Prefix of the message that replaces the older turns after compaction.
+const COMPACT_SUMMARY_PREFIX: &str =
+    "The earlier conversation was compacted to save context. Summary of it so far:";
+
+async fn run_compact_task(sess: Arc<Session>, sub_id: String) {
+    let event = Event {
+        id: sub_id.clone(),
+        msg: EventMsg::TaskStarted,
+    };
+    if sess.tx_event.send(event).await.is_err() {
+        return;
+    }
+
+    match compact_conversation(&sess, &sub_id).await {
+        Ok(true) => {
+            sess.notify_background_event(&sub_id, "Conversation history compacted.")
+                .await;
+        }
+        Ok(false) => {
+            sess.notify_background_event(&sub_id, "Nothing to compact.")
+                .await;
+        }
+        Err(e) => {
+            let event = Event {
+                id: sub_id.clone(),
+                msg: EventMsg::Error(ErrorEvent {
+                    message: format!("Failed to compact conversation: {e}"),
+                }),
+            };
+            sess.tx_event.send(event).await.ok();
+        }
+    }
+
+    sess.remove_task(&sub_id);
+    let event = Event {
+        id: sub_id,
+        msg: EventMsg::TaskComplete,
+    };
+    sess.tx_event.send(event).await.ok();
+}
+
+/// Ask the model to summarize the conversation except for its last
+/// [`COMPACT_KEEP_RECENT_TURNS`] turns and replace the transcript with a
+/// message containing that summary followed by those turns. Returns `false`
+/// if there was nothing to compact.
+async fn compact_conversation(sess: &Session, sub_id: &str) -> CodexResult<bool> {
+    let (mut input, recent_items) = sess
+        .state
+        .lock()
+        .unwrap()
+        .history()
+        .split_recent_turns(COMPACT_KEEP_RECENT_TURNS);
+    if input.is_empty() {
+        return Ok(false);
+    }
+    input.push(ResponseItem::user_text(COMPACT_PROMPT));
+
+    let prompt = Prompt {
+        input,
+        store: false,
+        prev_id: None,
+        instructions: sess.instructions.clone(),
+        extra_tools: HashMap::new(),
+        include_mcp_resource_tools: false,
+        include_shell_session_tools: false,
+    };
+
+    let mut stream = sess.client.clone().stream(&prompt).await?;
+    let mut summary = String::new();
+    while let Some(event) = stream.next().await {
+        match event? {
+            ResponseEvent::OutputItemDone(ResponseItem::Message { role, content })
+                if role == "assistant" =>
+            {
+                for item in content {
+                    if let ContentItem::OutputText { text } = item {
+                        summary.push_str(&text);
+                    }
+                }
+            }
+            // The model was asked not to call tools; ignore anything else.
+            // The summary is not shown to the user, so the deltas are dropped.
+            ResponseEvent::OutputItemDone(_)
+            | ResponseEvent::OutputTextDelta(_)
+            | ResponseEvent::ReasoningSummaryDelta(_) => {}
+            ResponseEvent::Completed { token_usage, .. } => {
+                if let Some(usage) = token_usage {
+                    sess.record_token_usage(sub_id, usage).await;
+                }
+                break;
+            }
+        }
+    }
+
+    if summary.trim().is_empty() {
+        warn!("model returned an empty summary; leaving the conversation as is");
+        return Ok(false);
+    }
+
+    let summary_item = ResponseItem::Message {
+        role: "user".to_string(),
+        content: vec![ContentItem::InputText {
+            text: format!("{COMPACT_SUMMARY_PREFIX}\n\n{summary}"),
+        }],
+    };
+
+    // From here on the (compacted) transcript is resent with every turn, so
+    // any server-side conversation state is no longer needed. The token count
+    // of the latest request no longer applies either.
+    let mut state = sess.state.lock().unwrap();
+    state.previous_response_id = None;
+    state.last_input_tokens = None;
+    state.server_side_history = ConversationHistory::new();
+    state
+        .zdr_transcript
+        .get_or_insert_with(ConversationHistory::new)
+        .replace([vec![summary_item], recent_items].concat());
+    Ok(true)
+}
+
 async fn run_turn(
     sess: &Session,
     sub_id: String,
@@ -903,12 +1488,15 @@ async fn run_turn(
     };
 
     let extra_tools = sess.mcp_connection_manager.list_all_tools();
+    let include_mcp_resource_tools = sess.mcp_connection_manager.has_resources();
     let prompt = Prompt {
         input,
         prev_id,
         instructions,
         store,
         extra_tools,
+        include_mcp_resource_tools,
+        include_shell_session_tools: sess.shell_sessions.is_some(),
     };
 
     let mut retries = 0;
@@ -963,27 +1551,50 @@ async fn try_run_turn(
 ) -> CodexResult<Vec<ProcessedResponseItem>> {
     let mut stream = sess.client.clone().stream(prompt).await?;
 
-    // Buffer all the incoming messages from the stream first, then execute them.
+    // Buffer all the incoming items from the stream first, then execute them.
     // If we execute a function call in the middle of handling the stream, it can time out.
+    // Text deltas are cheap to handle, so they are forwarded to the client as
+    // they arrive.
     let mut input = Vec::new();
     while let Some(event) = stream.next().await {
-        input.push(event?);
-    }
-
-    let mut output = Vec::new();
-    for event in input {
-        match event {
-            ResponseEvent::OutputItemDone(item) => {
-                let response = handle_response_item(sess, sub_id, item.clone()).await?;
-                output.push(ProcessedResponseItem { item, response });
+        match event? {
+            ResponseEvent::OutputItemDone(item) => input.push(item),
+            ResponseEvent::OutputTextDelta(delta) => {
+                let event = Event {
+                    id: sub_id.to_string(),
+                    msg: EventMsg::AgentMessageDelta(AgentMessageDeltaEvent { delta }),
+                };
+                sess.tx_event.send(event).await.ok();
             }
-            ResponseEvent::Completed { response_id } => {
-                let mut state = sess.state.lock().unwrap();
-                state.previous_response_id = Some(response_id);
+            ResponseEvent::ReasoningSummaryDelta(delta) => {
+                let event = Event {
+                    id: sub_id.to_string(),
+                    msg: EventMsg::AgentReasoningDelta(AgentReasoningDeltaEvent { delta }),
+                };
+                sess.tx_event.send(event).await.ok();
+            }
+            ResponseEvent::Completed {
+                response_id,
+                token_usage,
+            } => {
+                {
+                    let mut state = sess.state.lock().unwrap();
+                    state.previous_response_id = Some(response_id);
+                    state.last_input_tokens = token_usage.map(|usage| usage.input_tokens);
+                }
+                if let Some(usage) = token_usage {
+                    sess.record_token_usage(sub_id, usage).await;
+                }
                 break;
             }
         }
     }
+
+    let mut output = Vec::new();
+    for item in input {
+        let response = handle_response_item(sess, sub_id, item.clone()).await?;
+        output.push(ProcessedResponseItem { item, response });
+    }
     Ok(output)
 }
 
@@ -1092,11 +1703,20 @@ async fn handle_function_call(
             };
             handle_container_exec_with_params(params, sess, sub_id, call_id).await
         }
+        LIST_MCP_RESOURCES_TOOL_NAME => handle_list_mcp_resources(sess, call_id, arguments),
+        READ_MCP_RESOURCE_TOOL_NAME => handle_read_mcp_resource(sess, call_id, arguments).await,
+        SHELL_SESSION_START_TOOL_NAME
+        | SHELL_SESSION_WRITE_TOOL_NAME
+        | SHELL_SESSION_READ_TOOL_NAME
+        | SHELL_SESSION_KILL_TOOL_NAME
+            if sess.shell_sessions.is_some() =>
+        {
+            handle_shell_session_call(sess, &sub_id, call_id, &name, arguments).await
+        }
         _ => {
             match try_parse_fully_qualified_tool_name(&name) {
                 Some((server, tool_name)) => {
-                    // TODO(mbolin): Determine appropriate timeout for tool call.
-                    let timeout = None;
+                    let timeout = sess.mcp_connection_manager.tool_timeout(&server);
                     handle_mcp_tool_call(
                         sess, &sub_id, call_id, server, tool_name, arguments, timeout,
                     )
@@ -1121,7 +1741,9 @@ fn to_exec_params(params: ShellToolCallParams, sess: &Session) -> ExecParams {
     ExecParams {
         command: params.command,
         cwd: sess.resolve_path(params.workdir.clone()),
-        timeout_ms: params.timeout_ms,
+        timeout_ms: Some(sess.exec_timeout_ms(params.timeout_ms)),
+        env: create_env(&sess.shell_environment_policy),
+        stdin: None,
     }
 }
 
@@ -1176,41 +1798,17 @@ async fn handle_container_exec_with_params(
         MaybeApplyPatchVerified::NotApplyPatch => (),
     }
 
-    // safety checks
-    let safety = {
-        let state = sess.state.lock().unwrap();
-        assess_command_safety(
-            &params.command,
-            sess.approval_policy,
-            &sess.sandbox_policy,
-            &state.approved_commands,
-        )
-    };
-    let sandbox_type = match safety {
+    let sandbox_type = match sess.assess_command_safety(&params) {
         SafetyCheck::AutoApprove { sandbox_type } => sandbox_type,
         SafetyCheck::AskUser => {
-            let rx_approve = sess
-                .request_command_approval(
-                    sub_id.clone(),
-                    params.command.clone(),
-                    params.cwd.clone(),
-                    None,
-                )
-                .await;
-            match rx_approve.await.unwrap_or_default() {
-                ReviewDecision::Approved => (),
-                ReviewDecision::ApprovedForSession => {
-                    sess.add_approved_command(params.command.clone());
-                }
-                ReviewDecision::Denied | ReviewDecision::Abort => {
-                    return ResponseInputItem::FunctionCallOutput {
-                        call_id,
-                        output: crate::models::FunctionCallOutputPayload {
-                            content: "exec command rejected by user".to_string(),
-                            success: None,
-                        },
-                    };
-                }
+            if !request_approval_to_run(sess, &sub_id, &params).await {
+                return ResponseInputItem::FunctionCallOutput {
+                    call_id,
+                    output: crate::models::FunctionCallOutputPayload {
+                        content: "exec command rejected by user".to_string(),
+                        success: None,
+                    },
+                };
             }
             // No sandboxing is applied because the user has given
             // explicit approval. Often, we end up in this case because
@@ -1228,6 +1826,7 @@ async fn handle_container_exec_with_params(
             };
         }
     };
+    let sandbox_type = sess.configured_sandbox_type(sandbox_type);
 
     sess.notify_exec_command_begin(&sub_id, &call_id, &params)
         .await;
@@ -1237,27 +1836,25 @@ async fn handle_container_exec_with_params(
         sandbox_type,
         sess.ctrl_c.clone(),
         &sess.sandbox_policy,
+        sess.shell_output_limits,
+        &sess.full_output_dir,
+        Some(sess.exec_output_sink(&sub_id, &call_id)),
     )
     .await;
 
     match output_result {
         Ok(output) => {
-            let ExecToolCallOutput {
-                exit_code,
-                stdout,
-                stderr,
-                duration,
-            } = output;
-
-            sess.notify_exec_command_end(&sub_id, &call_id, &stdout, &stderr, exit_code)
-                .await;
+            sess.notify_exec_command_end(
+                &sub_id,
+                &call_id,
+                &output.stdout,
+                &output.stderr,
+                output.exit_code,
+            )
+            .await;
 
-            let is_success = exit_code == 0;
-            let content = format_exec_output(
-                if is_success { &stdout } else { &stderr },
-                exit_code,
-                duration,
-            );
+            let is_success = output.exit_code == 0;
+            let content = format_exec_output(&output);
 
             ResponseInputItem::FunctionCallOutput {
                 call_id,
@@ -1267,6 +1864,30 @@ async fn handle_container_exec_with_params(
                 },
             }
         }
+        Err(CodexErr::Sandbox(SandboxErr::Timeout { timeout_ms, output })) => {
+            // Running the command again without the sandbox would not help.
+            let message = sess.exec_timeout_message(timeout_ms);
+            let mut stderr = output.stderr.clone();
+            if !stderr.is_empty() && !stderr.ends_with('\n') {
+                stderr.push('\n');
+            }
+            stderr.push_str(&message);
+            sess.notify_exec_command_end(&sub_id, &call_id, &output.stdout, &stderr, -1)
+                .await;
+            // Report what the command printed before it was stopped, and
+            // where its complete output was saved, like for any command.
+            let content = format!(
+                "{message}\nOutput until the command was stopped: {}",
+                format_exec_output(&output)
+            );
+            ResponseInputItem::FunctionCallOutput {
+                call_id,
+                output: FunctionCallOutputPayload {
+                    content,
+                    success: Some(false),
+                },
+            }
+        }
         Err(CodexErr::Sandbox(error)) => {
             handle_sanbox_error(error, sandbox_type, params, sess, sub_id, call_id).await
         }
@@ -1283,6 +1904,230 @@ async fn handle_container_exec_with_params(
     }
 }
 
+/// Asks the user whether the command of `params` may run outside the sandbox.
+/// Returns whether they approved it.
+async fn request_approval_to_run(sess: &Session, sub_id: &str, params: &ExecParams) -> bool {
+    let rx_approve = sess
+        .request_command_approval(
+            sub_id.to_string(),
+            params.command.clone(),
+            params.cwd.clone(),
+            None,
+        )
+        .await;
+    match rx_approve.await.unwrap_or_default() {
+        ReviewDecision::Approved => true,
+        ReviewDecision::ApprovedForSession => {
+            sess.add_approved_command(params.command.clone());
+            true
+        }
+        ReviewDecision::Denied | ReviewDecision::Abort => false,
+    }
+}
+
+async fn handle_shell_session_call(
+    sess: &Session,
+    sub_id: &str,
+    call_id: String,
+    name: &str,
+    arguments: String,
+) -> ResponseInputItem {
+    let result = match name {
+        SHELL_SESSION_START_TOOL_NAME => start_shell_session(sess, sub_id, arguments).await,
+        SHELL_SESSION_WRITE_TOOL_NAME => {
+            write_shell_session(sess, sub_id, &call_id, arguments).await
+        }
+        SHELL_SESSION_READ_TOOL_NAME => read_shell_session(sess, arguments).await,
+        _ => kill_shell_session(sess, sub_id, arguments).await,
+    };
+    let output = match result {
+        Ok(output) => FunctionCallOutputPayload {
+            content: serde_json::to_string(&output)
+                .unwrap_or_else(|e| format!("JSON serialization error: {e}")),
+            success: Some(true),
+        },
+        Err(content) => FunctionCallOutputPayload {
+            content,
+            success: None,
+        },
+    };
+    ResponseInputItem::FunctionCallOutput { call_id, output }
+}
+
+fn shell_session_manager(sess: &Session) -> Result<&ShellSessionManager, String> {
+    sess.shell_sessions
+        .as_ref()
+        .ok_or_else(|| "shell sessions are disabled".to_string())
+}
+
+fn parse_shell_session_arguments<T: for<'de> serde::Deserialize<'de>>(
+    arguments: &str,
+) -> Result<T, String> {
+    serde_json::from_str(arguments).map_err(|e| format!("failed to parse function arguments: {e}"))
+}
+
+async fn start_shell_session(
+    sess: &Session,
+    sub_id: &str,
+    arguments: String,
+) -> Result<ShellSessionOutput, String> {
+    let shell_sessions = shell_session_manager(sess)?;
+    let params: ShellToolCallParams = parse_shell_session_arguments(&arguments)?;
+    // `to_exec_params()` fills in the command timeout, which does not apply
+    // to collecting the first output of a session.
+    let timeout = output_timeout(params.timeout_ms, sess.exec_max_timeout_ms);
+    let params = to_exec_params(params, sess);
+
+    let sandbox_type = match sess.assess_command_safety(&params) {
+        // Unlike a `shell` call, a session goes on to run whatever the model
+        // writes to it, so known-safe commands still get the sandbox. Commands
+        // the user approved for the session do not.
+        SafetyCheck::AutoApprove {
+            sandbox_type: SandboxType::None,
+        } if !sess.sandbox_policy.is_unrestricted()
+            && !sess
+                .state
+                .lock()
+                .unwrap()
+                .approved_commands
+                .contains(&params.command) =>
+        {
+            get_platform_sandbox().unwrap_or(SandboxType::None)
+        }
+        SafetyCheck::AutoApprove { sandbox_type } => sandbox_type,
+        SafetyCheck::AskUser => {
+            if !request_approval_to_run(sess, sub_id, &params).await {
+                return Err("exec command rejected by user".to_string());
+            }
+            SandboxType::None
+        }
+        SafetyCheck::Reject { reason } => return Err(format!("exec command rejected: {reason}")),
+    };
+    let sandbox_type = sess.configured_sandbox_type(sandbox_type);
+    let input_needs_approval =
+        sandbox_type == SandboxType::None && !sess.sandbox_policy.is_unrestricted();
+
+    let session = shell_sessions
+        .start(
+            params,
+            sandbox_type,
+            &sess.sandbox_policy,
+            input_needs_approval,
+        )
+        .map_err(|e| format!("failed to start shell session: {e}"))?;
+    sess.notify_background_event(
+        sub_id,
+        format!(
+            "Started shell session {}: {}",
+            session.id(),
+            session.command().join(" ")
+        ),
+    )
+    .await;
+    Ok(session.read(timeout).await)
+}
+
+/// Write the input to a shell session and read back its output. The input
+/// and output are reported like a `shell` call, with `ExecCommandBegin` and
+/// `ExecCommandEnd` events, so that the user sees what runs in the session.
+async fn write_shell_session(
+    sess: &Session,
+    sub_id: &str,
+    call_id: &str,
+    arguments: String,
+) -> Result<ShellSessionOutput, String> {
+    let shell_sessions = shell_session_manager(sess)?;
+    let args: ShellSessionWriteArgs = parse_shell_session_arguments(&arguments)?;
+    let session = shell_sessions
+        .get(args.session_id)
+        .ok_or_else(|| format!("unknown shell session: {}", args.session_id))?;
+
+    if session.input_needs_approval() {
+        if sess.approval_policy == AskForApproval::Never {
+            return Err("input rejected: the shell session runs outside the sandbox".to_string());
+        }
+        let rx_approve = sess
+            .request_command_approval(
+                sub_id.to_string(),
+                session.command().to_vec(),
+                session.cwd().to_path_buf(),
+                Some(format!(
+                    "send {:?} to shell session {}?",
+                    args.input,
+                    session.id()
+                )),
+            )
+            .await;
+        match rx_approve.await.unwrap_or_default() {
+            ReviewDecision::Approved => {}
+            ReviewDecision::ApprovedForSession => session.approve_input(),
+            ReviewDecision::Denied | ReviewDecision::Abort => {
+                return Err("input rejected by user".to_string());
+            }
+        }
+    }
+
+    let begin = Event {
+        id: sub_id.to_string(),
+        msg: EventMsg::ExecCommandBegin(ExecCommandBeginEvent {
+            call_id: call_id.to_string(),
+            command: vec![args.input.trim_end_matches('\n').to_string()],
+            cwd: session.cwd().to_path_buf(),
+        }),
+    };
+    let _ = sess.tx_event.send(begin).await;
+    if let Err(e) = session.write(&args.input).await {
+        let message = format!("failed to write to shell session {}: {e}", session.id());
+        sess.notify_exec_command_end(sub_id, call_id, "", &message, -1)
+            .await;
+        return Err(message);
+    }
+    let output = session
+        .read(output_timeout(args.timeout_ms, sess.exec_max_timeout_ms))
+        .await;
+    // There is no exit code unless the input ended the session, so a session
+    // that is still running is reported as successful.
+    sess.notify_exec_command_end(
+        sub_id,
+        call_id,
+        &output.output,
+        "",
+        output.exit_code.unwrap_or(0),
+    )
+    .await;
+    Ok(output)
+}
+
+async fn read_shell_session(
+    sess: &Session,
+    arguments: String,
+) -> Result<ShellSessionOutput, String> {
+    let shell_sessions = shell_session_manager(sess)?;
+    let args: ShellSessionReadArgs = parse_shell_session_arguments(&arguments)?;
+    let session = shell_sessions
+        .get(args.session_id)
+        .ok_or_else(|| format!("unknown shell session: {}", args.session_id))?;
+    Ok(session
+        .read(output_timeout(args.timeout_ms, sess.exec_max_timeout_ms))
+        .await)
+}
+
+async fn kill_shell_session(
+    sess: &Session,
+    sub_id: &str,
+    arguments: String,
+) -> Result<ShellSessionOutput, String> {
+    let shell_sessions = shell_session_manager(sess)?;
+    let args: ShellSessionKillArgs = parse_shell_session_arguments(&arguments)?;
+    let output = shell_sessions
+        .kill(args.session_id)
+        .await
+        .ok_or_else(|| format!("unknown shell session: {}", args.session_id))?;
+    sess.notify_background_event(sub_id, format!("Killed shell session {}", args.session_id))
+        .await;
+    Ok(output)
+}
+
 async fn handle_sanbox_error(
     error: SandboxErr,
     sandbox_type: SandboxType,
@@ -1293,13 +2138,22 @@ async fn handle_sanbox_error(
 ) -> ResponseInputItem {
     // Early out if the user never wants to be asked for approval; just return to the model immediately
     if sess.approval_policy == AskForApproval::Never {
+        let content = match &error {
+            // Report the output like that of any other command, including
+            // where its complete output was saved.
+            SandboxErr::Denied(output) => format!(
+                "failed in sandbox {sandbox_type:?}: {}",
+                format_exec_output(output)
+            ),
+            _ => format!(
+                "failed in sandbox {:?} with execution error: {error}",
+                sandbox_type
+            ),
+        };
         return ResponseInputItem::FunctionCallOutput {
             call_id,
             output: FunctionCallOutputPayload {
-                content: format!(
-                    "failed in sandbox {:?} with execution error: {error}",
-                    sandbox_type
-                ),
+                content,
                 success: Some(false),
             },
         };
@@ -1341,33 +2195,25 @@ async fn handle_sanbox_error(
                 SandboxType::None,
                 sess.ctrl_c.clone(),
                 &sess.sandbox_policy,
+                sess.shell_output_limits,
+                &sess.full_output_dir,
+                Some(sess.exec_output_sink(&sub_id, &retry_call_id)),
             )
             .await;
 
             match retry_output_result {
                 Ok(retry_output) => {
-                    let ExecToolCallOutput {
-                        exit_code,
-                        stdout,
-                        stderr,
-                        duration,
-                    } = retry_output;
-
                     sess.notify_exec_command_end(
                         &sub_id,
                         &retry_call_id,
-                        &stdout,
-                        &stderr,
-                        exit_code,
+                        &retry_output.stdout,
+                        &retry_output.stderr,
+                        retry_output.exit_code,
                     )
                     .await;
 
-                    let is_success = exit_code == 0;
-                    let content = format_exec_output(
-                        if is_success { &stdout } else { &stderr },
-                        exit_code,
-                        duration,
-                    );
+                    let is_success = retry_output.exit_code == 0;
+                    let content = format_exec_output(&retry_output);
 
                     ResponseInputItem::FunctionCallOutput {
                         call_id,
@@ -1494,114 +2340,143 @@ async fn apply_patch(
         })
         .await;
 
-    let mut stdout = Vec::new();
-    let mut stderr = Vec::new();
-    // Enforce writable roots. If a write is blocked, collect offending root
-    // and prompt the user to extend permissions.
-    let mut result = apply_changes_from_apply_patch_and_report(&action, &mut stdout, &mut stderr);
-
-    if let Err(err) = &result {
-        if err.kind() == std::io::ErrorKind::PermissionDenied {
-            // Determine first offending path.
-            let offending_opt = action
-                .changes()
-                .iter()
-                .flat_map(|(path, change)| match change {
-                    ApplyPatchFileChange::Add { .. } => vec![path.as_ref()],
-                    ApplyPatchFileChange::Delete => vec![path.as_ref()],
-                    ApplyPatchFileChange::Update {
-                        move_path: Some(move_path),
-                        ..
-                    } => {
-                        vec![path.as_ref(), move_path.as_ref()]
-                    }
-                    ApplyPatchFileChange::Update {
-                        move_path: None, ..
-                    } => vec![path.as_ref()],
-                })
-                .find_map(|path: &Path| {
-                    // ApplyPatchAction promises to guarantee absolute paths.
-                    if !path.is_absolute() {
-                        panic!("apply_patch invariant failed: path is not absolute: {path:?}");
-                    }
-
-                    let writable = {
-                        let roots = sess.writable_roots.lock().unwrap();
-                        roots.iter().any(|root| path.starts_with(root))
-                    };
-                    if writable {
-                        None
-                    } else {
-                        Some(path.to_path_buf())
-                    }
-                });
-
-            if let Some(offending) = offending_opt {
-                let root = offending.parent().unwrap_or(&offending).to_path_buf();
-
-                let reason = Some(format!(
-                    "grant write access to {} for this session",
-                    root.display()
-                ));
-                let rx = sess
-                    .request_patch_approval(
-                        sub_id.clone(),
-                        &action,
-                        reason.clone(),
-                        Some(root.clone()),
-                    )
-                    .await;
-                if matches!(
-                    rx.await.unwrap_or_default(),
-                    ReviewDecision::Approved | ReviewDecision::ApprovedForSession
-                ) {
-                    // Extend writable roots.
-                    sess.writable_roots.lock().unwrap().push(root);
-                    stdout.clear();
-                    stderr.clear();
-                    result = apply_changes_from_apply_patch_and_report(
-                        &action,
-                        &mut stdout,
-                        &mut stderr,
-                    );
-                }
+    let mut result =
+        exec_apply_patch(sess, &action, patch_sandbox_type(&sess.sandbox_policy)).await;
+
+    // The paths of the patch were checked against the writable roots, so if
+    // the sandbox blocked it anyway, one of them most likely leads outside of
+    // them through a symlink. As above, let the user grant write access to
+    // the directory it actually resolves to and retry under the sandbox.
+    let denied_by_sandbox = matches!(result, Err(CodexErr::Sandbox(SandboxErr::Denied(..))));
+    if denied_by_sandbox && sess.approval_policy != AskForApproval::Never {
+        let writable_roots = sess.writable_roots.lock().unwrap().clone();
+        let sandbox_policy = sess.sandbox_policy.with_writable_roots(&writable_roots);
+        if let Some(root) = first_root_outside_sandbox(&action, &sandbox_policy, &sess.cwd) {
+            let reason = Some(format!(
+                "grant write access to {} for this session",
+                root.display()
+            ));
+            let rx = sess
+                .request_patch_approval(sub_id.clone(), &action, reason, Some(root.clone()))
+                .await;
+            if matches!(
+                rx.await.unwrap_or_default(),
+                ReviewDecision::Approved | ReviewDecision::ApprovedForSession
+            ) {
+                sess.writable_roots.lock().unwrap().push(root);
+                result =
+                    exec_apply_patch(sess, &action, patch_sandbox_type(&sess.sandbox_policy)).await;
             }
         }
     }
 
+    let (stdout, stderr, success) = match result {
+        Ok(output) => (output.stdout, output.stderr, output.exit_code == 0),
+        Err(CodexErr::Sandbox(SandboxErr::Denied(output))) => (output.stdout, output.stderr, false),
+        Err(e) => (String::new(), format!("failed to apply patch: {e}"), false),
+    };
+
     // Emit PatchApplyEnd event.
-    let success_flag = result.is_ok();
     let _ = sess
         .tx_event
         .send(Event {
             id: sub_id.clone(),
             msg: EventMsg::PatchApplyEnd(PatchApplyEndEvent {
                 call_id: call_id.clone(),
-                stdout: String::from_utf8_lossy(&stdout).to_string(),
-                stderr: String::from_utf8_lossy(&stderr).to_string(),
-                success: success_flag,
+                stdout: stdout.clone(),
+                stderr: stderr.clone(),
+                success,
             }),
         })
         .await;
 
-    match result {
-        Ok(_) => ResponseInputItem::FunctionCallOutput {
+    if success {
+        ResponseInputItem::FunctionCallOutput {
             call_id,
             output: FunctionCallOutputPayload {
-                content: String::from_utf8_lossy(&stdout).to_string(),
+                content: stdout,
                 success: None,
             },
-        },
-        Err(e) => ResponseInputItem::FunctionCallOutput {
+        }
+    } else {
+        ResponseInputItem::FunctionCallOutput {
             call_id,
             output: FunctionCallOutputPayload {
-                content: format!("error: {e:#}, stderr: {}", String::from_utf8_lossy(&stderr)),
+                content: format!("error: patch failed to apply, stderr: {stderr}"),
                 success: Some(false),
             },
-        },
+        }
+    }
+}
+
+/// Patches are applied under the same sandbox as shell commands unless the
+/// sandbox policy allows writing anywhere anyway.
+fn patch_sandbox_type(sandbox_policy: &SandboxPolicy) -> SandboxType {
+    if sandbox_policy.has_full_disk_write_access() {
+        SandboxType::None
+    } else {
+        get_platform_sandbox().unwrap_or(SandboxType::None)
     }
 }
 
+/// Time limit for applying a patch. It is independent of the timeouts
+/// configured for shell commands, which may be set much lower or higher than
+/// what writing a few files takes.
+const APPLY_PATCH_TIMEOUT_MS: u64 = 30_000;
+
+/// The sandbox policy for the `apply_patch` helper at `helper_exe`: in
+/// addition to what `sandbox_policy` allows, the helper may write to
+/// `writable_roots` and read its own executable, which may be installed
+/// outside of the readable roots (e.g. in `~/.cargo/bin`).
+pub(crate) fn apply_patch_helper_sandbox_policy(
+    sandbox_policy: &SandboxPolicy,
+    writable_roots: &[PathBuf],
+    helper_exe: &Path,
+) -> SandboxPolicy {
+    let helper_exe = helper_exe
+        .canonicalize()
+        .unwrap_or_else(|_| helper_exe.to_path_buf());
+    sandbox_policy
+        .with_writable_roots(writable_roots)
+        .with_readable_roots(&[helper_exe])
+}
+
+/// Apply the patch by re-executing the current binary as an `apply_patch`
+/// helper (see [`codex_apply_patch::maybe_run_apply_patch_helper`]) under
+/// `sandbox_type`. In addition to what the session's sandbox policy allows,
+/// the helper may write to the writable roots, which include those the user
+/// granted during the session.
+async fn exec_apply_patch(
+    sess: &Session,
+    action: &ApplyPatchAction,
+    sandbox_type: SandboxType,
+) -> CodexResult<ExecToolCallOutput> {
+    let codex_exe = std::env::current_exe()?;
+    let params = ExecParams {
+        command: vec![
+            codex_exe.to_string_lossy().into_owned(),
+            CODEX_APPLY_PATCH_ARG1.to_string(),
+        ],
+        cwd: action.cwd().to_path_buf(),
+        timeout_ms: Some(APPLY_PATCH_TIMEOUT_MS),
+        env: create_env(&sess.shell_environment_policy),
+        stdin: Some(action.patch().to_string()),
+    };
+    let writable_roots = sess.writable_roots.lock().unwrap().clone();
+    let sandbox_policy =
+        apply_patch_helper_sandbox_policy(&sess.sandbox_policy, &writable_roots, &codex_exe);
+    process_exec_tool_call(
+        params,
+        sandbox_type,
+        sess.ctrl_c.clone(),
+        &sandbox_policy,
+        sess.shell_output_limits,
+        &sess.full_output_dir,
+        None,
+    )
+    .await
+}
+
 /// Return the first path in `hunks` that is NOT under any of the
 /// `writable_roots` (after normalising). If all paths are acceptable,
 /// returns None.
@@ -1644,6 +2519,57 @@ fn first_offending_path(
     None
 }
 
+/// Return the directory containing the first path changed by `action` that,
+/// once symlinks are resolved, is not under any writable root of
+/// `sandbox_policy`. If all paths are acceptable, returns None.
+fn first_root_outside_sandbox(
+    action: &ApplyPatchAction,
+    sandbox_policy: &SandboxPolicy,
+    cwd: &Path,
+) -> Option<PathBuf> {
+    let writable_roots: Vec<PathBuf> = sandbox_policy
+        .get_writable_roots_with_cwd(cwd)
+        .iter()
+        .map(|root| resolve_symlinks(&cwd.join(root), MAX_SYMLINKS))
+        .collect();
+    action
+        .changes()
+        .iter()
+        .flat_map(|(path, change)| match change {
+            ApplyPatchFileChange::Update {
+                move_path: Some(move_path),
+                ..
+            } => vec![path, move_path],
+            _ => vec![path],
+        })
+        .map(|path| resolve_symlinks(&cwd.join(path), MAX_SYMLINKS))
+        .find(|path| !writable_roots.iter().any(|root| path.starts_with(root)))
+        .map(|path| path.parent().map(Path::to_path_buf).unwrap_or(path))
+}
+
+/// Maximum number of symlinks followed by [`resolve_symlinks`], as with
+/// `ELOOP` on Linux.
+const MAX_SYMLINKS: usize = 40;
+
+/// Resolve the symlinks in the absolute `path`, which need not exist. Unlike
+/// [`Path::canonicalize`], this also follows symlinks to files that do not
+/// exist yet, which a patch adding such a file would write through.
+fn resolve_symlinks(path: &Path, max_symlinks: usize) -> PathBuf {
+    for ancestor in path.ancestors() {
+        let rest = path.strip_prefix(ancestor).unwrap_or(Path::new(""));
+        if let Ok(resolved) = ancestor.canonicalize() {
+            return resolved.join(rest);
+        }
+        if max_symlinks == 0 {
+            continue;
+        }
+        if let (Ok(target), Some(parent)) = (std::fs::read_link(ancestor), ancestor.parent()) {
+            return resolve_symlinks(&parent.join(target).join(rest), max_symlinks - 1);
+        }
+    }
+    path.to_path_buf()
+}
+
 fn convert_apply_patch_to_protocol(action: &ApplyPatchAction) -> HashMap<PathBuf, FileChange> {
     let changes = action.changes();
     let mut result = HashMap::with_capacity(changes.len());
@@ -1667,85 +2593,6 @@ fn convert_apply_patch_to_protocol(action: &ApplyPatchAction) -> HashMap<PathBuf
     result
 }
 
-fn apply_changes_from_apply_patch_and_report(
-    action: &ApplyPatchAction,
-    stdout: &mut impl std::io::Write,
-    stderr: &mut impl std::io::Write,
-) -> std::io::Result<()> {
-    match apply_changes_from_apply_patch(action) {
-        Ok(affected_paths) => {
-            print_summary(&affected_paths, stdout)?;
-        }
-        Err(err) => {
-            writeln!(stderr, "{err:?}")?;
-        }
-    }
-
-    Ok(())
-}
-
-fn apply_changes_from_apply_patch(action: &ApplyPatchAction) -> anyhow::Result<AffectedPaths> {
-    let mut added: Vec<PathBuf> = Vec::new();
-    let mut modified: Vec<PathBuf> = Vec::new();
-    let mut deleted: Vec<PathBuf> = Vec::new();
-
-    let changes = action.changes();
-    for (path, change) in changes {
-        match change {
-            ApplyPatchFileChange::Add { content } => {
-                if let Some(parent) = path.parent() {
-                    if !parent.as_os_str().is_empty() {
-                        std::fs::create_dir_all(parent).with_context(|| {
-                            format!("Failed to create parent directories for {}", path.display())
-                        })?;
-                    }
-                }
-                std::fs::write(path, content)
-                    .with_context(|| format!("Failed to write file {}", path.display()))?;
-                added.push(path.clone());
-            }
-            ApplyPatchFileChange::Delete => {
-                std::fs::remove_file(path)
-                    .with_context(|| format!("Failed to delete file {}", path.display()))?;
-                deleted.push(path.clone());
-            }
-            ApplyPatchFileChange::Update {
-                unified_diff: _unified_diff,
-                move_path,
-                new_content,
-            } => {
-                if let Some(move_path) = move_path {
-                    if let Some(parent) = move_path.parent() {
-                        if !parent.as_os_str().is_empty() {
-                            std::fs::create_dir_all(parent).with_context(|| {
-                                format!(
-                                    "Failed to create parent directories for {}",
-                                    move_path.display()
-                                )
-                            })?;
-                        }
-                    }
-
-                    std::fs::rename(path, move_path)
-                        .with_context(|| format!("Failed to rename file {}", path.display()))?;
-                    std::fs::write(move_path, new_content)?;
-                    modified.push(move_path.clone());
-                    deleted.push(path.clone());
-                } else {
-                    std::fs::write(path, new_content)?;
-                    modified.push(path.clone());
-                }
-            }
-        }
-    }
-
-    Ok(AffectedPaths {
-        added,
-        modified,
-        deleted,
-    })
-}
-
 fn get_writable_roots(cwd: &Path) -> Vec<std::path::PathBuf> {
     let mut writable_roots = Vec::new();
     if cfg!(target_os = "macos") {
@@ -1773,28 +2620,33 @@ fn get_writable_roots(cwd: &Path) -> Vec<std::path::PathBuf> {
     writable_roots
 }
 
-/// Exec output is a pre-serialized JSON payload
-fn format_exec_output(output: &str, exit_code: i32, duration: std::time::Duration) -> String {
+/// Exec output is a pre-serialized JSON payload with the interleaved stdout
+/// and stderr of the command.
+fn format_exec_output(output: &ExecToolCallOutput) -> String {
     #[derive(Serialize)]
-    struct ExecMetadata {
+    struct ExecMetadata<'a> {
         exit_code: i32,
         duration_seconds: f32,
+        /// Where the complete output was saved because it was truncated.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        full_output_path: Option<&'a Path>,
     }
 
     #[derive(Serialize)]
     struct ExecOutput<'a> {
         output: &'a str,
-        metadata: ExecMetadata,
+        metadata: ExecMetadata<'a>,
     }
 
     // round to 1 decimal place
-    let duration_seconds = ((duration.as_secs_f32()) * 10.0).round() / 10.0;
+    let duration_seconds = ((output.duration.as_secs_f32()) * 10.0).round() / 10.0;
 
     let payload = ExecOutput {
-        output,
+        output: &output.aggregated_output,
         metadata: ExecMetadata {
-            exit_code,
+            exit_code: output.exit_code,
             duration_seconds,
+            full_output_path: output.full_output_path.as_deref(),
         },
     };
 
@@ -1822,6 +2674,34 @@ fn get_last_assistant_message_from_turn(responses: &[ResponseItem]) -> Option<St
     })
 }
 
+/// Convert the items restored from a rollout file into the messages that
+/// clients should replay to the user.
+fn resumed_messages(items: &[ResponseItem]) -> Vec<ResumedMessage> {
+    items
+        .iter()
+        .filter_map(|item| match item {
+            ResponseItem::Message { role, content } => {
+                let text = content
+                    .iter()
+                    .filter_map(|c| match c {
+                        ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                            Some(text.as_str())
+                        }
+                        ContentItem::InputImage { .. } => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                match role.as_str() {
+                    "user" => Some(ResumedMessage::User { text }),
+                    "assistant" => Some(ResumedMessage::Assistant { text }),
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+        .collect()
+}
+
 /// See [`ConversationHistory`] for details.
 fn record_conversation_history(disable_response_storage: bool, wire_api: WireApi) -> bool {
     if disable_response_storage {
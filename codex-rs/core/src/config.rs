@@ -1,4 +1,5 @@
 use crate::config_profile::ConfigProfile;
+use crate::exec::DEFAULT_TIMEOUT_MS;
 use crate::flags::OPENAI_DEFAULT_MODEL;
 use crate::mcp_server_config::McpServerConfig;
 use crate::model_provider_info::ModelProviderInfo;
@@ -6,6 +7,7 @@ use crate::model_provider_info::built_in_model_providers;
 use crate::protocol::AskForApproval;
 use crate::protocol::SandboxPermission;
 use crate::protocol::SandboxPolicy;
+use crate::protocol::TokenUsage;
 use dirs::home_dir;
 use serde::Deserialize;
 use std::collections::HashMap;
@@ -18,6 +20,14 @@ use std::path::PathBuf;
 /// the context window.
 pub(crate) const PROJECT_DOC_MAX_BYTES: usize = 32 * 1024; // 32 KiB
 
+/// Names of the project doc files that are looked for in each directory, in
+/// order of preference.
+pub(crate) const PROJECT_DOC_FILENAMES: &[&str] = &["AGENTS.md"];
+
+/// Estimated transcript size (in tokens) above which the conversation is
+/// automatically compacted before the next turn.
+pub(crate) const AUTO_COMPACT_TOKEN_LIMIT: usize = 100_000;
+
 /// Location of GitHub Copilot configuration files
 fn copilot_config_dir() -> PathBuf {
     if cfg!(target_os = "windows") {
@@ -128,9 +138,21 @@ pub struct Config {
     /// Combined provider map (defaults merged with user-defined overrides).
     pub model_providers: HashMap<String, ModelProviderInfo>,
 
-    /// Maximum number of bytes to include from an AGENTS.md project doc file.
+    /// Maximum number of bytes to include from the AGENTS.md project doc
+    /// files, all of them combined.
     pub project_doc_max_bytes: usize,
 
+    /// Names of the project doc files, relative to the directory they apply
+    /// to. In each directory, the first one that exists is used.
+    pub project_doc_filenames: Vec<String>,
+
+    /// When the conversation takes up more tokens of the context window than
+    /// this value (as reported by the model for the latest request, or else
+    /// estimated), all but its most recent turns are summarized by the model
+    /// and replaced with the summary before the next task. `0` disables
+    /// automatic compaction.
+    pub auto_compact_token_limit: usize,
+
     /// Directory containing all Codex state (defaults to `~/.codex` but can be
     /// overridden by the `CODEX_HOME` environment variable).
     pub codex_home: PathBuf,
@@ -144,6 +166,66 @@ pub struct Config {
 
     /// Collection of settings that are specific to the TUI.
     pub tui: Tui,
+
+    /// Rollout file of a previous session to resume. When set, the
+    /// conversation recorded in the file is restored and new items are
+    /// appended to the same file.
+    pub resume_path: Option<PathBuf>,
+
+    /// Optional `.policy` file (see the `codex-execpolicy` crate) consulted
+    /// before running a command: commands it deems safe are auto-approved and
+    /// commands it forbids are rejected.
+    pub exec_policy: Option<PathBuf>,
+
+    /// Prices of the models, keyed by model name, used to estimate the cost
+    /// of a session from the token usage the provider reports.
+    pub model_prices: HashMap<String, ModelPrice>,
+
+    /// How the Linux sandbox blocks network access for commands.
+    pub linux_network_sandbox: LinuxNetworkSandbox,
+
+    /// Environment of the commands run for the model.
+    pub shell_environment_policy: ShellEnvironmentPolicy,
+
+    /// Whether the model is offered tools to start and drive persistent,
+    /// interactive shell sessions in addition to the `shell` tool.
+    pub shell_sessions: bool,
+
+    /// How much of the output of a command is sent to the model.
+    pub shell_output_limits: ShellOutputLimits,
+
+    /// Timeout of a command run for the model if it does not specify one.
+    pub exec_default_timeout_ms: u64,
+
+    /// Upper bound on the timeout the model can request for a command, if any.
+    pub exec_max_timeout_ms: Option<u64>,
+}
+
+/// Price of a model in US dollars per million tokens.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq)]
+pub struct ModelPrice {
+    /// Price of uncached input tokens.
+    pub input: f64,
+
+    /// Price of input tokens served from the prompt cache. Defaults to the
+    /// price of uncached input.
+    pub cached_input: Option<f64>,
+
+    /// Price of output tokens (including reasoning tokens).
+    pub output: f64,
+}
+
+impl ModelPrice {
+    /// Estimated cost of `usage` in US dollars.
+    pub fn cost_usd(&self, usage: &TokenUsage) -> f64 {
+        let cached = usage.cached_input_tokens.min(usage.input_tokens);
+        let uncached = usage.input_tokens - cached;
+        let cached_price = self.cached_input.unwrap_or(self.input);
+        (uncached as f64 * self.input
+            + cached as f64 * cached_price
+            + usage.output_tokens as f64 * self.output)
+            / 1_000_000.0
+    }
 }
 
 /// Settings that govern if and what will be written to `~/.codex/history.jsonl`.
@@ -167,6 +249,92 @@ pub enum HistoryPersistence {
     None,
 }
 
+/// Mechanism used by the Linux sandbox to block network access.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinuxNetworkSandbox {
+    /// Deny socket syscalls (other than creating Unix domain sockets) with a
+    /// seccomp filter.
+    #[default]
+    Seccomp,
+    /// Run commands in their own user and network namespaces, in which only a
+    /// loopback interface exists. Unix domain sockets and `localhost` keep
+    /// working. Requires unprivileged user namespaces to be enabled.
+    Namespace,
+}
+
+/// Policy for building the environment of the commands that Codex runs for
+/// the model. Variables are matched by name against glob patterns (e.g.,
+/// `AWS_*`), ignoring case.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct ShellEnvironmentPolicy {
+    /// Which variables of Codex's own environment the command starts with.
+    pub inherit: ShellEnvironmentPolicyInherit,
+
+    /// Unless set, inherited variables that look like secrets (names
+    /// containing `KEY`, `SECRET`, or `TOKEN`) are removed.
+    pub ignore_default_excludes: bool,
+
+    /// Patterns of inherited variables to remove.
+    #[serde(deserialize_with = "deserialize_env_var_patterns")]
+    pub exclude: Vec<glob::Pattern>,
+
+    /// Variables to set, overriding inherited values.
+    pub set: HashMap<String, String>,
+
+    /// If not empty, only the variables matching one of these patterns are
+    /// kept (including those from `set`).
+    #[serde(deserialize_with = "deserialize_env_var_patterns")]
+    pub include_only: Vec<glob::Pattern>,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ShellEnvironmentPolicyInherit {
+    /// Start with Codex's full environment.
+    #[default]
+    All,
+    /// Start with the few variables most programs need, such as `HOME` and
+    /// `PATH`.
+    Core,
+    /// Start with an empty environment.
+    None,
+}
+
+/// Limits on the output of a command that is sent to the model. Longer output
+/// is truncated to its beginning and end, which get half of each limit, and
+/// the complete output is saved to a temporary file.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct ShellOutputLimits {
+    pub max_bytes: usize,
+    pub max_lines: usize,
+    /// Size of the temporary file with the complete output, beyond which the
+    /// output is not saved.
+    pub max_full_output_bytes: usize,
+}
+
+impl Default for ShellOutputLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: 10 * 1024,
+            max_lines: 256,
+            max_full_output_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+fn deserialize_env_var_patterns<'de, D>(deserializer: D) -> Result<Vec<glob::Pattern>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|pattern| glob::Pattern::new(pattern).map_err(serde::de::Error::custom))
+        .collect()
+}
+
 /// Collection of settings that are specific to the TUI.
 #[derive(Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct Tui {
@@ -253,9 +421,52 @@ pub struct ConfigToml {
     #[serde(default)]
     pub model_providers: HashMap<String, ModelProviderInfo>,
 
-    /// Maximum number of bytes to include from an AGENTS.md project doc file.
+    /// Maximum number of bytes to include from the AGENTS.md project doc
+    /// files, all of them combined.
     pub project_doc_max_bytes: Option<usize>,
 
+    /// Names of the project doc files to look for in each directory, in order
+    /// of preference, e.g., `["AGENTS.md", "CLAUDE.md"]`. Defaults to
+    /// `["AGENTS.md"]`.
+    pub project_doc_filenames: Option<Vec<String>>,
+
+    /// Size of the conversation (in tokens) that triggers automatic
+    /// compaction of it. `0` disables it.
+    pub auto_compact_token_limit: Option<usize>,
+
+    /// Path to a `.policy` file used to vet shell commands. Relative paths
+    /// are resolved against the session's working directory.
+    pub exec_policy: Option<PathBuf>,
+
+    /// Per-model prices (US dollars per million tokens) used to estimate the
+    /// cost of a session.
+    #[serde(default)]
+    pub model_prices: HashMap<String, ModelPrice>,
+
+    /// How the Linux sandbox blocks network access: `seccomp` (default) or
+    /// `namespace`.
+    pub linux_network_sandbox: Option<LinuxNetworkSandbox>,
+
+    /// Environment of the commands run for the model. By default, they
+    /// inherit Codex's environment minus variables that look like secrets.
+    pub shell_environment_policy: Option<ShellEnvironmentPolicy>,
+
+    /// Offer the model tools for persistent, interactive shell sessions.
+    /// Defaults to `false`.
+    pub shell_sessions: Option<bool>,
+
+    /// How much of the output of a command is sent to the model. By default,
+    /// 10 KiB or 256 lines, whichever is less.
+    pub shell_output_limits: Option<ShellOutputLimits>,
+
+    /// Timeout in milliseconds of commands for which the model does not
+    /// specify one. Defaults to 10 seconds.
+    pub exec_default_timeout_ms: Option<u64>,
+
+    /// Largest timeout in milliseconds the model may request for a command.
+    /// Unlimited by default.
+    pub exec_max_timeout_ms: Option<u64>,
+
     /// Profile to use from the `profiles` map.
     pub profile: Option<String>,
 
@@ -334,6 +545,8 @@ pub struct ConfigOverrides {
     pub disable_response_storage: Option<bool>,
     pub model_provider: Option<String>,
     pub config_profile: Option<String>,
+    /// Session id or rollout file path of a previous session to resume.
+    pub resume: Option<String>,
 }
 
 impl Config {
@@ -370,6 +583,7 @@ impl Config {
             disable_response_storage,
             model_provider,
             config_profile: config_profile_key,
+            resume,
         } = overrides;
 
         let config_profile = match config_profile_key.or(cfg.profile) {
@@ -442,6 +656,12 @@ impl Config {
 
         let history = cfg.history.unwrap_or_default();
 
+        let resume_path = resume
+            .map(|session| crate::rollout::find_rollout_file(&codex_home, &session))
+            .transpose()?;
+
+        let exec_policy = cfg.exec_policy.map(|p| resolved_cwd.join(p));
+
         let config = Self {
             model: model
                 .or(config_profile.model)
@@ -464,10 +684,33 @@ impl Config {
             mcp_servers: cfg.mcp_servers,
             model_providers,
             project_doc_max_bytes: cfg.project_doc_max_bytes.unwrap_or(PROJECT_DOC_MAX_BYTES),
+            project_doc_filenames: cfg.project_doc_filenames.unwrap_or_else(|| {
+                PROJECT_DOC_FILENAMES
+                    .iter()
+                    .map(|name| name.to_string())
+                    .collect()
+            }),
+            auto_compact_token_limit: cfg
+                .auto_compact_token_limit
+                .unwrap_or(AUTO_COMPACT_TOKEN_LIMIT),
             codex_home,
             history,
             file_opener: cfg.file_opener.unwrap_or(UriBasedFileOpener::VsCode),
             tui: cfg.tui.unwrap_or_default(),
+            resume_path,
+            exec_policy,
+            model_prices: cfg.model_prices,
+            linux_network_sandbox: cfg.linux_network_sandbox.unwrap_or_default(),
+            shell_environment_policy: cfg.shell_environment_policy.unwrap_or_default(),
+            shell_sessions: cfg.shell_sessions.unwrap_or(false),
+            shell_output_limits: cfg.shell_output_limits.unwrap_or_default(),
+            exec_default_timeout_ms: config_profile
+                .exec_default_timeout_ms
+                .or(cfg.exec_default_timeout_ms)
+                .unwrap_or(DEFAULT_TIMEOUT_MS),
+            exec_max_timeout_ms: config_profile
+                .exec_max_timeout_ms
+                .or(cfg.exec_max_timeout_ms),
         };
         Ok(config)
     }
@@ -502,7 +745,7 @@ fn default_model() -> String {
 ///   function will Err if the path does not exist.
 /// - If `CODEX_HOME` is not set, this function does not verify that the
 ///   directory exists.
-fn find_codex_home() -> std::io::Result<PathBuf> {
+pub fn find_codex_home() -> std::io::Result<PathBuf> {
     // Honor the `CODEX_HOME` environment variable when it is set to allow users
     // (and tests) to override the default location.
     if let Ok(val) = std::env::var("CODEX_HOME") {
@@ -535,26 +778,14 @@ pub fn parse_sandbox_permission_with_base_path(
 ) -> std::io::Result<SandboxPermission> {
     use SandboxPermission::*;
 
+    if let Some(path) = raw.strip_prefix("disk-read-folder=") {
+        let folder = parse_sandbox_permission_folder("disk-read-folder", path, base_path)?;
+        return Ok(DiskReadFolder { folder });
+    }
+
     if let Some(path) = raw.strip_prefix("disk-write-folder=") {
-        return if path.is_empty() {
-            Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                "--sandbox-permission disk-write-folder=<PATH> requires a non-empty PATH",
-            ))
-        } else {
-            use path_absolutize::*;
-
-            let file = PathBuf::from(path);
-            let absolute_path = if file.is_relative() {
-                file.absolutize_from(base_path)
-            } else {
-                file.absolutize()
-            }
-            .map(|path| path.into_owned())?;
-            Ok(DiskWriteFolder {
-                folder: absolute_path,
-            })
-        };
+        let folder = parse_sandbox_permission_folder("disk-write-folder", path, base_path)?;
+        return Ok(DiskWriteFolder { folder });
     }
 
     match raw {
@@ -573,10 +804,38 @@ pub fn parse_sandbox_permission_with_base_path(
     }
 }
 
+/// Resolves the `<PATH>` of a `<permission>=<PATH>` sandbox permission against
+/// `base_path`.
+fn parse_sandbox_permission_folder(
+    permission: &str,
+    path: &str,
+    base_path: PathBuf,
+) -> std::io::Result<PathBuf> {
+    if path.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("--sandbox-permission {permission}=<PATH> requires a non-empty PATH"),
+        ));
+    }
+
+    use path_absolutize::*;
+
+    let file = PathBuf::from(path);
+    let absolute_path = if file.is_relative() {
+        file.absolutize_from(base_path)
+    } else {
+        file.absolutize()
+    }
+    .map(|path| path.into_owned())?;
+    Ok(absolute_path)
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::expect_used, clippy::unwrap_used)]
     use super::*;
+    use crate::mcp_server_config::McpServerTransportConfig;
+    use crate::mcp_server_config::McpToolApproval;
     use pretty_assertions::assert_eq;
     use tempfile::TempDir;
 
@@ -666,6 +925,148 @@ persistence = "none"
         assert!(msg.contains("not-a-real-permission"));
     }
 
+    #[test]
+    fn test_shell_environment_policy() {
+        let toml = r#"
+[shell_environment_policy]
+inherit = "core"
+exclude = ["AWS_*"]
+set = { CI = "1" }
+"#;
+        let cfg: ConfigToml = toml::from_str(toml).expect("TOML deserialization should succeed");
+        assert_eq!(
+            Some(ShellEnvironmentPolicy {
+                inherit: ShellEnvironmentPolicyInherit::Core,
+                ignore_default_excludes: false,
+                exclude: vec![glob::Pattern::new("AWS_*").expect("valid pattern")],
+                set: HashMap::from([("CI".to_string(), "1".to_string())]),
+                include_only: Vec::new(),
+            }),
+            cfg.shell_environment_policy
+        );
+
+        let err = toml::from_str::<ConfigToml>(
+            r#"
+[shell_environment_policy]
+include_only = ["[PATH"]
+"#,
+        )
+        .expect_err("invalid patterns should be rejected");
+        assert!(err.to_string().contains("include_only"), "{err}");
+    }
+
+    #[test]
+    fn test_shell_output_limits() {
+        let cfg: ConfigToml = toml::from_str(
+            r#"
+[shell_output_limits]
+max_lines = 1000
+"#,
+        )
+        .expect("TOML deserialization should succeed");
+        assert_eq!(
+            Some(ShellOutputLimits {
+                max_lines: 1000,
+                ..ShellOutputLimits::default()
+            }),
+            cfg.shell_output_limits
+        );
+    }
+
+    #[test]
+    fn test_mcp_server_transports() {
+        let toml = r#"
+[mcp_servers.local]
+command = "docs-server"
+args = ["--stdio"]
+tool_timeout_ms = 120000
+
+[mcp_servers.shared]
+url = "https://mcp.example.com/mcp"
+bearer_token = "secret"
+approval = "always"
+
+[mcp_servers.shared.tool_approval]
+delete_page = "ask"
+"#;
+        let cfg: ConfigToml = toml::from_str(toml).expect("TOML deserialization should succeed");
+        assert_eq!(
+            McpServerTransportConfig::Stdio {
+                command: "docs-server".to_string(),
+                args: vec!["--stdio".to_string()],
+                env: None,
+            },
+            cfg.mcp_servers["local"].transport
+        );
+        assert_eq!(Some(120000), cfg.mcp_servers["local"].tool_timeout_ms);
+        assert_eq!(None, cfg.mcp_servers["local"].startup_timeout_ms);
+        assert_eq!(
+            McpServerTransportConfig::StreamableHttp {
+                url: "https://mcp.example.com/mcp".to_string(),
+                bearer_token: Some("secret".to_string()),
+            },
+            cfg.mcp_servers["shared"].transport
+        );
+        assert_eq!(None, cfg.mcp_servers["local"].approval);
+        assert_eq!(
+            Some(McpToolApproval::Always),
+            cfg.mcp_servers["shared"].approval
+        );
+        assert_eq!(
+            Some(&McpToolApproval::Ask),
+            cfg.mcp_servers["shared"].tool_approval.get("delete_page")
+        );
+    }
+
+    #[test]
+    fn test_sandbox_permission_folders_are_resolved() {
+        let base_path = PathBuf::from("/home/user/project");
+        assert_eq!(
+            SandboxPermission::DiskReadFolder {
+                folder: PathBuf::from("/home/user/project/docs"),
+            },
+            parse_sandbox_permission_with_base_path("disk-read-folder=docs", base_path.clone())
+                .unwrap()
+        );
+        assert_eq!(
+            SandboxPermission::DiskWriteFolder {
+                folder: PathBuf::from("/tmp/out"),
+            },
+            parse_sandbox_permission_with_base_path(
+                "disk-write-folder=/tmp/out",
+                base_path.clone()
+            )
+            .unwrap()
+        );
+
+        let err = parse_sandbox_permission_with_base_path("disk-read-folder=", base_path)
+            .expect_err("an empty path should be rejected");
+        assert!(err.to_string().contains("disk-read-folder=<PATH>"));
+    }
+
+    #[test]
+    fn test_model_prices_estimate_cost() {
+        let toml = r#"
+[model_prices.o3]
+input = 10.0
+cached_input = 2.5
+output = 40.0
+"#;
+        let cfg: ConfigToml = toml::from_str(toml).expect("TOML deserialization should succeed");
+        let price = cfg.model_prices["o3"];
+
+        let usage = TokenUsage {
+            input_tokens: 200_000,
+            cached_input_tokens: 100_000,
+            output_tokens: 50_000,
+            reasoning_output_tokens: 20_000,
+            total_tokens: 250_000,
+        };
+        // 100k uncached input ($1.00) + 100k cached input ($0.25) + 50k
+        // output ($2.00).
+        assert!((price.cost_usd(&usage) - 3.25).abs() < 1e-9);
+    }
+
     struct PrecedenceTestFixture {
         cwd: TempDir,
         codex_home: TempDir,
@@ -716,6 +1117,8 @@ model = "o3"
 model_provider = "openai"
 approval_policy = "on-failure"
 disable_response_storage = true
+exec_default_timeout_ms = 120000
+exec_max_timeout_ms = 600000
 "#;
 
         let cfg: ConfigToml = toml::from_str(toml).expect("TOML deserialization should succeed");
@@ -801,10 +1204,21 @@ disable_response_storage = true
                 mcp_servers: HashMap::new(),
                 model_providers: fixture.model_provider_map.clone(),
                 project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
+                project_doc_filenames: vec!["AGENTS.md".to_string()],
                 codex_home: fixture.codex_home(),
                 history: History::default(),
                 file_opener: UriBasedFileOpener::VsCode,
                 tui: Tui::default(),
+                resume_path: None,
+                auto_compact_token_limit: AUTO_COMPACT_TOKEN_LIMIT,
+                exec_policy: None,
+                model_prices: HashMap::new(),
+                linux_network_sandbox: LinuxNetworkSandbox::Seccomp,
+                shell_environment_policy: ShellEnvironmentPolicy::default(),
+                shell_sessions: false,
+                shell_output_limits: ShellOutputLimits::default(),
+                exec_default_timeout_ms: DEFAULT_TIMEOUT_MS,
+                exec_max_timeout_ms: None,
             },
             o3_profile_config
         );
@@ -838,10 +1252,21 @@ disable_response_storage = true
             mcp_servers: HashMap::new(),
             model_providers: fixture.model_provider_map.clone(),
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
+            project_doc_filenames: vec!["AGENTS.md".to_string()],
             codex_home: fixture.codex_home(),
             history: History::default(),
             file_opener: UriBasedFileOpener::VsCode,
             tui: Tui::default(),
+            resume_path: None,
+            auto_compact_token_limit: AUTO_COMPACT_TOKEN_LIMIT,
+            exec_policy: None,
+            model_prices: HashMap::new(),
+            linux_network_sandbox: LinuxNetworkSandbox::Seccomp,
+            shell_environment_policy: ShellEnvironmentPolicy::default(),
+            shell_sessions: false,
+            shell_output_limits: ShellOutputLimits::default(),
+            exec_default_timeout_ms: DEFAULT_TIMEOUT_MS,
+            exec_max_timeout_ms: None,
         };
 
         assert_eq!(expected_gpt3_profile_config, gpt3_profile_config);
@@ -890,10 +1315,21 @@ disable_response_storage = true
             mcp_servers: HashMap::new(),
             model_providers: fixture.model_provider_map.clone(),
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
+            project_doc_filenames: vec!["AGENTS.md".to_string()],
             codex_home: fixture.codex_home(),
             history: History::default(),
             file_opener: UriBasedFileOpener::VsCode,
             tui: Tui::default(),
+            resume_path: None,
+            auto_compact_token_limit: AUTO_COMPACT_TOKEN_LIMIT,
+            exec_policy: None,
+            model_prices: HashMap::new(),
+            linux_network_sandbox: LinuxNetworkSandbox::Seccomp,
+            shell_environment_policy: ShellEnvironmentPolicy::default(),
+            shell_sessions: false,
+            shell_output_limits: ShellOutputLimits::default(),
+            exec_default_timeout_ms: 120_000,
+            exec_max_timeout_ms: Some(600_000),
         };
 
         assert_eq!(expected_zdr_profile_config, zdr_profile_config);
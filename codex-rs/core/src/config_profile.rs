@@ -12,4 +12,6 @@ pub struct ConfigProfile {
     pub model_provider: Option<String>,
     pub approval_policy: Option<AskForApproval>,
     pub disable_response_storage: Option<bool>,
+    pub exec_default_timeout_ms: Option<u64>,
+    pub exec_max_timeout_ms: Option<u64>,
 }
@@ -1,12 +1,21 @@
+use crate::models::ContentItem;
 use crate::models::ResponseItem;
 
+/// Rough number of bytes per token used to estimate the size of the
+/// transcript without running a real tokenizer.
+const APPROX_BYTES_PER_TOKEN: usize = 4;
+
+/// Images are billed by size rather than by the length of their (base64) data
+/// URL, so count each one as a fixed number of tokens.
+const APPROX_TOKENS_PER_IMAGE: usize = 1_000;
+
 /// Transcript of conversation history that is needed:
 /// - for ZDR clients for which previous_response_id is not available, so we
 ///   must include the transcript with every API call. This must include each
 ///   `function_call` and its corresponding `function_call_output`.
 /// - for clients using the "chat completions" API as opposed to the
 ///   "responses" API.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub(crate) struct ConversationHistory {
     /// The oldest items are at the beginning of the vector.
     items: Vec<ResponseItem>,
@@ -34,6 +43,91 @@ impl ConversationHistory {
             }
         }
     }
+
+    /// Replace the entire transcript, e.g. with a summary after compaction.
+    pub(crate) fn replace(&mut self, items: Vec<ResponseItem>) {
+        self.items.clear();
+        self.record_items(items);
+    }
+
+    /// Split the transcript before its last `turns` turns, each of which
+    /// starts with a user message, and return clones of the items before and
+    /// from that point. The older items are empty if there are no more than
+    /// `turns` turns.
+    pub(crate) fn split_recent_turns(
+        &self,
+        turns: usize,
+    ) -> (Vec<ResponseItem>, Vec<ResponseItem>) {
+        let turn_starts = self.turn_starts();
+        let split = match turns {
+            0 => self.items.len(),
+            _ if turn_starts.len() <= turns => 0,
+            _ => turn_starts[turn_starts.len() - turns],
+        };
+        let (older, recent) = self.items.split_at(split);
+        (older.to_vec(), recent.to_vec())
+    }
+
+    /// Drop the oldest turns until the transcript is estimated to take up at
+    /// most `max_tokens` tokens. The last turn is always kept.
+    pub(crate) fn drop_oldest_turns_over(&mut self, max_tokens: usize) {
+        let mut tokens = self.estimated_token_count();
+        if tokens <= max_tokens {
+            return;
+        }
+        let mut split = 0;
+        for turn_start in self.turn_starts() {
+            if tokens <= max_tokens {
+                break;
+            }
+            let dropped_bytes: usize = self.items[split..turn_start]
+                .iter()
+                .map(approx_item_bytes)
+                .sum();
+            tokens = tokens.saturating_sub(dropped_bytes.div_ceil(APPROX_BYTES_PER_TOKEN));
+            split = turn_start;
+        }
+        self.items.drain(..split);
+    }
+
+    /// Indices of the user messages, each of which starts a turn.
+    fn turn_starts(&self) -> Vec<usize> {
+        self.items
+            .iter()
+            .enumerate()
+            .filter(
+                |(_, item)| matches!(item, ResponseItem::Message { role, .. } if role == "user"),
+            )
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Estimate how many tokens the transcript takes up in the context window.
+    /// This is a heuristic based on the length of the text in each item.
+    pub(crate) fn estimated_token_count(&self) -> usize {
+        let bytes: usize = self.items.iter().map(approx_item_bytes).sum();
+        bytes.div_ceil(APPROX_BYTES_PER_TOKEN)
+    }
+}
+
+fn approx_item_bytes(item: &ResponseItem) -> usize {
+    match item {
+        ResponseItem::Message { content, .. } => content
+            .iter()
+            .map(|c| match c {
+                ContentItem::InputText { text } | ContentItem::OutputText { text } => text.len(),
+                ContentItem::InputImage { .. } => APPROX_TOKENS_PER_IMAGE * APPROX_BYTES_PER_TOKEN,
+            })
+            .sum(),
+        ResponseItem::FunctionCall {
+            name, arguments, ..
+        } => name.len() + arguments.len(),
+        ResponseItem::FunctionCallOutput { output, .. } => output.content.len(),
+        ResponseItem::LocalShellCall { action, .. } => {
+            serde_json::to_string(action).map_or(0, |s| s.len())
+        }
+        ResponseItem::Reasoning { .. } | ResponseItem::Other => 0,
+    }
 }
 
 /// Anything that is not a system message or "reasoning" message is considered
@@ -47,3 +141,100 @@ fn is_api_message(message: &ResponseItem) -> bool {
         ResponseItem::Reasoning { .. } | ResponseItem::Other => false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::FunctionCallOutputPayload;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn replace_discards_previous_items_and_updates_estimate() {
+        let mut history = ConversationHistory::new();
+        history.record_items(vec![
+            ResponseItem::user_text(&"a".repeat(400)),
+            ResponseItem::FunctionCallOutput {
+                call_id: "call_1".to_string(),
+                output: FunctionCallOutputPayload {
+                    content: "b".repeat(800),
+                    success: Some(true),
+                },
+            },
+        ]);
+        assert_eq!(history.estimated_token_count(), 300);
+
+        history.replace(vec![ResponseItem::user_text("summary")]);
+        assert_eq!(history.contents().len(), 1);
+        assert_eq!(history.estimated_token_count(), 2);
+    }
+
+    #[test]
+    fn split_recent_turns_keeps_the_last_turns_whole() {
+        let call = ResponseItem::FunctionCall {
+            name: "shell".to_string(),
+            arguments: "{}".to_string(),
+            call_id: "call_1".to_string(),
+        };
+        let output = ResponseItem::FunctionCallOutput {
+            call_id: "call_1".to_string(),
+            output: FunctionCallOutputPayload {
+                content: "ok".to_string(),
+                success: Some(true),
+            },
+        };
+        let mut history = ConversationHistory::new();
+        history.record_items(vec![
+            ResponseItem::user_text("first"),
+            call.clone(),
+            output.clone(),
+            ResponseItem::user_text("second"),
+            call.clone(),
+            output.clone(),
+            ResponseItem::user_text("third"),
+        ]);
+
+        let (older, recent) = history.split_recent_turns(2);
+        assert_eq!(older.len(), 3);
+        assert_eq!(recent.len(), 4);
+        assert!(matches!(&recent[1], ResponseItem::FunctionCall { .. }));
+
+        let (older, recent) = history.split_recent_turns(3);
+        assert!(older.is_empty());
+        assert_eq!(recent.len(), 7);
+    }
+
+    #[test]
+    fn drop_oldest_turns_over_keeps_the_most_recent_turns() {
+        let mut history = ConversationHistory::new();
+        history.record_items(vec![
+            ResponseItem::user_text(&"a".repeat(400)),
+            ResponseItem::user_text(&"b".repeat(400)),
+            ResponseItem::user_text(&"c".repeat(400)),
+        ]);
+        assert_eq!(history.estimated_token_count(), 300);
+
+        history.drop_oldest_turns_over(300);
+        assert_eq!(first_letters(&history), "abc");
+
+        history.drop_oldest_turns_over(250);
+        assert_eq!(first_letters(&history), "bc");
+
+        // The last turn is kept even if it is too large on its own.
+        history.drop_oldest_turns_over(10);
+        assert_eq!(first_letters(&history), "c");
+    }
+
+    fn first_letters(history: &ConversationHistory) -> String {
+        history
+            .contents()
+            .iter()
+            .filter_map(|item| match item {
+                ResponseItem::Message { content, .. } => match content.first() {
+                    Some(ContentItem::InputText { text }) => text.chars().next(),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect()
+    }
+}
@@ -1,3 +1,4 @@
+use crate::exec::ExecToolCallOutput;
 use reqwest::StatusCode;
 use serde_json;
 use std::io;
@@ -8,9 +9,15 @@ pub type Result<T> = std::result::Result<T, CodexErr>;
 
 #[derive(Error, Debug)]
 pub enum SandboxErr {
-    /// Error from sandbox execution
-    #[error("sandbox denied exec error, exit code: {0}, stdout: {1}, stderr: {2}")]
-    Denied(i32, String, String),
+    /// Error from sandbox execution: the command exited with a non-zero
+    /// exit code, which may be because the sandbox blocked it.
+    #[error(
+        "sandbox denied exec error, exit code: {}, stdout: {}, stderr: {}",
+        .0.exit_code,
+        .0.stdout,
+        .0.stderr
+    )]
+    Denied(Box<ExecToolCallOutput>),
 
     /// Error from linux seccomp filter setup
     #[cfg(target_os = "linux")]
@@ -22,9 +29,12 @@ pub enum SandboxErr {
     #[error("seccomp backend error")]
     SeccompBackend(#[from] seccompiler::BackendError),
 
-    /// Command timed out
-    #[error("command timed out")]
-    Timeout,
+    /// Command timed out. `output` is what it printed before it was stopped.
+    #[error("command timed out after {timeout_ms} ms")]
+    Timeout {
+        timeout_ms: u64,
+        output: Box<ExecToolCallOutput>,
+    },
 
     /// Command was killed by a signal
     #[error("command was killed by a signal")]
@@ -1,35 +1,70 @@
 #[cfg(unix)]
 use std::os::unix::process::ExitStatusExt;
 
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fs::File;
 use std::io;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::ExitStatus;
 use std::process::Stdio;
 use std::sync::Arc;
+use std::sync::Mutex;
 use std::time::Duration;
 use std::time::Instant;
+use std::time::SystemTime;
 
+use async_channel::Sender;
 use tokio::io::AsyncRead;
 use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
 use tokio::io::BufReader;
 use tokio::process::Child;
 use tokio::process::Command;
 use tokio::sync::Notify;
+use uuid::Uuid;
 
+use crate::config::ShellOutputLimits;
 use crate::error::CodexErr;
 use crate::error::Result;
 use crate::error::SandboxErr;
 use crate::exec_linux::exec_linux;
+use crate::protocol::Event;
+use crate::protocol::EventMsg;
+use crate::protocol::ExecCommandOutputDeltaEvent;
+use crate::protocol::ExecOutputStream;
 use crate::protocol::SandboxPolicy;
 
-// Maximum we send for each stream, which is either:
-// - 10KiB OR
-// - 256 lines
-const MAX_STREAM_OUTPUT: usize = 10 * 1024;
-const MAX_STREAM_OUTPUT_LINES: usize = 256;
+/// Timeout of commands that do not specify one, unless configured otherwise
+/// with `exec_default_timeout_ms`.
+pub(crate) const DEFAULT_TIMEOUT_MS: u64 = 10_000;
 
-const DEFAULT_TIMEOUT_MS: u64 = 10_000;
+/// How long a command that timed out or was interrupted has to exit after
+/// `SIGTERM` before its process group is sent `SIGKILL`.
+const KILL_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// Minimum time between two `ExecCommandOutputDelta` events for the same
+/// stream, so that chatty commands do not flood the client.
+const OUTPUT_DELTA_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Maximum size of the chunk of an `ExecCommandOutputDelta` event. More
+/// output is sent in several events, regardless of the interval.
+const MAX_OUTPUT_DELTA_BYTES: usize = 8 * 1024;
+
+/// Sent in place of the output of a command that exceeds its limits for
+/// `ExecCommandOutputDelta` events.
+const OUTPUT_DELTAS_TRUNCATED_MARKER: &str = "\n[... output truncated ...]\n";
+
+/// Prefix of the [`FullOutputDir`] of each session in the temp directory.
+const FULL_OUTPUT_DIR_PREFIX: &str = "codex-exec-output-";
+
+/// How old the [`FullOutputDir`] of another session must be to be deleted,
+/// in case that session did not get to delete it when it ended.
+const FULL_OUTPUT_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
 
 // Hardcode these since it does not seem worth including the libc crate just
 // for these.
@@ -59,6 +94,122 @@ pub struct ExecParams {
     pub command: Vec<String>,
     pub cwd: PathBuf,
     pub timeout_ms: Option<u64>,
+    /// The complete environment of the command; see [`crate::exec_env`].
+    pub env: HashMap<String, String>,
+    /// Written to the command's stdin, which is `/dev/null` otherwise.
+    pub stdin: Option<String>,
+}
+
+/// Destination for the output of a command while it is still running. Each
+/// chunk is sent as an `ExecCommandOutputDelta` event for `call_id`.
+#[derive(Debug, Clone)]
+pub struct ExecOutputSink {
+    pub sub_id: String,
+    pub call_id: String,
+    pub tx_event: Sender<Event>,
+}
+
+/// How the output of a command is captured for the model.
+#[derive(Debug)]
+pub struct OutputCapture {
+    pub limits: ShellOutputLimits,
+    /// Receives the complete output of the command if it exceeds `limits`.
+    pub full_output: Option<File>,
+}
+
+/// Directory of a session where the complete output of the commands whose
+/// output was truncated is saved. It is only created once an output is saved,
+/// and is deleted with its files when dropped at the end of the session.
+#[derive(Debug)]
+pub struct FullOutputDir {
+    path: PathBuf,
+}
+
+impl FullOutputDir {
+    /// Picks a new directory in the temp directory, and deletes those left
+    /// behind by sessions that ended more than [`FULL_OUTPUT_RETENTION`] ago.
+    pub fn new() -> Self {
+        let temp_dir = std::env::temp_dir();
+        remove_expired_full_output_dirs(&temp_dir);
+        Self {
+            path: temp_dir.join(format!("{FULL_OUTPUT_DIR_PREFIX}{}", Uuid::new_v4())),
+        }
+    }
+
+    /// Whether commands run under `sandbox_policy` can read the saved files.
+    /// Otherwise there is no point in saving them, as the model could not
+    /// look at them.
+    fn is_readable_under(&self, sandbox_policy: &SandboxPolicy, cwd: &Path) -> bool {
+        if sandbox_policy.has_full_disk_read_access() {
+            return true;
+        }
+        // The directory may not exist yet, but its parent does.
+        let Some(parent) = self.path.parent().and_then(|p| p.canonicalize().ok()) else {
+            return false;
+        };
+        sandbox_policy
+            .get_readable_roots_with_cwd(cwd)
+            .into_iter()
+            .chain(sandbox_policy.get_writable_roots_with_cwd(cwd))
+            .filter_map(|root| root.canonicalize().ok())
+            .any(|root| parent.starts_with(root))
+    }
+
+    /// Copies the output saved in `output` to a new file in the directory.
+    fn save(&self, mut output: File) -> io::Result<PathBuf> {
+        let mut builder = std::fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        builder.create(&self.path)?;
+
+        let path = self.path.join(format!("{}.log", Uuid::new_v4()));
+        let mut file = create_private_file(&path)?;
+        output.seek(SeekFrom::Start(0))?;
+        io::copy(&mut output, &mut file)?;
+        Ok(path)
+    }
+}
+
+impl Default for FullOutputDir {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for FullOutputDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+fn remove_expired_full_output_dirs(temp_dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(temp_dir) else {
+        return;
+    };
+    let now = SystemTime::now();
+    for entry in entries.flatten() {
+        if !entry
+            .file_name()
+            .to_string_lossy()
+            .starts_with(FULL_OUTPUT_DIR_PREFIX)
+        {
+            continue;
+        }
+        let expired = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .is_ok_and(|modified| {
+                now.duration_since(modified)
+                    .is_ok_and(|age| age > FULL_OUTPUT_RETENTION)
+            });
+        if expired {
+            let _ = std::fs::remove_dir_all(entry.path());
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -70,6 +221,12 @@ pub enum SandboxType {
 
     /// Only available on Linux.
     LinuxSeccomp,
+
+    /// Only available on Linux. Like `LinuxSeccomp`, but the network is
+    /// blocked by running the command in its own user and network namespaces
+    /// (with only a loopback interface) rather than by a seccomp filter, so
+    /// Unix domain sockets and `localhost` keep working.
+    LinuxNetworkNamespace,
 }
 
 pub async fn process_exec_tool_call(
@@ -77,61 +234,100 @@ pub async fn process_exec_tool_call(
     sandbox_type: SandboxType,
     ctrl_c: Arc<Notify>,
     sandbox_policy: &SandboxPolicy,
+    output_limits: ShellOutputLimits,
+    full_output_dir: &FullOutputDir,
+    output_sink: Option<ExecOutputSink>,
 ) -> Result<ExecToolCallOutput> {
     let start = Instant::now();
+    let timeout_ms = params.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS);
+
+    // The Linux sandbox also applies to the thread that reads the output, so
+    // the complete output goes to an anonymous file created up front, which is
+    // only copied to `full_output_dir` if the output turns out to be truncated.
+    let full_output = if full_output_dir.is_readable_under(sandbox_policy, &params.cwd) {
+        tempfile::tempfile().ok()
+    } else {
+        None
+    };
+    let capture = OutputCapture {
+        limits: output_limits,
+        full_output: full_output.as_ref().and_then(|file| file.try_clone().ok()),
+    };
 
     let raw_output_result = match sandbox_type {
-        SandboxType::None => exec(params, sandbox_policy, ctrl_c).await,
+        SandboxType::None => exec(params, sandbox_policy, ctrl_c, capture, output_sink).await,
         SandboxType::MacosSeatbelt => {
             let ExecParams {
                 command,
                 cwd,
                 timeout_ms,
+                env,
+                stdin,
             } = params;
             let child = spawn_command_under_seatbelt(
                 command,
                 sandbox_policy,
                 cwd,
-                StdioPolicy::RedirectForShellTool,
+                StdioPolicy::for_shell_tool(&stdin),
+                env,
             )
             .await?;
-            consume_truncated_output(child, ctrl_c, timeout_ms).await
+            consume_truncated_output(child, ctrl_c, timeout_ms, stdin, capture, output_sink).await
+        }
+        SandboxType::LinuxSeccomp => {
+            exec_linux(params, ctrl_c, sandbox_policy, capture, output_sink, false)
+        }
+        SandboxType::LinuxNetworkNamespace => {
+            exec_linux(params, ctrl_c, sandbox_policy, capture, output_sink, true)
         }
-        SandboxType::LinuxSeccomp => exec_linux(params, ctrl_c, sandbox_policy),
     };
     let duration = start.elapsed();
+    let full_output_path = match (&raw_output_result, full_output) {
+        (Ok(raw_output), Some(file)) if raw_output.full_output_saved => {
+            full_output_dir.save(file).ok()
+        }
+        _ => None,
+    };
     match raw_output_result {
         Ok(raw_output) => {
             let stdout = String::from_utf8_lossy(&raw_output.stdout).to_string();
             let stderr = String::from_utf8_lossy(&raw_output.stderr).to_string();
+            let aggregated_output = String::from_utf8_lossy(&raw_output.aggregated).to_string();
+
+            let exit_code = raw_output.exit_status.code().unwrap_or(-1);
+
+            // NOTE(ragona): This is much less restrictive than the previous check. If we exec
+            // a command, and it returns anything other than success, we assume that it may have
+            // been a sandboxing error and allow the user to retry. (The user of course may choose
+            // not to retry, or in a non-interactive mode, would automatically reject the approval.)
+            let output = ExecToolCallOutput {
+                exit_code,
+                stdout,
+                stderr,
+                aggregated_output,
+                duration,
+                full_output_path,
+            };
 
             #[cfg(target_family = "unix")]
             match raw_output.exit_status.signal() {
-                Some(TIMEOUT_CODE) => return Err(CodexErr::Sandbox(SandboxErr::Timeout)),
+                Some(TIMEOUT_CODE) => {
+                    return Err(CodexErr::Sandbox(SandboxErr::Timeout {
+                        timeout_ms,
+                        output: Box::new(output),
+                    }));
+                }
                 Some(signal) => {
                     return Err(CodexErr::Sandbox(SandboxErr::Signal(signal)));
                 }
                 None => {}
             }
 
-            let exit_code = raw_output.exit_status.code().unwrap_or(-1);
-
-            // NOTE(ragona): This is much less restrictive than the previous check. If we exec
-            // a command, and it returns anything other than success, we assume that it may have
-            // been a sandboxing error and allow the user to retry. (The user of course may choose
-            // not to retry, or in a non-interactive mode, would automatically reject the approval.)
             if exit_code != 0 && sandbox_type != SandboxType::None {
-                return Err(CodexErr::Sandbox(SandboxErr::Denied(
-                    exit_code, stdout, stderr,
-                )));
+                return Err(CodexErr::Sandbox(SandboxErr::Denied(Box::new(output))));
             }
 
-            Ok(ExecToolCallOutput {
-                exit_code,
-                stdout,
-                stderr,
-                duration,
-            })
+            Ok(output)
         }
         Err(err) => {
             tracing::error!("exec error: {err}");
@@ -145,12 +341,13 @@ pub async fn spawn_command_under_seatbelt(
     sandbox_policy: &SandboxPolicy,
     cwd: PathBuf,
     stdio_policy: StdioPolicy,
+    env: HashMap<String, String>,
 ) -> std::io::Result<Child> {
     let seatbelt_command = create_seatbelt_command(command, sandbox_policy, &cwd);
-    spawn_child_async(seatbelt_command, cwd, sandbox_policy, stdio_policy).await
+    spawn_child_async(seatbelt_command, cwd, sandbox_policy, stdio_policy, env).await
 }
 
-fn create_seatbelt_command(
+pub(crate) fn create_seatbelt_command(
     command: Vec<String>,
     sandbox_policy: &SandboxPolicy,
     cwd: &Path,
@@ -192,7 +389,6 @@ fn create_seatbelt_command(
         ""
     };
 
-    // TODO(mbolin): apply_patch calls must also honor the SandboxPolicy.
     let network_policy = if sandbox_policy.has_full_network_access() {
         "(allow network-outbound)\n(allow network-inbound)\n(allow system-socket)"
     } else {
@@ -218,6 +414,11 @@ pub struct RawExecToolCallOutput {
     pub exit_status: ExitStatus,
     pub stdout: Vec<u8>,
     pub stderr: Vec<u8>,
+    /// Output of both streams, interleaved in the order it was read.
+    pub aggregated: Vec<u8>,
+    /// Whether the aggregated output was truncated and saved in full to
+    /// [`OutputCapture::full_output`].
+    pub full_output_saved: bool,
 }
 
 #[derive(Debug)]
@@ -225,7 +426,12 @@ pub struct ExecToolCallOutput {
     pub exit_code: i32,
     pub stdout: String,
     pub stderr: String,
+    /// Output of both streams, interleaved in the order it was produced.
+    pub aggregated_output: String,
     pub duration: Duration,
+    /// File with the complete output, if `aggregated_output` was truncated
+    /// and the sandbox lets commands read it.
+    pub full_output_path: Option<PathBuf>,
 }
 
 async fn exec(
@@ -233,41 +439,57 @@ async fn exec(
         command,
         cwd,
         timeout_ms,
+        env,
+        stdin,
     }: ExecParams,
     sandbox_policy: &SandboxPolicy,
     ctrl_c: Arc<Notify>,
+    capture: OutputCapture,
+    output_sink: Option<ExecOutputSink>,
 ) -> Result<RawExecToolCallOutput> {
     let child = spawn_child_async(
         command,
         cwd,
         sandbox_policy,
-        StdioPolicy::RedirectForShellTool,
+        StdioPolicy::for_shell_tool(&stdin),
+        env,
     )
     .await?;
-    consume_truncated_output(child, ctrl_c, timeout_ms).await
+    consume_truncated_output(child, ctrl_c, timeout_ms, stdin, capture, output_sink).await
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum StdioPolicy {
     RedirectForShellTool,
+    /// Like `RedirectForShellTool`, but stdin is a pipe so that
+    /// [`ExecParams::stdin`] can be written to it.
+    RedirectForShellToolWithStdin,
     Inherit,
 }
 
+impl StdioPolicy {
+    /// The policy for a shell tool call whose stdin is `stdin`.
+    pub(crate) fn for_shell_tool(stdin: &Option<String>) -> Self {
+        if stdin.is_some() {
+            StdioPolicy::RedirectForShellToolWithStdin
+        } else {
+            StdioPolicy::RedirectForShellTool
+        }
+    }
+}
+
 macro_rules! configure_command {
     (
         $cmd_type: path,
         $command: expr,
         $cwd: expr,
         $sandbox_policy: expr,
-        $stdio_policy: expr
+        $stdio_policy: expr,
+        $env: expr
     ) => {{
-        // For now, we take `SandboxPolicy` as a parameter to spawn_child() because
-        // we need to determine whether to set the
-        // `CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR` environment variable.
-        // Ultimately, we should be stricter about the environment variables that
-        // are set for the command (as we are when spawning an MCP server), so
-        // instead of SandboxPolicy, we should take the exact env to use for the
-        // Command (i.e., `env_clear().envs(env)`).
+        // `SandboxPolicy` is needed to determine whether to set the
+        // `CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR` environment variable on top
+        // of `$env`, which is the exact environment to use for the command.
         if $command.is_empty() {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
@@ -278,20 +500,31 @@ macro_rules! configure_command {
         let mut cmd = <$cmd_type>::new(&$command[0]);
         cmd.args(&$command[1..]);
         cmd.current_dir($cwd);
+        cmd.env_clear();
+        cmd.envs($env);
 
         if !$sandbox_policy.has_full_network_access() {
             cmd.env(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR, "1");
         }
 
         match $stdio_policy {
-            StdioPolicy::RedirectForShellTool => {
-                // Do not create a file descriptor for stdin because otherwise some
-                // commands may hang forever waiting for input. For example, ripgrep has
-                // a heuristic where it may try to read from stdin as explained here:
-                // https://github.com/BurntSushi/ripgrep/blob/e2362d4d5185d02fa857bf381e7bd52e66fafc73/crates/core/flags/hiargs.rs#L1101-L1103
-                cmd.stdin(Stdio::null());
+            StdioPolicy::RedirectForShellTool | StdioPolicy::RedirectForShellToolWithStdin => {
+                if matches!($stdio_policy, StdioPolicy::RedirectForShellToolWithStdin) {
+                    cmd.stdin(Stdio::piped());
+                } else {
+                    // Do not create a file descriptor for stdin because otherwise some
+                    // commands may hang forever waiting for input. For example, ripgrep has
+                    // a heuristic where it may try to read from stdin as explained here:
+                    // https://github.com/BurntSushi/ripgrep/blob/e2362d4d5185d02fa857bf381e7bd52e66fafc73/crates/core/flags/hiargs.rs#L1101-L1103
+                    cmd.stdin(Stdio::null());
+                }
 
                 cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+
+                // Lead a new process group so that the whole command, and
+                // not only its first process, can be stopped on timeout.
+                #[cfg(unix)]
+                cmd.process_group(0);
             }
             StdioPolicy::Inherit => {
                 // Inherit stdin, stdout, and stderr from the parent process.
@@ -313,8 +546,32 @@ pub(crate) async fn spawn_child_async(
     cwd: PathBuf,
     sandbox_policy: &SandboxPolicy,
     stdio_policy: StdioPolicy,
+    env: HashMap<String, String>,
 ) -> std::io::Result<Child> {
-    let mut cmd = configure_command!(Command, command, cwd, sandbox_policy, stdio_policy)?;
+    let mut cmd = configure_command!(Command, command, cwd, sandbox_policy, stdio_policy, env)?;
+    cmd.kill_on_drop(true).spawn()
+}
+
+/// Like `spawn_child_async()`, but `pre_exec` is run in the child after
+/// `fork()` and before `exec()`, so it must only make async-signal-safe calls.
+#[cfg(target_os = "linux")]
+pub(crate) async fn spawn_child_async_with_pre_exec<F>(
+    command: Vec<String>,
+    cwd: PathBuf,
+    sandbox_policy: &SandboxPolicy,
+    stdio_policy: StdioPolicy,
+    env: HashMap<String, String>,
+    pre_exec: F,
+) -> std::io::Result<Child>
+where
+    F: FnMut() -> std::io::Result<()> + Send + Sync + 'static,
+{
+    let mut cmd = configure_command!(Command, command, cwd, sandbox_policy, stdio_policy, env)?;
+    // SAFETY: callers only pass hooks that restrict themselves to raw system
+    // calls on memory that was prepared before the fork.
+    unsafe {
+        cmd.pre_exec(pre_exec);
+    }
     cmd.kill_on_drop(true).spawn()
 }
 
@@ -326,28 +583,93 @@ pub fn spawn_child_sync(
     cwd: PathBuf,
     sandbox_policy: &SandboxPolicy,
     stdio_policy: StdioPolicy,
+    env: HashMap<String, String>,
 ) -> std::io::Result<std::process::Child> {
+    #[cfg(unix)]
+    use std::os::unix::process::CommandExt;
+
     let mut cmd = configure_command!(
         std::process::Command,
         command,
         cwd,
         sandbox_policy,
-        stdio_policy
+        stdio_policy,
+        env
     )?;
     cmd.spawn()
 }
 
-/// Consumes the output of a child process, truncating it so it is suitable for
-/// use as the output of a `shell` tool call. Also enforces specified timeout.
+/// Creates a command that runs in a new session with `terminal`, the
+/// subsidiary side of a pseudoterminal, as its controlling terminal and as its
+/// stdin, stdout, and stderr. Used for interactive shell sessions.
+#[cfg(unix)]
+pub(crate) fn create_terminal_command(
+    command: Vec<String>,
+    cwd: PathBuf,
+    sandbox_policy: &SandboxPolicy,
+    env: HashMap<String, String>,
+    terminal: &std::fs::File,
+) -> std::io::Result<std::process::Command> {
+    use std::os::unix::process::CommandExt;
+
+    let mut cmd = configure_command!(
+        std::process::Command,
+        command,
+        cwd,
+        sandbox_policy,
+        StdioPolicy::Inherit,
+        env
+    )?;
+    cmd.stdin(terminal.try_clone()?)
+        .stdout(terminal.try_clone()?)
+        .stderr(terminal.try_clone()?);
+    // SAFETY: `setsid()` and `ioctl()` are async-signal-safe.
+    unsafe {
+        cmd.pre_exec(|| {
+            if libc::setsid() == -1 || libc::ioctl(0, libc::TIOCSCTTY as _, 0) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    Ok(cmd)
+}
+
+/// Consumes the output of a child process, truncating it as described by
+/// `capture` so it is suitable for use as the output of a `shell` tool call.
+/// Also enforces specified timeout. If `output_sink` is set, the beginning of
+/// the output, up to `capture.limits`, is also forwarded to it as it is read. If `stdin` is set, it is
+/// written to the child, which must have been spawned with
+/// [`StdioPolicy::RedirectForShellToolWithStdin`].
 pub(crate) async fn consume_truncated_output(
     mut child: Child,
     ctrl_c: Arc<Notify>,
     timeout_ms: Option<u64>,
+    stdin: Option<String>,
+    capture: OutputCapture,
+    output_sink: Option<ExecOutputSink>,
 ) -> Result<RawExecToolCallOutput> {
+    if let Some(input) = stdin {
+        let mut stdin_writer = child.stdin.take().ok_or_else(|| {
+            CodexErr::Io(io::Error::other(
+                "stdin pipe was unexpectedly not available",
+            ))
+        })?;
+        // Written concurrently with reading the output so that neither side
+        // blocks on a full pipe. The pipe is closed once all input is written
+        // so the child sees EOF; if it exits early, the write fails, which is
+        // reflected in its exit status rather than reported here.
+        tokio::spawn(async move {
+            let _ = stdin_writer.write_all(input.as_bytes()).await;
+        });
+    }
+
     // Both stdout and stderr were configured with `Stdio::piped()`
     // above, therefore `take()` should normally return `Some`.  If it doesn't
     // we treat it as an exceptional I/O error
 
+    let process_group = KillProcessGroupOnDrop::new(&child);
+
     let stdout_reader = child.stdout.take().ok_or_else(|| {
         CodexErr::Io(io::Error::other(
             "stdout pipe was unexpectedly not available",
@@ -359,15 +681,25 @@ pub(crate) async fn consume_truncated_output(
         ))
     })?;
 
+    let aggregated = Arc::new(Mutex::new(AggregatedOutput::new(
+        capture.limits,
+        capture.full_output,
+    )));
+    let delta_budget = Arc::new(Mutex::new(OutputDeltaBudget::new(capture.limits)));
     let stdout_handle = tokio::spawn(read_capped(
         BufReader::new(stdout_reader),
-        MAX_STREAM_OUTPUT,
-        MAX_STREAM_OUTPUT_LINES,
+        capture.limits,
+        aggregated.clone(),
+        output_sink.clone().map(|sink| {
+            OutputDeltaEmitter::new(sink, ExecOutputStream::Stdout, delta_budget.clone())
+        }),
     ));
     let stderr_handle = tokio::spawn(read_capped(
         BufReader::new(stderr_reader),
-        MAX_STREAM_OUTPUT,
-        MAX_STREAM_OUTPUT_LINES,
+        capture.limits,
+        aggregated.clone(),
+        output_sink
+            .map(|sink| OutputDeltaEmitter::new(sink, ExecOutputStream::Stderr, delta_budget)),
     ));
 
     let interrupted = ctrl_c.notified();
@@ -379,64 +711,473 @@ pub(crate) async fn consume_truncated_output(
                 Ok(e) => e?,
                 Err(_) => {
                     // timeout
-                    child.start_kill()?;
-                    // Debatable whether `child.wait().await` should be called here.
+                    terminate(&mut child).await?;
                     synthetic_exit_status(128 + TIMEOUT_CODE)
                 }
             }
         }
         _ = interrupted => {
-            child.start_kill()?;
+            terminate(&mut child).await?;
             synthetic_exit_status(128 + SIGKILL_CODE)
         }
     };
 
     let stdout = stdout_handle.await??;
     let stderr = stderr_handle.await??;
+    process_group.disarm();
+    #[expect(clippy::unwrap_used)]
+    let aggregated = aggregated.lock().unwrap();
 
     Ok(RawExecToolCallOutput {
         exit_status,
         stdout,
         stderr,
+        aggregated: aggregated.retained.to_bytes(),
+        full_output_saved: aggregated.full_output_saved(),
     })
 }
 
+/// Stops `child` and the processes it started. Commands run for the shell tool
+/// lead their own process group, which is sent `SIGTERM` and then, once the
+/// child exited or [`KILL_GRACE_PERIOD`] passed, `SIGKILL` for any process
+/// that is left, as those could otherwise keep the output pipes open.
+#[cfg(unix)]
+async fn terminate(child: &mut Child) -> io::Result<()> {
+    let Some(pgid) = child.id().and_then(|pid| libc::pid_t::try_from(pid).ok()) else {
+        // The child was already reaped.
+        return Ok(());
+    };
+    // SAFETY: `killpg()` has no memory safety requirements.
+    unsafe {
+        libc::killpg(pgid, libc::SIGTERM);
+    }
+    let _ = tokio::time::timeout(KILL_GRACE_PERIOD, child.wait()).await;
+    // SAFETY: as above.
+    unsafe {
+        libc::killpg(pgid, libc::SIGKILL);
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+async fn terminate(child: &mut Child) -> io::Result<()> {
+    child.start_kill()
+}
+
+/// Sends `SIGKILL` to the process group of a command run for the shell tool
+/// when dropped before [`KillProcessGroupOnDrop::disarm`] is called, e.g.,
+/// because the task running the command was aborted. `kill_on_drop()` alone
+/// would only kill the child, not the processes it started.
+#[cfg(unix)]
+struct KillProcessGroupOnDrop(Option<libc::pid_t>);
+
+#[cfg(unix)]
+impl KillProcessGroupOnDrop {
+    fn new(child: &Child) -> Self {
+        Self(child.id().and_then(|pid| libc::pid_t::try_from(pid).ok()))
+    }
+
+    fn disarm(mut self) {
+        self.0 = None;
+    }
+}
+
+#[cfg(unix)]
+impl Drop for KillProcessGroupOnDrop {
+    fn drop(&mut self) {
+        if let Some(pgid) = self.0 {
+            // SAFETY: `killpg()` has no memory safety requirements.
+            unsafe {
+                libc::killpg(pgid, libc::SIGKILL);
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+struct KillProcessGroupOnDrop;
+
+#[cfg(not(unix))]
+impl KillProcessGroupOnDrop {
+    fn new(_child: &Child) -> Self {
+        Self
+    }
+
+    fn disarm(self) {}
+}
+
+/// Retains the beginning and the end of some output within limits and
+/// counts what is omitted in between. The beginning and the end each get
+/// half of the limits.
+#[derive(Debug)]
+struct HeadTailBuffer {
+    head: Vec<u8>,
+    head_lines: usize,
+    max_head_bytes: usize,
+    max_head_lines: usize,
+    /// Set once the head is full; all further output goes to the tail.
+    head_full: bool,
+
+    tail: VecDeque<u8>,
+    tail_lines: usize,
+    max_tail_bytes: usize,
+    max_tail_lines: usize,
+
+    omitted_bytes: usize,
+    omitted_lines: usize,
+}
+
+impl HeadTailBuffer {
+    fn new(limits: ShellOutputLimits) -> Self {
+        let max_head_bytes = limits.max_bytes / 2;
+        let max_head_lines = limits.max_lines / 2;
+        Self {
+            head: Vec::new(),
+            head_lines: 0,
+            max_head_bytes,
+            max_head_lines,
+            head_full: false,
+            tail: VecDeque::new(),
+            tail_lines: 0,
+            max_tail_bytes: limits.max_bytes - max_head_bytes,
+            max_tail_lines: limits.max_lines - max_head_lines,
+            omitted_bytes: 0,
+            omitted_lines: 0,
+        }
+    }
+
+    fn push(&mut self, chunk: &[u8]) {
+        for &b in chunk {
+            if !self.head_full {
+                if self.head.len() < self.max_head_bytes && self.head_lines < self.max_head_lines {
+                    self.head.push(b);
+                    if b == b'\n' {
+                        self.head_lines += 1;
+                    }
+                    continue;
+                }
+                self.head_full = true;
+            }
+
+            self.tail.push_back(b);
+            if b == b'\n' {
+                self.tail_lines += 1;
+            }
+            while self.tail.len() > self.max_tail_bytes || self.tail_lines > self.max_tail_lines {
+                let Some(dropped) = self.tail.pop_front() else {
+                    break;
+                };
+                self.omitted_bytes += 1;
+                if dropped == b'\n' {
+                    self.tail_lines -= 1;
+                    self.omitted_lines += 1;
+                }
+            }
+        }
+    }
+
+    fn is_truncated(&self) -> bool {
+        self.omitted_bytes > 0
+    }
+
+    /// The retained output, with a marker in place of the omitted part.
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.head.clone();
+        if self.is_truncated() {
+            if !bytes.is_empty() && !bytes.ends_with(b"\n") {
+                bytes.push(b'\n');
+            }
+            bytes.extend_from_slice(
+                format!(
+                    "[... omitted {} lines ({} bytes) ...]\n",
+                    self.omitted_lines, self.omitted_bytes
+                )
+                .as_bytes(),
+            );
+        }
+        bytes.extend(&self.tail);
+        bytes
+    }
+}
+
+/// The output of all the streams of a command, interleaved in the order it
+/// was read.
+struct AggregatedOutput {
+    retained: HeadTailBuffer,
+    full_output: FullOutput,
+    max_full_output_bytes: usize,
+}
+
+/// State of the file that receives the complete output once it is truncated.
+enum FullOutput {
+    /// The output has not been truncated yet, so everything read so far is
+    /// kept in case it is.
+    Pending { file: File, bytes: Vec<u8> },
+    /// The output was truncated and is being written to the file, which
+    /// holds `written` bytes of it so far.
+    Saving { file: File, written: usize },
+    /// The file reached `max_full_output_bytes` and ends with a note saying
+    /// so; the rest of the output is discarded.
+    Full,
+    /// There is no file, or writing to it failed.
+    Unavailable,
+}
+
+impl AggregatedOutput {
+    fn new(limits: ShellOutputLimits, full_output: Option<File>) -> Self {
+        Self {
+            retained: HeadTailBuffer::new(limits),
+            full_output: match full_output {
+                Some(file) => FullOutput::Pending {
+                    file,
+                    bytes: Vec::new(),
+                },
+                None => FullOutput::Unavailable,
+            },
+            max_full_output_bytes: limits.max_full_output_bytes,
+        }
+    }
+
+    fn push(&mut self, chunk: &[u8]) {
+        self.retained.push(chunk);
+        let max_bytes = self.max_full_output_bytes;
+        self.full_output = match std::mem::replace(&mut self.full_output, FullOutput::Unavailable) {
+            FullOutput::Pending { file, mut bytes } => {
+                bytes.extend_from_slice(chunk);
+                if self.retained.is_truncated() {
+                    save_output(file, 0, &bytes, max_bytes)
+                } else {
+                    FullOutput::Pending { file, bytes }
+                }
+            }
+            FullOutput::Saving { file, written } => save_output(file, written, chunk, max_bytes),
+            FullOutput::Full => FullOutput::Full,
+            FullOutput::Unavailable => FullOutput::Unavailable,
+        };
+    }
+
+    fn full_output_saved(&self) -> bool {
+        matches!(
+            self.full_output,
+            FullOutput::Saving { .. } | FullOutput::Full
+        )
+    }
+}
+
+/// Appends `bytes` to `file`, which holds `written` bytes, without letting it
+/// grow past `max_bytes`.
+fn save_output(mut file: File, written: usize, bytes: &[u8], max_bytes: usize) -> FullOutput {
+    let len = bytes.len().min(max_bytes.saturating_sub(written));
+    if file.write_all(&bytes[..len]).is_err() {
+        return FullOutput::Unavailable;
+    }
+    if len == bytes.len() {
+        return FullOutput::Saving {
+            file,
+            written: written + len,
+        };
+    }
+    let note =
+        format!("\n[... output truncated: only the first {max_bytes} bytes were saved ...]\n");
+    match file.write_all(note.as_bytes()) {
+        Ok(()) => FullOutput::Full,
+        Err(_) => FullOutput::Unavailable,
+    }
+}
+
+/// Creates a new file at `path` that only the current user can read.
+fn create_private_file(path: &Path) -> io::Result<File> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+/// How much more of the output of a command, across all of its streams, may
+/// be forwarded to its [`ExecOutputSink`].
+struct OutputDeltaBudget {
+    bytes: usize,
+    lines: usize,
+    exhausted: bool,
+}
+
+impl OutputDeltaBudget {
+    fn new(limits: ShellOutputLimits) -> Self {
+        Self {
+            bytes: limits.max_bytes,
+            lines: limits.max_lines,
+            exhausted: false,
+        }
+    }
+
+    /// Length of the part of `chunk` that is within the budget, which is
+    /// deducted from it.
+    fn take(&mut self, chunk: &[u8]) -> usize {
+        if self.exhausted {
+            return 0;
+        }
+        let mut len = chunk.len().min(self.bytes);
+        // Stop after the last line that is within the budget.
+        if self.lines == 0 {
+            len = 0;
+        } else if let Some((index, _)) = chunk[..len]
+            .iter()
+            .enumerate()
+            .filter(|(_, byte)| **byte == b'\n')
+            .nth(self.lines - 1)
+        {
+            len = index + 1;
+        }
+        let newlines = chunk[..len].iter().filter(|byte| **byte == b'\n').count();
+        self.bytes -= len;
+        self.lines -= newlines;
+        self.exhausted = len < chunk.len();
+        len
+    }
+}
+
+/// Buffers the output read from one stream of a running command and forwards
+/// it to an [`ExecOutputSink`] at most once per [`OUTPUT_DELTA_INTERVAL`], in
+/// chunks of at most [`MAX_OUTPUT_DELTA_BYTES`], until the budget shared with
+/// the other streams runs out.
+struct OutputDeltaEmitter {
+    sink: ExecOutputSink,
+    stream: ExecOutputStream,
+    pending: Vec<u8>,
+    last_emit: tokio::time::Instant,
+    budget: Arc<Mutex<OutputDeltaBudget>>,
+}
+
+impl OutputDeltaEmitter {
+    fn new(
+        sink: ExecOutputSink,
+        stream: ExecOutputStream,
+        budget: Arc<Mutex<OutputDeltaBudget>>,
+    ) -> Self {
+        Self {
+            sink,
+            stream,
+            pending: Vec::new(),
+            budget,
+            // Allow the first chunk to be sent right away.
+            last_emit: tokio::time::Instant::now()
+                .checked_sub(OUTPUT_DELTA_INTERVAL)
+                .unwrap_or_else(tokio::time::Instant::now),
+        }
+    }
+
+    fn next_emit(&self) -> tokio::time::Instant {
+        self.last_emit + OUTPUT_DELTA_INTERVAL
+    }
+
+    /// Adds `chunk` to the pending output, or as much of it as the budget
+    /// allows. The first stream to run out of budget adds the marker.
+    fn push(&mut self, chunk: &[u8]) {
+        #[expect(clippy::unwrap_used)]
+        let mut budget = self.budget.lock().unwrap();
+        if budget.exhausted {
+            return;
+        }
+        let len = budget.take(chunk);
+        self.pending.extend_from_slice(&chunk[..len]);
+        if budget.exhausted {
+            self.pending
+                .extend_from_slice(OUTPUT_DELTAS_TRUNCATED_MARKER.as_bytes());
+        }
+    }
+
+    /// Number of pending bytes that can be sent in the next delta without
+    /// splitting a UTF-8 character. At EOF, the rest is sent (lossily)
+    /// regardless.
+    fn emittable_len(&self, eof: bool) -> usize {
+        let len = self.pending.len().min(MAX_OUTPUT_DELTA_BYTES);
+        let rest = eof && len == self.pending.len();
+        match std::str::from_utf8(&self.pending[..len]) {
+            Ok(_) => len,
+            Err(e) if e.error_len().is_none() && !rest => e.valid_up_to(),
+            Err(_) => len,
+        }
+    }
+
+    async fn emit(&mut self, eof: bool) {
+        let len = self.emittable_len(eof);
+        if len == 0 {
+            return;
+        }
+        let bytes: Vec<u8> = self.pending.drain(..len).collect();
+        self.last_emit = tokio::time::Instant::now();
+        let event = Event {
+            id: self.sink.sub_id.clone(),
+            msg: EventMsg::ExecCommandOutputDelta(ExecCommandOutputDeltaEvent {
+                call_id: self.sink.call_id.clone(),
+                stream: self.stream,
+                chunk: String::from_utf8_lossy(&bytes).into_owned(),
+            }),
+        };
+        let _ = self.sink.tx_event.send(event).await;
+    }
+}
+
+/// Reads `reader` to EOF and returns its output, truncated to `limits`. The
+/// output is also added to `aggregated` as it is read.
 async fn read_capped<R: AsyncRead + Unpin>(
     mut reader: R,
-    max_output: usize,
-    max_lines: usize,
+    limits: ShellOutputLimits,
+    aggregated: Arc<Mutex<AggregatedOutput>>,
+    mut emitter: Option<OutputDeltaEmitter>,
 ) -> io::Result<Vec<u8>> {
-    let mut buf = Vec::with_capacity(max_output.min(8 * 1024));
+    let mut retained = HeadTailBuffer::new(limits);
     let mut tmp = [0u8; 8192];
 
-    let mut remaining_bytes = max_output;
-    let mut remaining_lines = max_lines;
-
     loop {
-        let n = reader.read(&mut tmp).await?;
+        let read = reader.read(&mut tmp);
+        let n = match emitter.as_mut() {
+            // Output is being held back because of the rate limit: send it
+            // once the interval has passed even if the command goes quiet.
+            Some(emitter) if emitter.emittable_len(false) > 0 => {
+                match tokio::time::timeout_at(emitter.next_emit(), read).await {
+                    Ok(n) => n?,
+                    Err(_) => {
+                        emitter.emit(false).await;
+                        continue;
+                    }
+                }
+            }
+            _ => read.await?,
+        };
         if n == 0 {
             break;
         }
 
-        // Copy into the buffer only while we still have byte and line budget.
-        if remaining_bytes > 0 && remaining_lines > 0 {
-            let mut copy_len = 0;
-            for &b in &tmp[..n] {
-                if remaining_bytes == 0 || remaining_lines == 0 {
-                    break;
-                }
-                copy_len += 1;
-                remaining_bytes -= 1;
-                if b == b'\n' {
-                    remaining_lines -= 1;
-                }
+        if let Some(emitter) = emitter.as_mut() {
+            emitter.push(&tmp[..n]);
+            // A full delta is sent right away so that `pending` stays small.
+            while emitter.pending.len() >= MAX_OUTPUT_DELTA_BYTES {
+                emitter.emit(false).await;
+            }
+            if tokio::time::Instant::now() >= emitter.next_emit() {
+                emitter.emit(false).await;
             }
-            buf.extend_from_slice(&tmp[..copy_len]);
         }
-        // Continue reading to EOF to avoid back-pressure, but discard once caps are hit.
+
+        // Continue reading to EOF to avoid back-pressure; the buffers discard
+        // the middle of the output once the limits are hit.
+        retained.push(&tmp[..n]);
+        #[expect(clippy::unwrap_used)]
+        aggregated.lock().unwrap().push(&tmp[..n]);
+    }
+
+    if let Some(emitter) = emitter.as_mut() {
+        while !emitter.pending.is_empty() {
+            emitter.emit(true).await;
+        }
     }
 
-    Ok(buf)
+    Ok(retained.to_bytes())
 }
 
 #[cfg(unix)]
@@ -451,3 +1192,497 @@ fn synthetic_exit_status(code: i32) -> ExitStatus {
     #[expect(clippy::unwrap_used)]
     std::process::ExitStatus::from_raw(code.try_into().unwrap())
 }
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::protocol::SandboxPermission;
+    use pretty_assertions::assert_eq;
+    use std::io::Read;
+
+    #[tokio::test]
+    async fn read_capped_streams_all_output_to_sink() {
+        let (tx_event, rx_event) = async_channel::unbounded();
+        let sink = ExecOutputSink {
+            sub_id: "sub".to_string(),
+            call_id: "call".to_string(),
+            tx_event,
+        };
+        // Exceed the line cap so that the streamed output and the captured
+        // output differ.
+        let limits = ShellOutputLimits::default();
+        let input = "héllo\n".repeat(limits.max_lines + 1);
+        let aggregated = Arc::new(Mutex::new(AggregatedOutput::new(limits, None)));
+
+        let captured = read_capped(
+            input.as_bytes(),
+            limits,
+            aggregated.clone(),
+            Some(OutputDeltaEmitter::new(
+                sink,
+                ExecOutputStream::Stderr,
+                Arc::new(Mutex::new(OutputDeltaBudget::new(limits))),
+            )),
+        )
+        .await
+        .unwrap();
+        let expected = format!(
+            "{}[... omitted 1 lines (7 bytes) ...]\n{}",
+            "héllo\n".repeat(limits.max_lines / 2),
+            "héllo\n".repeat(limits.max_lines / 2),
+        );
+        assert_eq!(String::from_utf8(captured).unwrap(), expected);
+        assert_eq!(
+            aggregated.lock().unwrap().retained.to_bytes(),
+            expected.as_bytes()
+        );
+
+        let mut streamed = String::new();
+        while let Ok(event) = rx_event.try_recv() {
+            assert_eq!(event.id, "sub");
+            match event.msg {
+                EventMsg::ExecCommandOutputDelta(ExecCommandOutputDeltaEvent {
+                    call_id,
+                    stream,
+                    chunk,
+                }) => {
+                    assert_eq!(call_id, "call");
+                    assert_eq!(stream, ExecOutputStream::Stderr);
+                    streamed.push_str(&chunk);
+                }
+                other => panic!("unexpected event: {other:?}"),
+            }
+        }
+        // Only the output within the limits is streamed.
+        let expected = format!(
+            "{}{OUTPUT_DELTAS_TRUNCATED_MARKER}",
+            "héllo\n".repeat(limits.max_lines)
+        );
+        assert_eq!(streamed, expected);
+    }
+
+    #[test]
+    fn head_tail_buffer_keeps_both_ends() {
+        let mut buffer = HeadTailBuffer::new(ShellOutputLimits {
+            max_bytes: 1024,
+            max_lines: 4,
+            ..ShellOutputLimits::default()
+        });
+        buffer.push(b"one\ntwo\n");
+        assert!(!buffer.is_truncated());
+        assert_eq!(buffer.to_bytes(), b"one\ntwo\n");
+
+        buffer.push(b"three\nfour\nfive\nsix\nseven");
+        assert_eq!(
+            String::from_utf8(buffer.to_bytes()).unwrap(),
+            "one\ntwo\n[... omitted 2 lines (11 bytes) ...]\nfive\nsix\nseven"
+        );
+
+        let mut buffer = HeadTailBuffer::new(ShellOutputLimits {
+            max_bytes: 8,
+            max_lines: 256,
+            ..ShellOutputLimits::default()
+        });
+        buffer.push(b"0123456789abcdef");
+        assert_eq!(
+            String::from_utf8(buffer.to_bytes()).unwrap(),
+            "0123\n[... omitted 0 lines (8 bytes) ...]\ncdef"
+        );
+    }
+
+    #[test]
+    fn aggregated_output_saves_everything_once_truncated() {
+        let limits = ShellOutputLimits {
+            max_bytes: 8,
+            max_lines: 256,
+            ..ShellOutputLimits::default()
+        };
+        let mut file = tempfile::tempfile().unwrap();
+
+        let mut aggregated = AggregatedOutput::new(limits, Some(file.try_clone().unwrap()));
+        aggregated.push(b"out\n");
+        aggregated.push(b"err\n");
+        assert!(!aggregated.full_output_saved());
+        aggregated.push(b"more out\n");
+        aggregated.push(b"done\n");
+        assert!(aggregated.full_output_saved());
+        let mut saved = String::new();
+        file.seek(SeekFrom::Start(0)).unwrap();
+        file.read_to_string(&mut saved).unwrap();
+        assert_eq!(saved, "out\nerr\nmore out\ndone\n");
+
+        let mut aggregated = AggregatedOutput::new(limits, None);
+        aggregated.push(b"0123456789");
+        assert!(!aggregated.full_output_saved());
+    }
+
+    #[test]
+    fn aggregated_output_stops_saving_at_the_limit() {
+        let limits = ShellOutputLimits {
+            max_bytes: 8,
+            max_lines: 256,
+            max_full_output_bytes: 16,
+        };
+        let mut file = tempfile::tempfile().unwrap();
+
+        let mut aggregated = AggregatedOutput::new(limits, Some(file.try_clone().unwrap()));
+        aggregated.push(b"0123456789");
+        aggregated.push(b"abcdefghij");
+        aggregated.push(b"klmnopqrst");
+        assert!(aggregated.full_output_saved());
+        let mut saved = String::new();
+        file.seek(SeekFrom::Start(0)).unwrap();
+        file.read_to_string(&mut saved).unwrap();
+        assert_eq!(
+            saved,
+            "0123456789abcdef\n[... output truncated: only the first 16 bytes were saved ...]\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn only_truncated_output_is_saved_until_the_session_ends() {
+        let full_output_dir = FullOutputDir::new();
+        let sandbox_policy = SandboxPolicy::new_read_only_policy();
+        let limits = ShellOutputLimits {
+            max_bytes: 1024,
+            max_lines: 4,
+            ..ShellOutputLimits::default()
+        };
+        let run = |script: &str| {
+            let params = ExecParams {
+                command: vec!["sh".to_string(), "-c".to_string(), script.to_string()],
+                cwd: std::env::current_dir().unwrap(),
+                timeout_ms: Some(10_000),
+                env: std::env::vars().collect(),
+                stdin: None,
+            };
+            process_exec_tool_call(
+                params,
+                SandboxType::None,
+                Arc::new(Notify::new()),
+                &sandbox_policy,
+                limits,
+                &full_output_dir,
+                None,
+            )
+        };
+
+        let output = run("echo short").await.unwrap();
+        assert_eq!(output.full_output_path, None);
+        assert!(!full_output_dir.path.exists());
+
+        let output = run("seq 1 10").await.unwrap();
+        let path = output.full_output_path.unwrap();
+        assert!(path.starts_with(&full_output_dir.path));
+        let expected: String = (1..=10).map(|n| format!("{n}\n")).collect();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), expected);
+
+        drop(full_output_dir);
+        assert!(!path.exists());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn failure_in_sandbox_keeps_the_output() {
+        let full_output_dir = FullOutputDir::new();
+        let params = ExecParams {
+            command: vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                "seq 1 10; exit 3".to_string(),
+            ],
+            cwd: std::env::current_dir().unwrap(),
+            timeout_ms: Some(10_000),
+            env: std::env::vars().collect(),
+            stdin: None,
+        };
+        let result = process_exec_tool_call(
+            params,
+            SandboxType::LinuxSeccomp,
+            Arc::new(Notify::new()),
+            &SandboxPolicy::new_read_only_policy(),
+            ShellOutputLimits {
+                max_bytes: 1024,
+                max_lines: 4,
+                ..ShellOutputLimits::default()
+            },
+            &full_output_dir,
+            None,
+        )
+        .await;
+        let Err(CodexErr::Sandbox(SandboxErr::Denied(output))) = result else {
+            panic!("unexpected result: {result:?}");
+        };
+        assert_eq!(output.exit_code, 3);
+        assert_eq!(
+            output.aggregated_output,
+            "1\n2\n[... omitted 6 lines (12 bytes) ...]\n9\n10\n"
+        );
+        let path = output.full_output_path.unwrap();
+        let expected: String = (1..=10).map(|n| format!("{n}\n")).collect();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), expected);
+    }
+
+    #[test]
+    fn full_output_dir_is_only_readable_if_the_sandbox_allows_it() {
+        let full_output_dir = FullOutputDir::new();
+        let cwd = std::env::current_dir().unwrap();
+        assert!(full_output_dir.is_readable_under(&SandboxPolicy::new_read_only_policy(), &cwd));
+
+        let temp_dir = std::env::temp_dir();
+        let restricted = SandboxPolicy::from(vec![]);
+        assert!(!full_output_dir.is_readable_under(&restricted, &cwd));
+        let restricted = SandboxPolicy::from(vec![SandboxPermission::DiskReadFolder {
+            folder: temp_dir.clone(),
+        }]);
+        assert!(full_output_dir.is_readable_under(&restricted, &cwd));
+        let restricted = SandboxPolicy::from(vec![SandboxPermission::DiskWriteFolder {
+            folder: temp_dir,
+        }]);
+        assert!(full_output_dir.is_readable_under(&restricted, &cwd));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn timeout_stops_the_whole_process_group() {
+        let params = ExecParams {
+            // The background `sleep` inherits the output pipes, so the call
+            // only returns early if it is killed too.
+            command: vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                "sleep 30 & sleep 30".to_string(),
+            ],
+            cwd: std::env::current_dir().unwrap(),
+            timeout_ms: Some(200),
+            env: std::env::vars().collect(),
+            stdin: None,
+        };
+        let start = Instant::now();
+        let result = process_exec_tool_call(
+            params,
+            SandboxType::None,
+            Arc::new(Notify::new()),
+            &SandboxPolicy::new_read_only_policy(),
+            ShellOutputLimits::default(),
+            &FullOutputDir::new(),
+            None,
+        )
+        .await;
+        assert!(
+            matches!(
+                result,
+                Err(CodexErr::Sandbox(SandboxErr::Timeout {
+                    timeout_ms: 200,
+                    ..
+                }))
+            ),
+            "unexpected result: {result:?}"
+        );
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn timeout_keeps_the_output_so_far() {
+        let params = ExecParams {
+            command: vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                "echo started; echo working >&2; sleep 30".to_string(),
+            ],
+            cwd: std::env::current_dir().unwrap(),
+            timeout_ms: Some(200),
+            env: std::env::vars().collect(),
+            stdin: None,
+        };
+        let result = process_exec_tool_call(
+            params,
+            SandboxType::None,
+            Arc::new(Notify::new()),
+            &SandboxPolicy::new_read_only_policy(),
+            ShellOutputLimits::default(),
+            &FullOutputDir::new(),
+            None,
+        )
+        .await;
+        let Err(CodexErr::Sandbox(SandboxErr::Timeout { output, .. })) = result else {
+            panic!("unexpected result: {result:?}");
+        };
+        assert_eq!(output.stdout, "started\n");
+        assert_eq!(output.stderr, "working\n");
+        assert_eq!(output.aggregated_output, "started\nworking\n");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn aborting_the_call_kills_the_whole_process_group() {
+        let dir = tempfile::tempdir().unwrap();
+        let pid_file = dir.path().join("pid");
+        let params = ExecParams {
+            command: vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                format!("sleep 30 & echo $! > {}; wait", pid_file.display()),
+            ],
+            cwd: std::env::current_dir().unwrap(),
+            timeout_ms: Some(30_000),
+            env: std::env::vars().collect(),
+            stdin: None,
+        };
+        let call = tokio::spawn(async move {
+            process_exec_tool_call(
+                params,
+                SandboxType::None,
+                Arc::new(Notify::new()),
+                &SandboxPolicy::new_read_only_policy(),
+                ShellOutputLimits::default(),
+                &FullOutputDir::new(),
+                None,
+            )
+            .await
+        });
+
+        let start = Instant::now();
+        let grandchild = loop {
+            if let Some(pid) = std::fs::read_to_string(&pid_file)
+                .ok()
+                .and_then(|pid| pid.trim().parse::<u32>().ok())
+            {
+                break pid;
+            }
+            assert!(start.elapsed() < Duration::from_secs(10));
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        call.abort();
+        assert!(call.await.unwrap_err().is_cancelled());
+
+        // Once killed, the grandchild is gone or a zombie waiting for its new
+        // parent to reap it.
+        let is_running = || {
+            std::fs::read_to_string(format!("/proc/{grandchild}/stat"))
+                .is_ok_and(|stat| !stat.rsplit(')').next().unwrap_or("").starts_with(" Z"))
+        };
+        while is_running() {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn stdin_is_written_to_the_command() {
+        // Larger than a single argument may be on Linux.
+        let input = "x".repeat(256 * 1024);
+        let params = ExecParams {
+            command: vec!["wc".to_string(), "-c".to_string()],
+            cwd: std::env::current_dir().unwrap(),
+            timeout_ms: Some(10_000),
+            env: std::env::vars().collect(),
+            stdin: Some(input.clone()),
+        };
+        let output = process_exec_tool_call(
+            params,
+            SandboxType::None,
+            Arc::new(Notify::new()),
+            &SandboxPolicy::new_read_only_policy(),
+            ShellOutputLimits::default(),
+            &FullOutputDir::new(),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(output.stdout.trim(), input.len().to_string());
+    }
+
+    #[test]
+    fn emitter_does_not_split_utf8_characters() {
+        let (tx_event, _rx_event) = async_channel::unbounded();
+        let sink = ExecOutputSink {
+            sub_id: "sub".to_string(),
+            call_id: "call".to_string(),
+            tx_event,
+        };
+        let budget = OutputDeltaBudget::new(ShellOutputLimits::default());
+        let mut emitter =
+            OutputDeltaEmitter::new(sink, ExecOutputStream::Stdout, Arc::new(Mutex::new(budget)));
+        // "é" is two bytes; only the first one has been read so far.
+        emitter.pending.extend_from_slice(&"abé".as_bytes()[..3]);
+        assert_eq!(emitter.emittable_len(false), 2);
+        assert_eq!(emitter.emittable_len(true), 3);
+    }
+
+    #[tokio::test]
+    async fn deltas_are_capped_in_size_and_in_total() {
+        let (tx_event, rx_event) = async_channel::unbounded();
+        let sink = ExecOutputSink {
+            sub_id: "sub".to_string(),
+            call_id: "call".to_string(),
+            tx_event,
+        };
+        let limits = ShellOutputLimits {
+            max_bytes: 3 * MAX_OUTPUT_DELTA_BYTES,
+            max_lines: 256,
+            ..ShellOutputLimits::default()
+        };
+        let budget = Arc::new(Mutex::new(OutputDeltaBudget::new(limits)));
+        let input = "y".repeat(10 * MAX_OUTPUT_DELTA_BYTES);
+
+        read_capped(
+            input.as_bytes(),
+            limits,
+            Arc::new(Mutex::new(AggregatedOutput::new(limits, None))),
+            Some(OutputDeltaEmitter::new(
+                sink.clone(),
+                ExecOutputStream::Stdout,
+                budget.clone(),
+            )),
+        )
+        .await
+        .unwrap();
+        // The budget is shared with the other stream, which gets nothing.
+        read_capped(
+            "more".as_bytes(),
+            limits,
+            Arc::new(Mutex::new(AggregatedOutput::new(limits, None))),
+            Some(OutputDeltaEmitter::new(
+                sink,
+                ExecOutputStream::Stderr,
+                budget,
+            )),
+        )
+        .await
+        .unwrap();
+
+        let mut streamed = String::new();
+        while let Ok(event) = rx_event.try_recv() {
+            let EventMsg::ExecCommandOutputDelta(delta) = event.msg else {
+                panic!("unexpected event: {:?}", event.msg);
+            };
+            assert_eq!(delta.stream, ExecOutputStream::Stdout);
+            assert!(delta.chunk.len() <= MAX_OUTPUT_DELTA_BYTES);
+            streamed.push_str(&delta.chunk);
+        }
+        let expected = format!(
+            "{}{OUTPUT_DELTAS_TRUNCATED_MARKER}",
+            &input[..limits.max_bytes]
+        );
+        assert_eq!(streamed, expected);
+    }
+
+    #[test]
+    fn delta_budget_stops_after_the_last_line_within_the_limit() {
+        let mut budget = OutputDeltaBudget::new(ShellOutputLimits {
+            max_bytes: 1024,
+            max_lines: 2,
+            ..ShellOutputLimits::default()
+        });
+        assert_eq!(budget.take(b"a\nb"), 3);
+        assert_eq!(budget.take(b"c\nd\ne\n"), 2);
+        assert!(budget.exhausted);
+        assert_eq!(budget.take(b"f\n"), 0);
+    }
+}
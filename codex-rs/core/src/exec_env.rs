@@ -0,0 +1,170 @@
+//! Builds the environment of the commands that Codex runs for the model
+//! according to the user's [`ShellEnvironmentPolicy`].
+
+use std::collections::HashMap;
+
+use glob::MatchOptions;
+use glob::Pattern;
+
+use crate::config::ShellEnvironmentPolicy;
+use crate::config::ShellEnvironmentPolicyInherit;
+
+/// Variables kept by [`ShellEnvironmentPolicyInherit::Core`].
+#[cfg(unix)]
+const CORE_ENV_VARS: &[&str] = &[
+    "HOME", "LANG", "LC_ALL", "LOGNAME", "PATH", "SHELL", "TERM", "TMPDIR", "TZ", "USER",
+];
+
+#[cfg(windows)]
+const CORE_ENV_VARS: &[&str] = &[
+    "PATH",
+    "PATHEXT",
+    "SYSTEMROOT",
+    "TEMP",
+    "TMP",
+    "USERDOMAIN",
+    "USERNAME",
+    "USERPROFILE",
+];
+
+/// Inherited variables that are removed unless the policy sets
+/// `ignore_default_excludes`, so that API keys and the like are not handed to
+/// commands the model runs.
+const DEFAULT_EXCLUDES: &[&str] = &["*KEY*", "*SECRET*", "*TOKEN*"];
+
+/// Environment variable names are matched ignoring case.
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: false,
+    require_literal_separator: false,
+    require_literal_leading_dot: false,
+};
+
+/// The environment for a command, derived from Codex's own environment.
+pub fn create_env(policy: &ShellEnvironmentPolicy) -> HashMap<String, String> {
+    populate_env(std::env::vars(), policy)
+}
+
+fn populate_env<I>(vars: I, policy: &ShellEnvironmentPolicy) -> HashMap<String, String>
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    let matches_any = |name: &str, patterns: &[Pattern]| {
+        patterns
+            .iter()
+            .any(|pattern| pattern.matches_with(name, MATCH_OPTIONS))
+    };
+
+    let mut env: HashMap<String, String> = match policy.inherit {
+        ShellEnvironmentPolicyInherit::All => vars.into_iter().collect(),
+        ShellEnvironmentPolicyInherit::Core => vars
+            .into_iter()
+            .filter(|(name, _)| CORE_ENV_VARS.contains(&name.as_str()))
+            .collect(),
+        ShellEnvironmentPolicyInherit::None => HashMap::new(),
+    };
+
+    if !policy.ignore_default_excludes {
+        let default_excludes: Vec<Pattern> = DEFAULT_EXCLUDES
+            .iter()
+            .filter_map(|pattern| Pattern::new(pattern).ok())
+            .collect();
+        env.retain(|name, _| !matches_any(name, &default_excludes));
+    }
+    env.retain(|name, _| !matches_any(name, &policy.exclude));
+
+    env.extend(policy.set.clone());
+
+    if !policy.include_only.is_empty() {
+        env.retain(|name, _| matches_any(name, &policy.include_only));
+    }
+    env
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn patterns(patterns: &[&str]) -> Vec<Pattern> {
+        patterns.iter().map(|p| Pattern::new(p).unwrap()).collect()
+    }
+
+    #[test]
+    fn default_policy_removes_secrets() {
+        let env = populate_env(
+            vars(&[
+                ("PATH", "/usr/bin"),
+                ("OPENAI_API_KEY", "sk-secret"),
+                ("GITHUB_COPILOT_TOKEN", "ghu-secret"),
+                ("aws_secret_access_key", "secret"),
+                ("EDITOR", "vim"),
+            ]),
+            &ShellEnvironmentPolicy::default(),
+        );
+        assert_eq!(
+            env,
+            vars(&[("PATH", "/usr/bin"), ("EDITOR", "vim")])
+                .into_iter()
+                .collect::<HashMap<_, _>>()
+        );
+    }
+
+    #[test]
+    fn policy_is_applied_in_order() {
+        let policy = ShellEnvironmentPolicy {
+            inherit: ShellEnvironmentPolicyInherit::Core,
+            ignore_default_excludes: true,
+            exclude: patterns(&["TERM"]),
+            set: HashMap::from([
+                ("CI".to_string(), "1".to_string()),
+                ("NPM_TOKEN".to_string(), "explicit".to_string()),
+                ("PATH".to_string(), "/opt/bin".to_string()),
+            ]),
+            include_only: patterns(&["ci", "HOME", "PATH", "TERM", "*_TOKEN"]),
+        };
+        let env = populate_env(
+            vars(&[
+                ("HOME", "/home/user"),
+                ("PATH", "/usr/bin"),
+                ("TERM", "xterm"),
+                ("USER", "user"),
+                ("EDITOR", "vim"),
+            ]),
+            &policy,
+        );
+        assert_eq!(
+            env,
+            vars(&[
+                ("HOME", "/home/user"),
+                ("PATH", "/opt/bin"),
+                ("CI", "1"),
+                ("NPM_TOKEN", "explicit"),
+            ])
+            .into_iter()
+            .collect::<HashMap<_, _>>()
+        );
+    }
+
+    #[test]
+    fn inherit_none_only_keeps_set_variables() {
+        let policy = ShellEnvironmentPolicy {
+            inherit: ShellEnvironmentPolicyInherit::None,
+            set: HashMap::from([("PATH".to_string(), "/bin".to_string())]),
+            ..Default::default()
+        };
+        let env = populate_env(vars(&[("HOME", "/home/user")]), &policy);
+        assert_eq!(
+            env,
+            HashMap::from([("PATH".to_string(), "/bin".to_string())])
+        );
+    }
+}
@@ -1,22 +1,33 @@
+use std::collections::HashMap;
 use std::io;
 use std::path::Path;
 use std::sync::Arc;
 
 use crate::error::CodexErr;
 use crate::error::Result;
+use crate::exec::ExecOutputSink;
 use crate::exec::ExecParams;
+use crate::exec::OutputCapture;
 use crate::exec::RawExecToolCallOutput;
 use crate::exec::StdioPolicy;
 use crate::exec::consume_truncated_output;
 use crate::exec::spawn_child_async;
 use crate::protocol::SandboxPolicy;
 
+use tokio::process::Child;
 use tokio::sync::Notify;
 
+/// Runs the command described by `params` under Landlock. Unless the policy
+/// grants full network access, the network is blocked either with a seccomp
+/// filter or, if `network_namespace` is true, by running the command in its
+/// own user and network namespaces.
 pub fn exec_linux(
     params: ExecParams,
     ctrl_c: Arc<Notify>,
     sandbox_policy: &SandboxPolicy,
+    capture: OutputCapture,
+    output_sink: Option<ExecOutputSink>,
+    network_namespace: bool,
 ) -> Result<RawExecToolCallOutput> {
     // Allow READ on /
     // Allow WRITE on /dev/null
@@ -34,16 +45,19 @@ pub fn exec_linux(
                 command,
                 cwd,
                 timeout_ms,
+                env,
+                stdin,
             } = params;
-            apply_sandbox_policy_to_current_thread(&sandbox_policy, &cwd)?;
-            let child = spawn_child_async(
-                command,
-                cwd,
-                &sandbox_policy,
-                StdioPolicy::RedirectForShellTool,
-            )
-            .await?;
-            consume_truncated_output(child, ctrl_c_copy, timeout_ms).await
+            let stdio_policy = StdioPolicy::for_shell_tool(&stdin);
+            let child = if network_namespace {
+                spawn_child_in_network_namespace(command, cwd, &sandbox_policy, stdio_policy, env)
+                    .await?
+            } else {
+                apply_sandbox_policy_to_current_thread(&sandbox_policy, &cwd)?;
+                spawn_child_async(command, cwd, &sandbox_policy, stdio_policy, env).await?
+            };
+            consume_truncated_output(child, ctrl_c_copy, timeout_ms, stdin, capture, output_sink)
+                .await
         })
     })
     .join();
@@ -57,6 +71,59 @@ pub fn exec_linux(
     }
 }
 
+/// Spawns `cmd` (see [`crate::exec::create_terminal_command`]) under Landlock
+/// and blocks the network like [`exec_linux`] does, but returns the child
+/// right away instead of waiting for it.
+#[cfg(target_os = "linux")]
+pub(crate) fn spawn_terminal_child_linux(
+    mut cmd: std::process::Command,
+    cwd: &Path,
+    sandbox_policy: &SandboxPolicy,
+    network_namespace: bool,
+) -> Result<std::process::Child> {
+    use std::os::unix::process::CommandExt;
+
+    let cwd = cwd.to_path_buf();
+    let sandbox_policy = sandbox_policy.clone();
+
+    // As in `exec_linux()`, the sandbox is applied to a dedicated thread.
+    let child = std::thread::spawn(move || {
+        if network_namespace {
+            let pre_exec =
+                crate::landlock::create_network_namespace_pre_exec(&sandbox_policy, &cwd)?;
+            // SAFETY: the hook only makes raw system calls on memory that was
+            // prepared before the fork.
+            unsafe {
+                cmd.pre_exec(pre_exec);
+            }
+        } else {
+            apply_sandbox_policy_to_current_thread(&sandbox_policy, &cwd)?;
+        }
+        Ok(cmd.spawn()?)
+    })
+    .join();
+
+    match child {
+        Ok(result) => result,
+        Err(e) => Err(CodexErr::Io(io::Error::other(format!(
+            "thread join failed: {e:?}"
+        )))),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn spawn_terminal_child_linux(
+    _cmd: std::process::Command,
+    _cwd: &Path,
+    _sandbox_policy: &SandboxPolicy,
+    _network_namespace: bool,
+) -> Result<std::process::Child> {
+    Err(CodexErr::Io(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "linux sandbox is not supported on this platform",
+    )))
+}
+
 #[cfg(target_os = "linux")]
 pub fn apply_sandbox_policy_to_current_thread(
     sandbox_policy: &SandboxPolicy,
@@ -75,3 +142,38 @@ pub fn apply_sandbox_policy_to_current_thread(
         "linux sandbox is not supported on this platform",
     )))
 }
+
+#[cfg(target_os = "linux")]
+async fn spawn_child_in_network_namespace(
+    command: Vec<String>,
+    cwd: std::path::PathBuf,
+    sandbox_policy: &SandboxPolicy,
+    stdio_policy: StdioPolicy,
+    env: HashMap<String, String>,
+) -> Result<Child> {
+    let pre_exec = crate::landlock::create_network_namespace_pre_exec(sandbox_policy, &cwd)?;
+    let child = crate::exec::spawn_child_async_with_pre_exec(
+        command,
+        cwd,
+        sandbox_policy,
+        stdio_policy,
+        env,
+        pre_exec,
+    )
+    .await?;
+    Ok(child)
+}
+
+#[cfg(not(target_os = "linux"))]
+async fn spawn_child_in_network_namespace(
+    _command: Vec<String>,
+    _cwd: std::path::PathBuf,
+    _sandbox_policy: &SandboxPolicy,
+    _stdio_policy: StdioPolicy,
+    _env: HashMap<String, String>,
+) -> Result<Child> {
+    Err(CodexErr::Io(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "linux sandbox is not supported on this platform",
+    )))
+}
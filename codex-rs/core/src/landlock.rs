@@ -1,4 +1,6 @@
 use std::collections::BTreeMap;
+use std::ffi::CStr;
+use std::io;
 use std::path::Path;
 use std::path::PathBuf;
 
@@ -14,7 +16,9 @@ use landlock::CompatLevel;
 use landlock::Compatible;
 use landlock::Ruleset;
 use landlock::RulesetAttr;
+use landlock::RulesetCreated;
 use landlock::RulesetCreatedAttr;
+use landlock::RulesetStatus;
 use seccompiler::BpfProgram;
 use seccompiler::SeccompAction;
 use seccompiler::SeccompCmpArgLen;
@@ -25,6 +29,50 @@ use seccompiler::SeccompRule;
 use seccompiler::TargetArch;
 use seccompiler::apply_filter;
 
+/// System paths that commands may read even when the sandbox policy does not
+/// grant full read access: programs, shared libraries, and the configuration
+/// they commonly need (dynamic linker cache, TLS certificates, name service).
+///
+/// Only system-wide entries of `/proc` are listed: the per-process ones would
+/// expose the environment of Codex itself, including API keys. (`/proc/self`
+/// cannot be granted either, as it would resolve to Codex's own entry when the
+/// rule is created.)
+const READABLE_SYSTEM_PATHS: &[&str] = &[
+    "/bin",
+    "/sbin",
+    "/usr",
+    "/lib",
+    "/lib32",
+    "/lib64",
+    "/opt",
+    "/dev",
+    "/proc/cpuinfo",
+    "/proc/filesystems",
+    "/proc/loadavg",
+    "/proc/meminfo",
+    "/proc/stat",
+    "/proc/sys",
+    "/proc/uptime",
+    "/proc/version",
+    "/etc/alternatives",
+    "/etc/ca-certificates",
+    "/etc/ssl",
+    "/etc/pki",
+    "/etc/ld.so.cache",
+    "/etc/ld.so.conf",
+    "/etc/ld.so.conf.d",
+    "/etc/localtime",
+    "/etc/passwd",
+    "/etc/group",
+    "/etc/nsswitch.conf",
+    "/etc/hosts",
+    "/etc/resolv.conf",
+    "/etc/profile",
+    "/etc/profile.d",
+    "/etc/bash.bashrc",
+    "/etc/inputrc",
+];
+
 /// Apply sandbox policies inside this thread so only the child inherits
 /// them, not the entire CLI process.
 pub(crate) fn apply_sandbox_policy_to_current_thread(
@@ -35,47 +83,195 @@ pub(crate) fn apply_sandbox_policy_to_current_thread(
         install_network_seccomp_filter_on_current_thread()?;
     }
 
-    if !sandbox_policy.has_full_disk_write_access() {
-        let writable_roots = sandbox_policy.get_writable_roots_with_cwd(cwd);
-        install_filesystem_landlock_rules_on_current_thread(writable_roots)?;
+    if let Some(ruleset) = create_filesystem_landlock_ruleset(sandbox_policy, cwd)? {
+        install_filesystem_landlock_rules_on_current_thread(ruleset)?;
     }
 
-    // TODO(ragona): Add appropriate restrictions if
-    // `sandbox_policy.has_full_disk_read_access()` is `false`.
-
     Ok(())
 }
 
-/// Installs Landlock file-system rules on the current thread allowing read
-/// access to the entire file-system while restricting write access to
-/// `/dev/null` and the provided list of `writable_roots`.
+/// Installs the Landlock file-system `ruleset` on the current thread.
 ///
 /// # Errors
 /// Returns [`CodexErr::Sandbox`] variants when the ruleset fails to apply.
-fn install_filesystem_landlock_rules_on_current_thread(writable_roots: Vec<PathBuf>) -> Result<()> {
+fn install_filesystem_landlock_rules_on_current_thread(ruleset: RulesetCreated) -> Result<()> {
+    let status = ruleset.restrict_self()?;
+
+    if status.ruleset == RulesetStatus::NotEnforced {
+        return Err(CodexErr::Sandbox(SandboxErr::LandlockRestrict));
+    }
+
+    Ok(())
+}
+
+/// Creates the Landlock file-system ruleset for `sandbox_policy`, or `None`
+/// if the policy grants full read and write access.
+///
+/// Without full read access, only the cwd, the folders granted by
+/// `DiskReadFolder`, and [`READABLE_SYSTEM_PATHS`] can be read. Without full
+/// write access, only `/dev/null` and the writable roots can be written (and
+/// read).
+fn create_filesystem_landlock_ruleset(
+    sandbox_policy: &SandboxPolicy,
+    cwd: &Path,
+) -> Result<Option<RulesetCreated>> {
+    let full_read = sandbox_policy.has_full_disk_read_access();
+    let full_write = sandbox_policy.has_full_disk_write_access();
+    if full_read && full_write {
+        return Ok(None);
+    }
+
     let abi = ABI::V5;
     let access_rw = AccessFs::from_all(abi);
     let access_ro = AccessFs::from_read(abi);
+    let access_wo = AccessFs::from_write(abi);
 
     let mut ruleset = Ruleset::default()
         .set_compatibility(CompatLevel::BestEffort)
         .handle_access(access_rw)?
         .create()?
-        .add_rules(landlock::path_beneath_rules(&["/"], access_ro))?
-        .add_rules(landlock::path_beneath_rules(&["/dev/null"], access_rw))?
         .set_no_new_privs(true);
 
-    if !writable_roots.is_empty() {
-        ruleset = ruleset.add_rules(landlock::path_beneath_rules(&writable_roots, access_rw))?;
+    if full_read {
+        ruleset = ruleset.add_rules(landlock::path_beneath_rules(&["/"], access_ro))?;
+    } else {
+        // Opening a path that does not exist fails, so skip those.
+        let readable_roots: Vec<PathBuf> = sandbox_policy
+            .get_readable_roots_with_cwd(cwd)
+            .into_iter()
+            .chain(READABLE_SYSTEM_PATHS.iter().map(PathBuf::from))
+            .filter(|path| path.exists())
+            .collect();
+        ruleset = ruleset.add_rules(landlock::path_beneath_rules(&readable_roots, access_ro))?;
     }
 
-    let status = ruleset.restrict_self()?;
+    if full_write {
+        ruleset = ruleset.add_rules(landlock::path_beneath_rules(&["/"], access_wo))?;
+    } else {
+        ruleset = ruleset.add_rules(landlock::path_beneath_rules(&["/dev/null"], access_rw))?;
 
-    if status.ruleset == landlock::RulesetStatus::NotEnforced {
-        return Err(CodexErr::Sandbox(SandboxErr::LandlockRestrict));
+        let writable_roots = sandbox_policy.get_writable_roots_with_cwd(cwd);
+        if !writable_roots.is_empty() {
+            ruleset =
+                ruleset.add_rules(landlock::path_beneath_rules(&writable_roots, access_rw))?;
+        }
     }
 
-    Ok(())
+    Ok(Some(ruleset))
+}
+
+/// Alternative to [`apply_sandbox_policy_to_current_thread`] that blocks the
+/// network by moving the child into fresh user and network namespaces, where
+/// only a loopback interface exists, instead of denying socket syscalls. Unix
+/// domain sockets and `localhost` therefore keep working.
+///
+/// Unprivileged user namespaces can only be created by single-threaded
+/// processes, so the namespaces (and the Landlock rules, which would otherwise
+/// forbid writing the uid/gid maps) are set up by the returned hook, which
+/// must be run in the child between `fork()` and `exec()`. Everything that
+/// allocates is prepared here, before the fork. When the network is
+/// restricted, a seccomp filter that blocks `ptrace` is installed on the
+/// current thread so that the child inherits it.
+pub(crate) fn create_network_namespace_pre_exec(
+    sandbox_policy: &SandboxPolicy,
+    cwd: &Path,
+) -> Result<impl FnMut() -> io::Result<()> + Send + Sync + 'static> {
+    let network_namespace = if sandbox_policy.has_full_network_access() {
+        None
+    } else {
+        // The namespace takes care of the network, but the child must still
+        // not be able to ptrace processes outside of it.
+        install_ptrace_seccomp_filter_on_current_thread()?;
+
+        // SAFETY: `getuid()` and `getgid()` cannot fail.
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        Some(IdMaps {
+            uid_map: format!("{uid} {uid} 1").into_bytes(),
+            gid_map: format!("{gid} {gid} 1").into_bytes(),
+        })
+    };
+
+    let mut ruleset = create_filesystem_landlock_ruleset(sandbox_policy, cwd)?;
+
+    Ok(move || {
+        if let Some(id_maps) = &network_namespace {
+            enter_network_namespace(id_maps)?;
+        }
+        if let Some(ruleset) = ruleset.take() {
+            let status = ruleset.restrict_self().map_err(io::Error::other)?;
+            if status.ruleset == RulesetStatus::NotEnforced {
+                return Err(io::Error::other(SandboxErr::LandlockRestrict));
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Contents of `/proc/self/{uid,gid}_map` that map the current user and group
+/// to themselves inside the new user namespace.
+struct IdMaps {
+    uid_map: Vec<u8>,
+    gid_map: Vec<u8>,
+}
+
+/// Moves the calling (single-threaded) process into new user and network
+/// namespaces and brings up the loopback interface of the latter. Only makes
+/// raw system calls, so it is safe to call between `fork()` and `exec()`.
+fn enter_network_namespace(id_maps: &IdMaps) -> io::Result<()> {
+    // SAFETY: plain system call without pointer arguments.
+    if unsafe { libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // Writing `gid_map` as an unprivileged user requires denying
+    // `setgroups(2)` first.
+    write_proc_file(c"/proc/self/setgroups", b"deny")?;
+    write_proc_file(c"/proc/self/uid_map", &id_maps.uid_map)?;
+    write_proc_file(c"/proc/self/gid_map", &id_maps.gid_map)?;
+
+    bring_up_loopback()
+}
+
+fn write_proc_file(path: &CStr, contents: &[u8]) -> io::Result<()> {
+    // SAFETY: `path` is NUL-terminated and `contents` is valid for its length.
+    unsafe {
+        let fd = libc::open(path.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let written = libc::write(fd, contents.as_ptr().cast(), contents.len());
+        let result = if written < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        };
+        libc::close(fd);
+        result
+    }
+}
+
+/// A new network namespace starts with its loopback interface down.
+fn bring_up_loopback() -> io::Result<()> {
+    // SAFETY: `ifreq` is plain old data and is fully initialized before the
+    // `ioctl()` reads it.
+    unsafe {
+        let fd = libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0);
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut ifr: libc::ifreq = std::mem::zeroed();
+        for (dst, src) in ifr.ifr_name.iter_mut().zip(b"lo") {
+            *dst = *src as libc::c_char;
+        }
+        ifr.ifr_ifru.ifru_flags = (libc::IFF_UP | libc::IFF_RUNNING) as libc::c_short;
+        let result = if libc::ioctl(fd, libc::SIOCSIFFLAGS, &ifr) < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        };
+        libc::close(fd);
+        result
+    }
 }
 
 /// Installs a seccomp filter that blocks outbound network access except for
@@ -100,7 +296,6 @@ fn install_network_seccomp_filter_on_current_thread() -> std::result::Result<(),
     deny_syscall(libc::SYS_sendto);
     deny_syscall(libc::SYS_sendmsg);
     deny_syscall(libc::SYS_sendmmsg);
-    deny_syscall(libc::SYS_recvfrom);
     deny_syscall(libc::SYS_recvmsg);
     deny_syscall(libc::SYS_recvmmsg);
     deny_syscall(libc::SYS_getsockopt);
@@ -116,8 +311,51 @@ fn install_network_seccomp_filter_on_current_thread() -> std::result::Result<(),
     )?])?;
 
     rules.insert(libc::SYS_socket, vec![unix_only_rule]);
-    rules.insert(libc::SYS_socketpair, vec![]); // always deny (Unix can use socketpair but fine, keep open?)
 
+    // Commands get an explicit environment (see `crate::exec_env`), so the
+    // standard library spawns them with `fork()` and `exec()` rather than
+    // `posix_spawn()`, which needs an AF_UNIX `socketpair` and `recv(2)`, i.e.,
+    // `recvfrom` without a source address. Without them, every sandboxed
+    // command fails to spawn with `EPERM`.
+    //
+    // `recvfrom` is allowed on any fd, but that does not let a command receive
+    // from the network: it cannot create or connect a network socket, and a
+    // socket it inherited could be read with `read(2)` anyway, which `recv(2)`
+    // only adds flags to. Codex opens every fd with `O_CLOEXEC`, so commands do
+    // not inherit any socket (see
+    // `test_no_socket_is_inherited_under_network_seccomp_filter`).
+    let non_unix_rule = SeccompRule::new(vec![SeccompCondition::new(
+        0, // first argument (domain)
+        SeccompCmpArgLen::Dword,
+        SeccompCmpOp::Ne,
+        libc::AF_UNIX as u64,
+    )?])?;
+    rules.insert(libc::SYS_socketpair, vec![non_unix_rule]);
+
+    let with_source_address_rule = SeccompRule::new(vec![SeccompCondition::new(
+        4, // fifth argument (src_addr)
+        SeccompCmpArgLen::Qword,
+        SeccompCmpOp::Ne,
+        0,
+    )?])?;
+    rules.insert(libc::SYS_recvfrom, vec![with_source_address_rule]);
+
+    install_seccomp_filter_on_current_thread(rules)
+}
+
+/// Installs a seccomp filter that only blocks `ptrace`, for use when the
+/// network is isolated with a network namespace instead.
+fn install_ptrace_seccomp_filter_on_current_thread() -> std::result::Result<(), SandboxErr> {
+    let mut rules: BTreeMap<i64, Vec<SeccompRule>> = BTreeMap::new();
+    rules.insert(libc::SYS_ptrace, vec![]);
+    install_seccomp_filter_on_current_thread(rules)
+}
+
+/// Installs a seccomp filter that makes the syscalls matched by `rules` fail
+/// with `EPERM` and allows all others.
+fn install_seccomp_filter_on_current_thread(
+    rules: BTreeMap<i64, Vec<SeccompRule>>,
+) -> std::result::Result<(), SandboxErr> {
     let filter = SeccompFilter::new(
         rules,
         SeccompAction::Allow,                     // default – allow
@@ -143,29 +381,62 @@ mod tests {
     #![expect(clippy::unwrap_used, clippy::expect_used)]
 
     use super::*;
+    use crate::codex::apply_patch_helper_sandbox_policy;
+    use crate::config::ShellEnvironmentPolicy;
+    use crate::config::ShellOutputLimits;
     use crate::exec::ExecParams;
+    use crate::exec::FullOutputDir;
     use crate::exec::SandboxType;
     use crate::exec::process_exec_tool_call;
+    use crate::exec_env::create_env;
+    use crate::protocol::SandboxPermission;
     use crate::protocol::SandboxPolicy;
     use std::sync::Arc;
     use tempfile::NamedTempFile;
     use tokio::sync::Notify;
 
-    #[allow(clippy::print_stdout)]
     async fn run_cmd(cmd: &[&str], writable_roots: &[PathBuf], timeout_ms: u64) {
+        run_cmd_with_sandbox(SandboxType::LinuxSeccomp, cmd, writable_roots, timeout_ms).await;
+    }
+
+    async fn run_cmd_with_sandbox(
+        sandbox_type: SandboxType,
+        cmd: &[&str],
+        writable_roots: &[PathBuf],
+        timeout_ms: u64,
+    ) {
+        let sandbox_policy =
+            SandboxPolicy::new_read_only_policy_with_writable_roots(writable_roots);
+        run_cmd_with_policy(sandbox_type, cmd, &sandbox_policy, timeout_ms).await;
+    }
+
+    #[allow(clippy::print_stdout)]
+    async fn run_cmd_with_policy(
+        sandbox_type: SandboxType,
+        cmd: &[&str],
+        sandbox_policy: &SandboxPolicy,
+        timeout_ms: u64,
+    ) {
         let params = ExecParams {
             command: cmd.iter().map(|elm| elm.to_string()).collect(),
             cwd: std::env::current_dir().expect("cwd should exist"),
             timeout_ms: Some(timeout_ms),
+            env: create_env(&ShellEnvironmentPolicy::default()),
+            stdin: None,
         };
 
-        let sandbox_policy =
-            SandboxPolicy::new_read_only_policy_with_writable_roots(writable_roots);
         let ctrl_c = Arc::new(Notify::new());
-        let res =
-            process_exec_tool_call(params, SandboxType::LinuxSeccomp, ctrl_c, &sandbox_policy)
-                .await
-                .unwrap();
+        let res = process_exec_tool_call(
+            params,
+            sandbox_type,
+            ctrl_c,
+            sandbox_policy,
+            ShellOutputLimits::default(),
+            &FullOutputDir::new(),
+            None,
+        )
+        .await
+        .unwrap();
 
         if res.exit_code != 0 {
             println!("stdout:\n{}", res.stdout);
@@ -179,6 +450,25 @@ mod tests {
         run_cmd(&["ls", "-l", "/bin"], &[], 200).await;
     }
 
+    /// Spawning with an explicit environment needs `socketpair` and `recv(2)`,
+    /// which the network seccomp filter must therefore allow for AF_UNIX.
+    #[tokio::test]
+    async fn test_spawn_with_explicit_env_under_network_seccomp_filter() {
+        run_cmd(&["true"], &[], 1000).await;
+    }
+
+    /// `recvfrom` is allowed on any fd, which is only harmless as long as no
+    /// network socket leaks into the command.
+    #[tokio::test]
+    async fn test_no_socket_is_inherited_under_network_seccomp_filter() {
+        run_cmd(
+            &["sh", "-c", "! ls -l /proc/self/fd/ | grep socket:"],
+            &[],
+            1000,
+        )
+        .await;
+    }
+
     #[tokio::test]
     #[should_panic]
     async fn test_root_write() {
@@ -223,7 +513,7 @@ mod tests {
     }
 
     #[tokio::test]
-    #[should_panic(expected = "Sandbox(Timeout)")]
+    #[should_panic(expected = "Sandbox(Timeout { timeout_ms: 50, output: ")]
     async fn test_timeout() {
         run_cmd(&["sleep", "2"], &[], 50).await;
     }
@@ -233,24 +523,37 @@ mod tests {
     /// is missing in which case we silently treat it as an accepted skip so the
     /// suite remains green on leaner CI images.
     async fn assert_network_blocked(cmd: &[&str]) {
+        assert_network_blocked_with_sandbox(SandboxType::LinuxSeccomp, cmd).await;
+    }
+
+    async fn assert_network_blocked_with_sandbox(sandbox_type: SandboxType, cmd: &[&str]) {
         let params = ExecParams {
             command: cmd.iter().map(|s| s.to_string()).collect(),
             cwd: std::env::current_dir().expect("cwd should exist"),
             // Give the tool a generous 2‑second timeout so even slow DNS timeouts
             // do not stall the suite.
             timeout_ms: Some(2_000),
+            env: create_env(&ShellEnvironmentPolicy::default()),
+            stdin: None,
         };
 
         let sandbox_policy = SandboxPolicy::new_read_only_policy();
         let ctrl_c = Arc::new(Notify::new());
-        let result =
-            process_exec_tool_call(params, SandboxType::LinuxSeccomp, ctrl_c, &sandbox_policy)
-                .await;
+        let result = process_exec_tool_call(
+            params,
+            sandbox_type,
+            ctrl_c,
+            &sandbox_policy,
+            ShellOutputLimits::default(),
+            &FullOutputDir::new(),
+            None,
+        )
+        .await;
 
         let (exit_code, stdout, stderr) = match result {
             Ok(output) => (output.exit_code, output.stdout, output.stderr),
-            Err(CodexErr::Sandbox(SandboxErr::Denied(exit_code, stdout, stderr))) => {
-                (exit_code, stdout, stderr)
+            Err(CodexErr::Sandbox(SandboxErr::Denied(output))) => {
+                (output.exit_code, output.stdout, output.stderr)
             }
             _ => {
                 panic!("expected sandbox denied error, got: {:?}", result);
@@ -323,4 +626,199 @@ mod tests {
         // all images ship bash, so we guard against 127 as well.
         assert_network_blocked(&["bash", "-c", "echo hi > /dev/tcp/127.0.0.1/80"]).await;
     }
+
+    #[tokio::test]
+    async fn network_namespace_blocks_curl() {
+        assert_network_blocked_with_sandbox(
+            SandboxType::LinuxNetworkNamespace,
+            &["curl", "-I", "http://openai.com"],
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn network_namespace_blocks_getent() {
+        assert_network_blocked_with_sandbox(
+            SandboxType::LinuxNetworkNamespace,
+            &["getent", "ahosts", "openai.com"],
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn network_namespace_only_has_loopback() {
+        // `/proc/net/dev` lists the interfaces of the caller's network
+        // namespace after two header lines.
+        run_cmd_with_sandbox(
+            SandboxType::LinuxNetworkNamespace,
+            &[
+                "bash",
+                "-c",
+                "test \"$(tail -n +3 /proc/net/dev | cut -d: -f1 | tr -d ' ')\" = lo",
+            ],
+            &[],
+            1_000,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn network_namespace_allows_loopback() {
+        // Nothing listens on port 1, so a working loopback interface refuses
+        // the connection rather than failing with EPERM or ENETUNREACH.
+        run_cmd_with_sandbox(
+            SandboxType::LinuxNetworkNamespace,
+            &[
+                "bash",
+                "-c",
+                "(echo > /dev/tcp/127.0.0.1/1) 2>&1 | grep -q 'Connection refused'",
+            ],
+            &[],
+            1_000,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    #[should_panic]
+    async fn network_namespace_root_write() {
+        let tmpfile = NamedTempFile::new().unwrap();
+        let tmpfile_path = tmpfile.path().to_string_lossy();
+        run_cmd_with_sandbox(
+            SandboxType::LinuxNetworkNamespace,
+            &["bash", "-c", &format!("echo blah > {}", tmpfile_path)],
+            &[],
+            1_000,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn network_namespace_writable_root() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let file_path = tmpdir.path().join("test");
+        run_cmd_with_sandbox(
+            SandboxType::LinuxNetworkNamespace,
+            &[
+                "bash",
+                "-c",
+                &format!("echo blah > {}", file_path.to_string_lossy()),
+            ],
+            &[tmpdir.path().to_path_buf()],
+            1_000,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_system_paths_readable_without_full_read_access() {
+        run_cmd_with_policy(
+            SandboxType::LinuxSeccomp,
+            &["ls", "/usr/bin"],
+            &SandboxPolicy::from(vec![]),
+            1_000,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    #[should_panic]
+    async fn test_read_outside_readable_roots() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let file_path = tmpdir.path().join("secret");
+        std::fs::write(&file_path, "shh").unwrap();
+        run_cmd_with_policy(
+            SandboxType::LinuxSeccomp,
+            &["cat", &file_path.to_string_lossy()],
+            &SandboxPolicy::from(vec![]),
+            1_000,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_system_proc_entries_readable_without_full_read_access() {
+        run_cmd_with_policy(
+            SandboxType::LinuxSeccomp,
+            &["cat", "/proc/meminfo"],
+            &SandboxPolicy::from(vec![]),
+            1_000,
+        )
+        .await;
+    }
+
+    /// The environment of Codex, which holds API keys, must not be readable.
+    #[tokio::test]
+    #[should_panic(expected = "Permission denied")]
+    async fn test_parent_environ_not_readable_without_full_read_access() {
+        run_cmd_with_policy(
+            SandboxType::LinuxSeccomp,
+            &["sh", "-c", "cat /proc/$PPID/environ"],
+            &SandboxPolicy::from(vec![]),
+            1_000,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_disk_read_folder() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let file_path = tmpdir.path().join("notes");
+        std::fs::write(&file_path, "hello").unwrap();
+        let sandbox_policy = SandboxPolicy::from(vec![SandboxPermission::DiskReadFolder {
+            folder: tmpdir.path().to_path_buf(),
+        }]);
+        run_cmd_with_policy(
+            SandboxType::LinuxSeccomp,
+            &["cat", &file_path.to_string_lossy()],
+            &sandbox_policy,
+            1_000,
+        )
+        .await;
+    }
+
+    /// The `apply_patch` helper re-executes the current binary, which is not
+    /// under the cwd or any of the readable system paths. The test binary
+    /// stands in for it and merely lists its tests.
+    #[tokio::test]
+    async fn test_apply_patch_helper_runs_without_full_read_access() {
+        let exe = std::env::current_exe().unwrap();
+        let sandbox_policy =
+            apply_patch_helper_sandbox_policy(&SandboxPolicy::from(vec![]), &[], &exe);
+        run_cmd_with_policy(
+            SandboxType::LinuxSeccomp,
+            &[&exe.to_string_lossy(), "--list"],
+            &sandbox_policy,
+            5_000,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    #[should_panic]
+    async fn test_current_exe_not_executable_without_read_access() {
+        let exe = std::env::current_exe().unwrap();
+        run_cmd_with_policy(
+            SandboxType::LinuxSeccomp,
+            &[&exe.to_string_lossy(), "--list"],
+            &SandboxPolicy::from(vec![]),
+            5_000,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    #[should_panic]
+    async fn test_read_restricted_with_full_write_access() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let file_path = tmpdir.path().join("secret");
+        std::fs::write(&file_path, "shh").unwrap();
+        run_cmd_with_policy(
+            SandboxType::LinuxSeccomp,
+            &["cat", &file_path.to_string_lossy()],
+            &SandboxPolicy::from(vec![SandboxPermission::DiskFullWriteAccess]),
+            1_000,
+        )
+        .await;
+    }
 }
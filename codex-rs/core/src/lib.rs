@@ -10,29 +10,38 @@ mod client;
 mod client_common;
 pub mod codex;
 pub use codex::Codex;
+pub use codex_apply_patch::maybe_run_apply_patch_helper;
 pub mod codex_wrapper;
 pub mod config;
 pub mod config_profile;
 mod conversation_history;
 pub mod error;
 pub mod exec;
+pub mod exec_env;
 pub mod exec_linux;
 mod flags;
 mod is_safe_command;
 #[cfg(target_os = "linux")]
 pub mod landlock;
 mod mcp_connection_manager;
+mod mcp_resources;
 pub mod mcp_server_config;
 mod mcp_tool_call;
 mod message_history;
+pub use message_history::history_path;
 mod model_provider_info;
 pub use model_provider_info::ModelProviderInfo;
 pub use model_provider_info::WireApi;
 mod models;
+mod openai_tools;
 mod project_doc;
 pub mod protocol;
 mod rollout;
+pub use rollout::SessionRollout;
+pub use rollout::find_session_rollout;
+pub use rollout::list_session_rollouts;
 mod safety;
+mod shell_session;
 mod user_notification;
 pub mod util;
 pub mod auth_utils;
@@ -4,22 +4,48 @@
 //! configured server (keyed by the *server name*). It offers convenience
 //! helpers to query the available tools across *all* servers and returns them
 //! in a single aggregated map using the fully-qualified tool name
-//! `"<server><MCP_TOOL_NAME_DELIMITER><tool>"` as the key.
+//! `"<server><MCP_TOOL_NAME_DELIMITER><tool>"` as the key. Resources and
+//! prompts are aggregated per server instead, keyed by the server name.
+//!
+//! Servers launched over stdio are supervised: if the server process exits,
+//! it is respawned and re-initialized, and the user is informed via
+//! `BackgroundEvent`s. The tools of a server are re-fetched whenever it sends
+//! `notifications/tools/list_changed`, so the aggregated map stays current.
 
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::RwLock;
+use std::sync::Weak;
 use std::time::Duration;
+use std::time::Instant;
 
 use anyhow::Context;
 use anyhow::Result;
 use anyhow::anyhow;
+use async_channel::Sender;
 use codex_mcp_client::McpClient;
 use mcp_types::ClientCapabilities;
 use mcp_types::Implementation;
+use mcp_types::JSONRPCNotification;
+use mcp_types::ListToolsRequestParams;
+use mcp_types::ModelContextProtocolNotification;
+use mcp_types::Prompt;
+use mcp_types::Resource;
+use mcp_types::ServerCapabilities;
 use mcp_types::Tool;
+use mcp_types::ToolListChangedNotification;
+use tokio::sync::broadcast;
 use tokio::task::JoinSet;
 use tracing::info;
+use tracing::warn;
 
 use crate::mcp_server_config::McpServerConfig;
+use crate::mcp_server_config::McpServerTransportConfig;
+use crate::mcp_server_config::McpToolApproval;
+use crate::protocol::BackgroundEventEvent;
+use crate::protocol::Event;
+use crate::protocol::EventMsg;
+use crate::util::backoff;
 
 /// Delimiter used to separate the server name from the tool name in a fully
 /// qualified tool name.
@@ -28,13 +54,33 @@ use crate::mcp_server_config::McpServerConfig;
 /// choose a delimiter from this character set.
 const MCP_TOOL_NAME_DELIMITER: &str = "__OAI_CODEX_MCP__";
 
-/// Timeout for the `tools/list` request.
+/// Timeout for each `tools/list` request (i.e., for each page of tools).
 const LIST_TOOLS_TIMEOUT: Duration = Duration::from_secs(10);
 
+/// Timeout for the `resources/list` and `prompts/list` requests.
+const LIST_RESOURCES_AND_PROMPTS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default for [`McpServerConfig::startup_timeout_ms`].
+const DEFAULT_STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default for [`McpServerConfig::tool_timeout_ms`].
+const DEFAULT_TOOL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Number of consecutive attempts to restart a server before giving up.
+const MAX_RESTART_ATTEMPTS: u64 = 3;
+
+/// A restarted server that stays up at least this long is considered healthy
+/// again, i.e., it gets another `MAX_RESTART_ATTEMPTS` if it exits later.
+const STABLE_UPTIME: Duration = Duration::from_secs(30);
+
 /// Map that holds a startup error for every MCP server that could **not** be
 /// spawned successfully.
 pub type ClientStartErrors = HashMap<String, anyhow::Error>;
 
+/// Fully qualified tool name -> tool instance, shared with the tasks that
+/// refresh the tools of a server when they change.
+type ToolMap = Arc<RwLock<HashMap<String, Tool>>>;
+
 fn fully_qualified_tool_name(server: &str, tool: &str) -> String {
     format!("{server}{MCP_TOOL_NAME_DELIMITER}{tool}")
 }
@@ -47,6 +93,22 @@ pub(crate) fn try_parse_fully_qualified_tool_name(fq_name: &str) -> Option<(Stri
     Some((server.to_string(), tool.to_string()))
 }
 
+/// The client for a single server. The client is replaced when the server is
+/// restarted.
+struct ManagedClient {
+    client: RwLock<Arc<McpClient>>,
+    tool_timeout: Duration,
+    approval: Option<McpToolApproval>,
+    tool_approval: HashMap<String, McpToolApproval>,
+}
+
+impl ManagedClient {
+    fn current(&self) -> Arc<McpClient> {
+        #[expect(clippy::unwrap_used)]
+        self.client.read().unwrap().clone()
+    }
+}
+
 /// A thin wrapper around a set of running [`McpClient`] instances.
 #[derive(Default)]
 pub(crate) struct McpConnectionManager {
@@ -54,10 +116,16 @@ pub(crate) struct McpConnectionManager {
     ///
     /// The server name originates from the keys of the `mcp_servers` map in
     /// the user configuration.
-    clients: HashMap<String, std::sync::Arc<McpClient>>,
+    clients: HashMap<String, Arc<ManagedClient>>,
 
     /// Fully qualified tool name -> tool instance.
-    tools: HashMap<String, Tool>,
+    tools: ToolMap,
+
+    /// Server-name -> resources offered by that server.
+    resources: HashMap<String, Vec<Resource>>,
+
+    /// Server-name -> prompts offered by that server.
+    prompts: HashMap<String, Vec<Prompt>>,
 }
 
 impl McpConnectionManager {
@@ -65,12 +133,16 @@ impl McpConnectionManager {
     ///
     /// * `mcp_servers` – Map loaded from the user configuration where *keys*
     ///   are human-readable server identifiers and *values* are the spawn
-    ///   instructions.
+    ///   instructions (or the URL of a streamable HTTP server).
     ///
     /// Servers that fail to start are reported in `ClientStartErrors`: the
-    /// user should be informed about these errors.
+    /// user should be informed about these errors. Once started, servers that
+    /// are launched over stdio are restarted if they exit; this is reported
+    /// to the user as `BackgroundEvent`s with the id `sub_id` on `tx_event`.
     pub async fn new(
         mcp_servers: HashMap<String, McpServerConfig>,
+        tx_event: Sender<Event>,
+        sub_id: String,
     ) -> Result<(Self, ClientStartErrors)> {
         // Early exit if no servers are configured.
         if mcp_servers.is_empty() {
@@ -80,42 +152,13 @@ impl McpConnectionManager {
         // Launch all configured servers concurrently.
         let mut join_set = JoinSet::new();
 
-        for (server_name, cfg) in mcp_servers {
+        for (server_name, cfg) in mcp_servers.clone() {
             // TODO: Verify server name: require `^[a-zA-Z0-9_-]+$`?
-            join_set.spawn(async move {
-                let McpServerConfig { command, args, env } = cfg;
-                let client_res = McpClient::new_stdio_client(command, args, env).await;
-                match client_res {
-                    Ok(client) => {
-                        // Initialize the client.
-                        let params = mcp_types::InitializeRequestParams {
-                            capabilities: ClientCapabilities {
-                                experimental: None,
-                                roots: None,
-                                sampling: None,
-                            },
-                            client_info: Implementation {
-                                name: "codex-mcp-client".to_owned(),
-                                version: env!("CARGO_PKG_VERSION").to_owned(),
-                            },
-                            protocol_version: mcp_types::MCP_SCHEMA_VERSION.to_owned(),
-                        };
-                        let initialize_notification_params = None;
-                        let timeout = Some(Duration::from_secs(10));
-                        match client
-                            .initialize(params, initialize_notification_params, timeout)
-                            .await
-                        {
-                            Ok(_response) => (server_name, Ok(client)),
-                            Err(e) => (server_name, Err(e)),
-                        }
-                    }
-                    Err(e) => (server_name, Err(e.into())),
-                }
-            });
+            join_set.spawn(async move { (server_name, start_client(&cfg).await) });
         }
 
-        let mut clients: HashMap<String, std::sync::Arc<McpClient>> =
+        let mut clients: HashMap<String, Arc<McpClient>> = HashMap::with_capacity(join_set.len());
+        let mut capabilities: HashMap<String, ServerCapabilities> =
             HashMap::with_capacity(join_set.len());
         let mut errors = ClientStartErrors::new();
 
@@ -123,8 +166,9 @@ impl McpConnectionManager {
             let (server_name, client_res) = res?; // JoinError propagation
 
             match client_res {
-                Ok(client) => {
-                    clients.insert(server_name, std::sync::Arc::new(client));
+                Ok((client, server_capabilities)) => {
+                    capabilities.insert(server_name.clone(), server_capabilities);
+                    clients.insert(server_name, client);
                 }
                 Err(e) => {
                     errors.insert(server_name, e);
@@ -132,15 +176,101 @@ impl McpConnectionManager {
             }
         }
 
-        let tools = list_all_tools(&clients).await?;
+        // Subscribe before listing the tools so that a change in between is
+        // not missed.
+        let notifications: HashMap<String, broadcast::Receiver<JSONRPCNotification>> = clients
+            .iter()
+            .map(|(server_name, client)| (server_name.clone(), client.subscribe_notifications()))
+            .collect();
+        let tools: ToolMap = Arc::new(RwLock::new(list_all_tools(&clients).await?));
+        for (server_name, notifications) in notifications {
+            tokio::spawn(watch_tool_list_changes(
+                server_name.clone(),
+                Arc::downgrade(&clients[&server_name]),
+                notifications,
+                tools.clone(),
+                false,
+            ));
+        }
+        let (resources, prompts) = list_all_resources_and_prompts(&clients, &capabilities).await;
+
+        let clients = clients
+            .into_iter()
+            .map(|(server_name, client)| {
+                let cfg = &mcp_servers[&server_name];
+                let managed = Arc::new(ManagedClient {
+                    client: RwLock::new(client),
+                    tool_timeout: cfg
+                        .tool_timeout_ms
+                        .map_or(DEFAULT_TOOL_TIMEOUT, Duration::from_millis),
+                    approval: cfg.approval,
+                    tool_approval: cfg.tool_approval.clone(),
+                });
+                if matches!(cfg.transport, McpServerTransportConfig::Stdio { .. }) {
+                    tokio::spawn(supervise_stdio_server(
+                        server_name.clone(),
+                        cfg.clone(),
+                        Arc::downgrade(&managed),
+                        tools.clone(),
+                        tx_event.clone(),
+                        sub_id.clone(),
+                    ));
+                }
+                (server_name, managed)
+            })
+            .collect();
 
-        Ok((Self { clients, tools }, errors))
+        Ok((
+            Self {
+                clients,
+                tools,
+                resources,
+                prompts,
+            },
+            errors,
+        ))
     }
 
     /// Returns a single map that contains **all** tools. Each key is the
     /// fully-qualified name for the tool.
     pub fn list_all_tools(&self) -> HashMap<String, Tool> {
-        self.tools.clone()
+        #[expect(clippy::unwrap_used)]
+        self.tools.read().unwrap().clone()
+    }
+
+    /// Returns the resources offered by each server, keyed by server name.
+    /// Servers that do not offer any resources are omitted.
+    pub fn list_all_resources(&self) -> HashMap<String, Vec<Resource>> {
+        self.resources.clone()
+    }
+
+    /// Whether any server offers at least one resource.
+    pub fn has_resources(&self) -> bool {
+        !self.resources.is_empty()
+    }
+
+    /// Returns the prompts offered by each server, keyed by server name.
+    /// Servers that do not offer any prompts are omitted.
+    pub fn list_all_prompts(&self) -> HashMap<String, Vec<Prompt>> {
+        self.prompts.clone()
+    }
+
+    /// How long to wait for a tool call on the given server to complete, as
+    /// configured by its `tool_timeout_ms`.
+    pub fn tool_timeout(&self, server: &str) -> Option<Duration> {
+        self.clients.get(server).map(|managed| managed.tool_timeout)
+    }
+
+    /// The approval policy configured for the given tool: its entry in
+    /// `tool_approval` if any, otherwise the `approval` of its server. `None`
+    /// if neither is configured.
+    pub fn tool_approval(&self, server: &str, tool: &str) -> Option<McpToolApproval> {
+        let managed = self.clients.get(server)?;
+        managed
+            .tool_approval
+            .get(tool)
+            .copied()
+            .or(managed.approval)
     }
 
     /// Invoke the tool indicated by the (server, tool) pair.
@@ -151,23 +281,187 @@ impl McpConnectionManager {
         arguments: Option<serde_json::Value>,
         timeout: Option<Duration>,
     ) -> Result<mcp_types::CallToolResult> {
-        let client = self
-            .clients
-            .get(server)
-            .ok_or_else(|| anyhow!("unknown MCP server '{server}'"))?
-            .clone();
+        let client = self.client(server)?;
 
         client
             .call_tool(tool.to_string(), arguments, timeout)
             .await
             .with_context(|| format!("tool call failed for `{server}/{tool}`"))
     }
+
+    /// Read the resource identified by `uri` from the given server. Like a
+    /// tool call, this is bounded by the server's `tool_timeout_ms`.
+    pub async fn read_resource(
+        &self,
+        server: &str,
+        uri: &str,
+    ) -> Result<mcp_types::ReadResourceResult> {
+        let client = self.client(server)?;
+        let timeout = self.tool_timeout(server);
+
+        client
+            .read_resource(uri.to_string(), timeout)
+            .await
+            .with_context(|| format!("reading resource `{uri}` from `{server}` failed"))
+    }
+
+    /// Expand the prompt indicated by the (server, prompt) pair. Like a tool
+    /// call, this is bounded by the server's `tool_timeout_ms`.
+    pub async fn get_prompt(
+        &self,
+        server: &str,
+        prompt: &str,
+        arguments: Option<HashMap<String, String>>,
+    ) -> Result<mcp_types::GetPromptResult> {
+        let client = self.client(server)?;
+        let timeout = self.tool_timeout(server);
+        let arguments = arguments.map(serde_json::to_value).transpose()?;
+
+        client
+            .get_prompt(prompt.to_string(), arguments, timeout)
+            .await
+            .with_context(|| format!("getting prompt `{server}/{prompt}` failed"))
+    }
+
+    fn client(&self, server: &str) -> Result<Arc<McpClient>> {
+        self.clients
+            .get(server)
+            .map(|managed| managed.current())
+            .ok_or_else(|| anyhow!("unknown MCP server '{server}'"))
+    }
+}
+
+/// Launch the server described by `cfg` and negotiate the initialization,
+/// bounded by the server's `startup_timeout_ms`.
+async fn start_client(cfg: &McpServerConfig) -> Result<(Arc<McpClient>, ServerCapabilities)> {
+    let client = match cfg.transport.clone() {
+        McpServerTransportConfig::Stdio { command, args, env } => {
+            McpClient::new_stdio_client(command, args, env).await?
+        }
+        McpServerTransportConfig::StreamableHttp { url, bearer_token } => {
+            McpClient::new_streamable_http_client(url, bearer_token)?
+        }
+    };
+
+    let params = mcp_types::InitializeRequestParams {
+        capabilities: ClientCapabilities {
+            experimental: None,
+            roots: None,
+            sampling: None,
+        },
+        client_info: Implementation {
+            name: "codex-mcp-client".to_owned(),
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+        },
+        protocol_version: mcp_types::MCP_SCHEMA_VERSION.to_owned(),
+    };
+    let initialize_notification_params = None;
+    let timeout = cfg
+        .startup_timeout_ms
+        .map_or(DEFAULT_STARTUP_TIMEOUT, Duration::from_millis);
+    let response = client
+        .initialize(params, initialize_notification_params, Some(timeout))
+        .await?;
+
+    Ok((Arc::new(client), response.capabilities))
+}
+
+/// Wait for the server to exit and restart it, for as long as the manager
+/// that owns `managed` is alive. Gives up after `MAX_RESTART_ATTEMPTS`
+/// consecutive failed (or short-lived) restarts.
+async fn supervise_stdio_server(
+    server_name: String,
+    cfg: McpServerConfig,
+    managed: Weak<ManagedClient>,
+    tools: ToolMap,
+    tx_event: Sender<Event>,
+    sub_id: String,
+) {
+    let mut attempts = 0;
+    let mut started_at = Instant::now();
+    loop {
+        // Do not hold on to the client while waiting so that dropping the
+        // manager shuts the server down (which in turn ends this loop).
+        let closed = match managed.upgrade() {
+            Some(managed) => managed.current().closed(),
+            None => return,
+        };
+        closed.await;
+        if managed.strong_count() == 0 {
+            return;
+        }
+
+        warn!("MCP server `{server_name}` exited");
+        notify_background_event(
+            &tx_event,
+            &sub_id,
+            format!("MCP server `{server_name}` exited; restarting it…"),
+        )
+        .await;
+        if started_at.elapsed() >= STABLE_UPTIME {
+            attempts = 0;
+        }
+
+        loop {
+            if attempts >= MAX_RESTART_ATTEMPTS {
+                notify_background_event(
+                    &tx_event,
+                    &sub_id,
+                    format!("MCP server `{server_name}` keeps exiting; giving up on restarting it"),
+                )
+                .await;
+                return;
+            }
+            attempts += 1;
+            tokio::time::sleep(backoff(attempts)).await;
+
+            match start_client(&cfg).await {
+                Ok((client, _capabilities)) => {
+                    let Some(managed) = managed.upgrade() else {
+                        return;
+                    };
+                    // The restarted server may offer different tools.
+                    tokio::spawn(watch_tool_list_changes(
+                        server_name.clone(),
+                        Arc::downgrade(&client),
+                        client.subscribe_notifications(),
+                        tools.clone(),
+                        true,
+                    ));
+                    #[expect(clippy::unwrap_used)]
+                    let mut guard = managed.client.write().unwrap();
+                    *guard = client;
+                    break;
+                }
+                Err(e) => warn!("failed to restart MCP server `{server_name}`: {e:#}"),
+            }
+        }
+
+        started_at = Instant::now();
+        info!("MCP server `{server_name}` restarted");
+        notify_background_event(
+            &tx_event,
+            &sub_id,
+            format!("MCP server `{server_name}` is back up"),
+        )
+        .await;
+    }
+}
+
+async fn notify_background_event(tx_event: &Sender<Event>, sub_id: &str, message: String) {
+    let event = Event {
+        id: sub_id.to_string(),
+        msg: EventMsg::BackgroundEvent(BackgroundEventEvent { message }),
+    };
+    if let Err(e) = tx_event.send(event).await {
+        warn!("failed to send MCP server status event: {e}");
+    }
 }
 
 /// Query every server for its available tools and return a single map that
 /// contains **all** tools. Each key is the fully-qualified name for the tool.
 pub async fn list_all_tools(
-    clients: &HashMap<String, std::sync::Arc<McpClient>>,
+    clients: &HashMap<String, Arc<McpClient>>,
 ) -> Result<HashMap<String, Tool>> {
     let mut join_set = JoinSet::new();
 
@@ -178,9 +472,7 @@ pub async fn list_all_tools(
         let server_name_cloned = server_name.clone();
         let client_clone = client.clone();
         join_set.spawn(async move {
-            let res = client_clone
-                .list_tools(None, Some(LIST_TOOLS_TIMEOUT))
-                .await;
+            let res = list_server_tools(&client_clone).await;
             (server_name_cloned, res)
         });
     }
@@ -188,10 +480,10 @@ pub async fn list_all_tools(
     let mut aggregated: HashMap<String, Tool> = HashMap::with_capacity(join_set.len());
 
     while let Some(join_res) = join_set.join_next().await {
-        let (server_name, list_result) = join_res?;
-        let list_result = list_result?;
+        let (server_name, server_tools) = join_res?;
+        let server_tools = server_tools?;
 
-        for tool in list_result.tools {
+        for tool in server_tools {
             // TODO(mbolin): escape tool names that contain invalid characters.
             let fq_name = fully_qualified_tool_name(&server_name, &tool.name);
             if aggregated.insert(fq_name.clone(), tool).is_some() {
@@ -208,3 +500,324 @@ pub async fn list_all_tools(
 
     Ok(aggregated)
 }
+
+/// Fetch **all** tools of a server, following `next_cursor` across pages.
+async fn list_server_tools(client: &McpClient) -> Result<Vec<Tool>> {
+    let mut tools = Vec::new();
+    let mut cursor = None;
+    loop {
+        let params = cursor.map(|cursor| ListToolsRequestParams {
+            cursor: Some(cursor),
+        });
+        let result = client.list_tools(params, Some(LIST_TOOLS_TIMEOUT)).await?;
+        tools.extend(result.tools);
+        match result.next_cursor {
+            Some(next_cursor) => cursor = Some(next_cursor),
+            None => return Ok(tools),
+        }
+    }
+}
+
+/// Re-fetch the tools of a server whenever it sends
+/// `notifications/tools/list_changed`, for as long as `client` is alive. If
+/// `refresh_now` is set, the tools are also re-fetched right away.
+async fn watch_tool_list_changes(
+    server_name: String,
+    client: Weak<McpClient>,
+    mut notifications: broadcast::Receiver<JSONRPCNotification>,
+    tools: ToolMap,
+    refresh_now: bool,
+) {
+    if let Some(client) = client.upgrade().filter(|_| refresh_now) {
+        refresh_server_tools(&server_name, &client, &tools).await;
+    }
+
+    loop {
+        match notifications.recv().await {
+            Ok(notification) if notification.method == ToolListChangedNotification::METHOD => {}
+            Ok(_) => continue,
+            // Some notifications were dropped; any of them may have been a
+            // change to the list of tools.
+            Err(broadcast::error::RecvError::Lagged(_)) => {}
+            Err(broadcast::error::RecvError::Closed) => return,
+        }
+
+        let Some(client) = client.upgrade() else {
+            return;
+        };
+        refresh_server_tools(&server_name, &client, &tools).await;
+    }
+}
+
+/// Replace the tools of `server_name` in `tools` with the ones it offers now.
+/// On failure, the previous tools are kept.
+async fn refresh_server_tools(
+    server_name: &str,
+    client: &McpClient,
+    tools: &RwLock<HashMap<String, Tool>>,
+) {
+    let server_tools = match list_server_tools(client).await {
+        Ok(server_tools) => server_tools,
+        Err(e) => {
+            warn!("failed to refresh tools for `{server_name}`: {e:#}");
+            return;
+        }
+    };
+
+    info!("refreshed {} tools for `{server_name}`", server_tools.len());
+    #[expect(clippy::unwrap_used)]
+    let mut tools = tools.write().unwrap();
+    tools.retain(|fq_name, _| {
+        try_parse_fully_qualified_tool_name(fq_name).is_none_or(|(server, _)| server != server_name)
+    });
+    tools.extend(
+        server_tools
+            .into_iter()
+            .map(|tool| (fully_qualified_tool_name(server_name, &tool.name), tool)),
+    );
+}
+
+/// Query every server that advertises the `resources` and/or `prompts`
+/// capability for what it offers. Unlike tools, a server that fails to list
+/// its resources or prompts is logged and skipped rather than treated as a
+/// fatal error, since neither is required for the agent to function.
+async fn list_all_resources_and_prompts(
+    clients: &HashMap<String, Arc<McpClient>>,
+    capabilities: &HashMap<String, ServerCapabilities>,
+) -> (HashMap<String, Vec<Resource>>, HashMap<String, Vec<Prompt>>) {
+    let mut join_set = JoinSet::new();
+
+    for (server_name, client) in clients {
+        let Some(server_capabilities) = capabilities.get(server_name) else {
+            continue;
+        };
+        let has_resources = server_capabilities.resources.is_some();
+        let has_prompts = server_capabilities.prompts.is_some();
+        if !has_resources && !has_prompts {
+            continue;
+        }
+
+        let server_name_cloned = server_name.clone();
+        let client_clone = client.clone();
+        join_set.spawn(async move {
+            let resources = if has_resources {
+                Some(
+                    client_clone
+                        .list_resources(None, Some(LIST_RESOURCES_AND_PROMPTS_TIMEOUT))
+                        .await,
+                )
+            } else {
+                None
+            };
+            let prompts = if has_prompts {
+                Some(
+                    client_clone
+                        .list_prompts(None, Some(LIST_RESOURCES_AND_PROMPTS_TIMEOUT))
+                        .await,
+                )
+            } else {
+                None
+            };
+            (server_name_cloned, resources, prompts)
+        });
+    }
+
+    let mut resources = HashMap::new();
+    let mut prompts = HashMap::new();
+
+    while let Some(join_res) = join_set.join_next().await {
+        let (server_name, list_resources, list_prompts) = match join_res {
+            Ok(res) => res,
+            Err(e) => {
+                warn!("failed to list MCP resources and prompts: {e}");
+                continue;
+            }
+        };
+
+        match list_resources {
+            Some(Ok(result)) if !result.resources.is_empty() => {
+                resources.insert(server_name.clone(), result.resources);
+            }
+            Some(Err(e)) => warn!("failed to list resources for `{server_name}`: {e:#}"),
+            _ => {}
+        }
+        match list_prompts {
+            Some(Ok(result)) if !result.prompts.is_empty() => {
+                prompts.insert(server_name.clone(), result.prompts);
+            }
+            Some(Err(e)) => warn!("failed to list prompts for `{server_name}`: {e:#}"),
+            _ => {}
+        }
+    }
+
+    info!(
+        "aggregated resources from {} servers and prompts from {} servers",
+        resources.len(),
+        prompts.len()
+    );
+
+    (resources, prompts)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+
+    use super::*;
+
+    const INITIALIZE_RESPONSE: &str = r#"{"jsonrpc":"2.0","id":1,"result":{"capabilities":{"tools":{"listChanged":true}},"protocolVersion":"2025-03-26","serverInfo":{"name":"test","version":"0.0.0"}}}"#;
+
+    /// A stdio "server" that answers `initialize` and `tools/list` and then
+    /// exits. Once restarted (i.e., once `$MARKER` exists), it offers a
+    /// different tool and stays up.
+    const FLAKY_SERVER_SCRIPT: &str = r#"
+read -r _
+echo "$INITIALIZE_RESPONSE"
+read -r _
+read -r _
+if [ -e "$MARKER" ]; then
+  echo '{"jsonrpc":"2.0","id":2,"result":{"tools":[{"name":"after_restart","inputSchema":{"type":"object"}}]}}'
+  cat > /dev/null
+fi
+touch "$MARKER"
+echo '{"jsonrpc":"2.0","id":2,"result":{"tools":[]}}'
+"#;
+
+    /// A stdio "server" that offers its tools in two pages, and announces a
+    /// change to its tools once the client sends it a notification.
+    const PAGINATED_SERVER_SCRIPT: &str = r#"
+read -r _
+echo "$INITIALIZE_RESPONSE"
+read -r _
+read -r _
+echo '{"jsonrpc":"2.0","id":2,"result":{"tools":[{"name":"first","inputSchema":{"type":"object"}}],"nextCursor":"page-2"}}'
+read -r line
+case "$line" in
+  *'"cursor":"page-2"'*) echo '{"jsonrpc":"2.0","id":3,"result":{"tools":[{"name":"second","inputSchema":{"type":"object"}}]}}' ;;
+  *) echo '{"jsonrpc":"2.0","id":3,"error":{"code":-32602,"message":"unexpected cursor"}}' ;;
+esac
+read -r _
+echo '{"jsonrpc":"2.0","method":"notifications/tools/list_changed"}'
+read -r _
+echo '{"jsonrpc":"2.0","id":4,"result":{"tools":[{"name":"third","inputSchema":{"type":"object"}}]}}'
+cat > /dev/null
+"#;
+
+    fn sh_server_config(script: &str, env: HashMap<String, String>) -> McpServerConfig {
+        let mut env = env;
+        env.insert(
+            "INITIALIZE_RESPONSE".to_string(),
+            INITIALIZE_RESPONSE.to_string(),
+        );
+        McpServerConfig {
+            transport: McpServerTransportConfig::Stdio {
+                command: "sh".to_string(),
+                args: vec!["-c".to_string(), script.to_string()],
+                env: Some(env),
+            },
+            startup_timeout_ms: None,
+            tool_timeout_ms: Some(1_000),
+            approval: None,
+            tool_approval: HashMap::new(),
+        }
+    }
+
+    fn sorted_tool_names(tools: &HashMap<String, Tool>) -> Vec<String> {
+        let mut names: Vec<String> = tools.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn test_tools_are_paginated_and_refreshed_on_list_changed() {
+        let cfg = sh_server_config(PAGINATED_SERVER_SCRIPT, HashMap::new());
+        let (client, _capabilities) = start_client(&cfg).await.unwrap();
+        let notifications = client.subscribe_notifications();
+
+        let tools = list_server_tools(&client).await.unwrap();
+        let names: Vec<&str> = tools.iter().map(|tool| tool.name.as_str()).collect();
+        assert_eq!(vec!["first", "second"], names);
+
+        let tools: ToolMap = Arc::new(RwLock::new(HashMap::from([
+            (
+                fully_qualified_tool_name("paged", "first"),
+                tools[0].clone(),
+            ),
+            (fully_qualified_tool_name("other", "tool"), tools[1].clone()),
+        ])));
+        tokio::spawn(watch_tool_list_changes(
+            "paged".to_string(),
+            Arc::downgrade(&client),
+            notifications,
+            tools.clone(),
+            false,
+        ));
+
+        // Prompt the server to announce that its tools changed.
+        client
+            .send_notification::<mcp_types::InitializedNotification>(None)
+            .await
+            .unwrap();
+        let expected = vec![
+            fully_qualified_tool_name("other", "tool"),
+            fully_qualified_tool_name("paged", "third"),
+        ];
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while sorted_tool_names(&tools.read().unwrap()) != expected {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("tools should be refreshed after notifications/tools/list_changed");
+    }
+
+    #[tokio::test]
+    async fn test_exited_stdio_server_is_restarted() {
+        let tmp = tempfile::tempdir().unwrap();
+        let marker = tmp.path().join("started-once");
+        let cfg = sh_server_config(
+            FLAKY_SERVER_SCRIPT,
+            HashMap::from([("MARKER".to_string(), marker.display().to_string())]),
+        );
+        let (tx_event, rx_event) = async_channel::unbounded();
+        let (manager, errors) = McpConnectionManager::new(
+            HashMap::from([("flaky".to_string(), cfg)]),
+            tx_event,
+            "sub".to_string(),
+        )
+        .await
+        .unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(Some(Duration::from_secs(1)), manager.tool_timeout("flaky"));
+
+        let mut messages = Vec::new();
+        for _ in 0..2 {
+            let event = tokio::time::timeout(Duration::from_secs(10), rx_event.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!("sub", event.id);
+            let EventMsg::BackgroundEvent(BackgroundEventEvent { message }) = event.msg else {
+                panic!("expected a BackgroundEvent, got {:?}", event.msg);
+            };
+            messages.push(message);
+        }
+        assert_eq!(
+            vec![
+                "MCP server `flaky` exited; restarting it…".to_string(),
+                "MCP server `flaky` is back up".to_string(),
+            ],
+            messages
+        );
+
+        // The tools of the restarted server are fetched again.
+        let expected = vec![fully_qualified_tool_name("flaky", "after_restart")];
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while sorted_tool_names(&manager.list_all_tools()) != expected {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("tools should be refreshed after a restart");
+    }
+}
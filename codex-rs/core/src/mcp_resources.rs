@@ -0,0 +1,99 @@
+//! Built-in tools that let the model list and read the resources offered by
+//! the configured MCP servers.
+
+use serde::Deserialize;
+
+use crate::codex::Session;
+use crate::models::FunctionCallOutputPayload;
+use crate::models::ResponseInputItem;
+
+pub(crate) const LIST_MCP_RESOURCES_TOOL_NAME: &str = "list_mcp_resources";
+pub(crate) const READ_MCP_RESOURCE_TOOL_NAME: &str = "read_mcp_resource";
+
+#[derive(Deserialize)]
+struct ListMcpResourcesArgs {
+    #[serde(default)]
+    server: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ReadMcpResourceArgs {
+    server: String,
+    uri: String,
+}
+
+/// Replies with the resources offered by every server (or only `server`, if
+/// specified) as a JSON object keyed by server name.
+pub(crate) fn handle_list_mcp_resources(
+    sess: &Session,
+    call_id: String,
+    arguments: String,
+) -> ResponseInputItem {
+    let args = match parse_arguments::<ListMcpResourcesArgs>(&arguments) {
+        Ok(args) => args,
+        Err(output) => return function_call_output(call_id, output),
+    };
+
+    let mut resources = sess.list_mcp_resources();
+    if let Some(server) = args.server {
+        resources.retain(|name, _| *name == server);
+    }
+
+    let output = match serde_json::to_string(&resources) {
+        Ok(content) => FunctionCallOutputPayload {
+            content,
+            success: Some(true),
+        },
+        Err(e) => FunctionCallOutputPayload {
+            content: format!("JSON serialization error: {e}"),
+            success: Some(false),
+        },
+    };
+    function_call_output(call_id, output)
+}
+
+/// Replies with the contents of the resource identified by `uri` as returned
+/// by the server's `resources/read` method.
+pub(crate) async fn handle_read_mcp_resource(
+    sess: &Session,
+    call_id: String,
+    arguments: String,
+) -> ResponseInputItem {
+    let ReadMcpResourceArgs { server, uri } =
+        match parse_arguments::<ReadMcpResourceArgs>(&arguments) {
+            Ok(args) => args,
+            Err(output) => return function_call_output(call_id, output),
+        };
+
+    let output = match sess.read_mcp_resource(&server, &uri).await {
+        Ok(result) => FunctionCallOutputPayload {
+            content: serde_json::to_string(&result)
+                .unwrap_or_else(|e| format!("JSON serialization error: {e}")),
+            success: Some(true),
+        },
+        Err(e) => FunctionCallOutputPayload {
+            content: format!("err: {e:#}"),
+            success: Some(false),
+        },
+    };
+    function_call_output(call_id, output)
+}
+
+fn parse_arguments<T: for<'de> Deserialize<'de>>(
+    arguments: &str,
+) -> Result<T, FunctionCallOutputPayload> {
+    // Like MCP tool calls, an empty string is treated as "no arguments".
+    let arguments = if arguments.trim().is_empty() {
+        "{}"
+    } else {
+        arguments
+    };
+    serde_json::from_str(arguments).map_err(|e| FunctionCallOutputPayload {
+        content: format!("failed to parse function arguments: {e}"),
+        success: None,
+    })
+}
+
+fn function_call_output(call_id: String, output: FunctionCallOutputPayload) -> ResponseInputItem {
+    ResponseInputItem::FunctionCallOutput { call_id, output }
+}
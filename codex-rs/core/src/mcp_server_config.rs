@@ -4,11 +4,64 @@ use serde::Deserialize;
 
 #[derive(Deserialize, Debug, Clone, PartialEq)]
 pub struct McpServerConfig {
-    pub command: String,
+    /// How to reach the server: either a command to spawn, or a URL.
+    #[serde(flatten)]
+    pub transport: McpServerTransportConfig,
 
+    /// How long to wait for the server to start and respond to `initialize`.
+    /// Defaults to 10 seconds.
     #[serde(default)]
-    pub args: Vec<String>,
+    pub startup_timeout_ms: Option<u64>,
 
+    /// How long to wait for a tool call to complete. Defaults to 60 seconds.
     #[serde(default)]
-    pub env: Option<HashMap<String, String>>,
+    pub tool_timeout_ms: Option<u64>,
+
+    /// Whether calls to the tools of this server require approval. When
+    /// unset, the user is asked unless `approval_policy` is `never`.
+    #[serde(default)]
+    pub approval: Option<McpToolApproval>,
+
+    /// Per-tool overrides of `approval`, keyed by the name of the tool as
+    /// given by the server.
+    #[serde(default)]
+    pub tool_approval: HashMap<String, McpToolApproval>,
+}
+
+/// Approval policy for calls to an MCP tool.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum McpToolApproval {
+    /// Always run the tool without asking the user.
+    Always,
+
+    /// Ask the user to approve every call to the tool.
+    Ask,
+
+    /// Never run the tool; calls are rejected without asking the user.
+    Never,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum McpServerTransportConfig {
+    /// Spawn `command` and communicate with it over stdio.
+    Stdio {
+        command: String,
+
+        #[serde(default)]
+        args: Vec<String>,
+
+        #[serde(default)]
+        env: Option<HashMap<String, String>>,
+    },
+
+    /// Connect to a server that uses the streamable HTTP transport at `url`.
+    StreamableHttp {
+        url: String,
+
+        /// Sent as `Authorization: Bearer <token>` with every request.
+        #[serde(default)]
+        bearer_token: Option<String>,
+    },
 }
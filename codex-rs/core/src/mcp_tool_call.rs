@@ -3,15 +3,34 @@ use std::time::Duration;
 use tracing::error;
 
 use crate::codex::Session;
+use crate::mcp_server_config::McpToolApproval;
 use crate::models::FunctionCallOutputPayload;
 use crate::models::ResponseInputItem;
+use crate::protocol::AskForApproval;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
 use crate::protocol::McpToolCallBeginEvent;
 use crate::protocol::McpToolCallEndEvent;
+use crate::protocol::ReviewDecision;
+
+/// Combines the approval configured for a tool (its own or its server's) with
+/// the session's `approval_policy`; see [`Session::mcp_tool_approval`].
+pub(crate) fn effective_mcp_tool_approval(
+    configured: Option<McpToolApproval>,
+    approval_policy: AskForApproval,
+) -> McpToolApproval {
+    match (configured, approval_policy) {
+        (None, AskForApproval::Never) => McpToolApproval::Always,
+        (Some(McpToolApproval::Ask), AskForApproval::Never) => McpToolApproval::Never,
+        (None, _) => McpToolApproval::Ask,
+        (Some(approval), _) => approval,
+    }
+}
 
 /// Handles the specified tool call dispatches the appropriate
-/// `McpToolCallBegin` and `McpToolCallEnd` events to the `Session`.
+/// `McpToolCallBegin` and `McpToolCallEnd` events to the `Session`. If the
+/// tool requires approval, the user is asked first via an
+/// `McpToolCallApprovalRequest` event.
 pub(crate) async fn handle_mcp_tool_call(
     sess: &Session,
     sub_id: &str,
@@ -41,6 +60,37 @@ pub(crate) async fn handle_mcp_tool_call(
         }
     };
 
+    match sess.mcp_tool_approval(&server, &tool_name) {
+        McpToolApproval::Always => {}
+        McpToolApproval::Ask if sess.is_mcp_tool_approved(&server, &tool_name) => {}
+        McpToolApproval::Ask => {
+            let rx_approve = sess
+                .request_mcp_tool_call_approval(
+                    sub_id.to_string(),
+                    call_id.clone(),
+                    server.clone(),
+                    tool_name.clone(),
+                    arguments_value.clone(),
+                )
+                .await;
+            match rx_approve.await.unwrap_or_default() {
+                ReviewDecision::Approved => {}
+                ReviewDecision::ApprovedForSession => {
+                    sess.add_approved_mcp_tool(server.clone(), tool_name.clone());
+                }
+                ReviewDecision::Denied | ReviewDecision::Abort => {
+                    return rejected_tool_call(call_id, "tool call rejected by user".to_string());
+                }
+            }
+        }
+        McpToolApproval::Never => {
+            return rejected_tool_call(
+                call_id,
+                format!("calls to tool `{tool_name}` on MCP server `{server}` are not allowed"),
+            );
+        }
+    }
+
     let tool_call_begin_event = EventMsg::McpToolCallBegin(McpToolCallBeginEvent {
         call_id: call_id.clone(),
         server: server.clone(),
@@ -97,6 +147,16 @@ pub(crate) async fn handle_mcp_tool_call(
     }
 }
 
+fn rejected_tool_call(call_id: String, content: String) -> ResponseInputItem {
+    ResponseInputItem::FunctionCallOutput {
+        call_id,
+        output: FunctionCallOutputPayload {
+            content,
+            success: Some(false),
+        },
+    }
+}
+
 async fn notify_mcp_tool_call_event(sess: &Session, sub_id: &str, event: EventMsg) {
     sess.send_event(Event {
         id: sub_id.to_string(),
@@ -104,3 +164,53 @@ async fn notify_mcp_tool_call_event(sess: &Session, sub_id: &str, event: EventMs
     })
     .await;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn configured_approval_takes_precedence_over_approval_policy() {
+        let cases = [
+            // Without a configured approval, the user is asked unless they
+            // asked never to be.
+            (None, AskForApproval::Never, McpToolApproval::Always),
+            (
+                None,
+                AskForApproval::UnlessAllowListed,
+                McpToolApproval::Ask,
+            ),
+            (None, AskForApproval::OnFailure, McpToolApproval::Ask),
+            // A tool that needs approval cannot run if the user cannot be
+            // asked.
+            (
+                Some(McpToolApproval::Ask),
+                AskForApproval::Never,
+                McpToolApproval::Never,
+            ),
+            (
+                Some(McpToolApproval::Ask),
+                AskForApproval::OnFailure,
+                McpToolApproval::Ask,
+            ),
+            (
+                Some(McpToolApproval::Always),
+                AskForApproval::UnlessAllowListed,
+                McpToolApproval::Always,
+            ),
+            (
+                Some(McpToolApproval::Never),
+                AskForApproval::AutoEdit,
+                McpToolApproval::Never,
+            ),
+        ];
+        for (configured, approval_policy, expected) in cases {
+            assert_eq!(
+                effective_mcp_tool_approval(configured, approval_policy),
+                expected,
+                "{configured:?} with {approval_policy:?}"
+            );
+        }
+    }
+}
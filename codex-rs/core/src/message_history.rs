@@ -18,6 +18,7 @@ use std::fs::File;
 use std::fs::OpenOptions;
 use std::io::Result;
 use std::io::Write;
+use std::path::Path;
 use std::path::PathBuf;
 
 use serde::Deserialize;
@@ -49,9 +50,12 @@ pub struct HistoryEntry {
 }
 
 fn history_filepath(config: &Config) -> PathBuf {
-    let mut path = config.codex_home.clone();
-    path.push(HISTORY_FILENAME);
-    path
+    history_path(&config.codex_home)
+}
+
+/// Path of the message history file inside `codex_home`.
+pub fn history_path(codex_home: &Path) -> PathBuf {
+    codex_home.join(HISTORY_FILENAME)
 }
 
 /// Append a `text` entry associated with `session_id` to the history file. Uses
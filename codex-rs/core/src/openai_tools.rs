@@ -0,0 +1,351 @@
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+use crate::client_common::Prompt;
+use crate::mcp_resources::LIST_MCP_RESOURCES_TOOL_NAME;
+use crate::mcp_resources::READ_MCP_RESOURCE_TOOL_NAME;
+use crate::shell_session::SHELL_SESSION_KILL_TOOL_NAME;
+use crate::shell_session::SHELL_SESSION_READ_TOOL_NAME;
+use crate::shell_session::SHELL_SESSION_START_TOOL_NAME;
+use crate::shell_session::SHELL_SESSION_WRITE_TOOL_NAME;
+
+/// When serialized as JSON, this produces a valid "Tool" in the OpenAI
+/// Responses API.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+pub(crate) enum OpenAiTool {
+    #[serde(rename = "function")]
+    Function(ResponsesApiTool),
+    #[serde(rename = "local_shell")]
+    LocalShell {},
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct ResponsesApiTool {
+    name: &'static str,
+    description: &'static str,
+    strict: bool,
+    parameters: JsonSchema,
+}
+
+/// Generic JSON‑Schema subset needed for our tool definitions
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub(crate) enum JsonSchema {
+    String,
+    Number,
+    Array {
+        items: Box<JsonSchema>,
+    },
+    Object {
+        properties: BTreeMap<String, JsonSchema>,
+        required: &'static [&'static str],
+        #[serde(rename = "additionalProperties")]
+        additional_properties: bool,
+    },
+}
+
+/// Tool usage specification
+static DEFAULT_TOOLS: LazyLock<Vec<OpenAiTool>> = LazyLock::new(|| {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "command".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::String),
+        },
+    );
+    properties.insert("workdir".to_string(), JsonSchema::String);
+    properties.insert("timeout".to_string(), JsonSchema::Number);
+
+    vec![OpenAiTool::Function(ResponsesApiTool {
+        name: "shell",
+        description: "Runs a shell command, and returns its output.",
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: &["command"],
+            additional_properties: false,
+        },
+    })]
+});
+
+static DEFAULT_CODEX_MODEL_TOOLS: LazyLock<Vec<OpenAiTool>> =
+    LazyLock::new(|| vec![OpenAiTool::LocalShell {}]);
+
+/// Built-in tools for listing and reading the resources offered by the
+/// configured MCP servers.
+static MCP_RESOURCE_TOOLS: LazyLock<Vec<OpenAiTool>> = LazyLock::new(|| {
+    let mut list_properties = BTreeMap::new();
+    list_properties.insert("server".to_string(), JsonSchema::String);
+
+    let mut read_properties = BTreeMap::new();
+    read_properties.insert("server".to_string(), JsonSchema::String);
+    read_properties.insert("uri".to_string(), JsonSchema::String);
+
+    vec![
+        OpenAiTool::Function(ResponsesApiTool {
+            name: LIST_MCP_RESOURCES_TOOL_NAME,
+            description: "Lists the resources offered by the configured MCP servers, keyed by server name. Pass `server` to only list the resources of that server.",
+            strict: false,
+            parameters: JsonSchema::Object {
+                properties: list_properties,
+                required: &[],
+                additional_properties: false,
+            },
+        }),
+        OpenAiTool::Function(ResponsesApiTool {
+            name: READ_MCP_RESOURCE_TOOL_NAME,
+            description: "Reads the contents of the resource with the given `uri` from the MCP server named `server`.",
+            strict: false,
+            parameters: JsonSchema::Object {
+                properties: read_properties,
+                required: &["server", "uri"],
+                additional_properties: false,
+            },
+        }),
+    ]
+});
+
+/// Built-in tools for persistent, interactive shell sessions (see
+/// [`crate::shell_session`]).
+static SHELL_SESSION_TOOLS: LazyLock<Vec<OpenAiTool>> = LazyLock::new(|| {
+    let mut start_properties = BTreeMap::new();
+    start_properties.insert(
+        "command".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::String),
+        },
+    );
+    start_properties.insert("workdir".to_string(), JsonSchema::String);
+    start_properties.insert("timeout".to_string(), JsonSchema::Number);
+
+    let mut write_properties = BTreeMap::new();
+    write_properties.insert("session_id".to_string(), JsonSchema::Number);
+    write_properties.insert("input".to_string(), JsonSchema::String);
+    write_properties.insert("timeout".to_string(), JsonSchema::Number);
+
+    let mut read_properties = BTreeMap::new();
+    read_properties.insert("session_id".to_string(), JsonSchema::Number);
+    read_properties.insert("timeout".to_string(), JsonSchema::Number);
+
+    let mut kill_properties = BTreeMap::new();
+    kill_properties.insert("session_id".to_string(), JsonSchema::Number);
+
+    vec![
+        OpenAiTool::Function(ResponsesApiTool {
+            name: SHELL_SESSION_START_TOOL_NAME,
+            description: "Starts `command` (e.g. [\"bash\"] or [\"python3\"]) in a persistent, interactive terminal session and returns its `session_id` along with the output produced within `timeout` milliseconds. Use sessions for REPLs, long-running processes, and state such as the working directory that must survive across commands.",
+            strict: false,
+            parameters: JsonSchema::Object {
+                properties: start_properties,
+                required: &["command"],
+                additional_properties: false,
+            },
+        }),
+        OpenAiTool::Function(ResponsesApiTool {
+            name: SHELL_SESSION_WRITE_TOOL_NAME,
+            description: "Writes `input` to the terminal of a shell session and returns the output produced within `timeout` milliseconds. End `input` with a newline to submit a line; send \"\\u0003\" to interrupt the running command.",
+            strict: false,
+            parameters: JsonSchema::Object {
+                properties: write_properties,
+                required: &["session_id", "input"],
+                additional_properties: false,
+            },
+        }),
+        OpenAiTool::Function(ResponsesApiTool {
+            name: SHELL_SESSION_READ_TOOL_NAME,
+            description: "Returns the output a shell session produced since the last call, waiting up to `timeout` milliseconds for more. The result includes `exit_code` once the session's command has exited.",
+            strict: false,
+            parameters: JsonSchema::Object {
+                properties: read_properties,
+                required: &["session_id"],
+                additional_properties: false,
+            },
+        }),
+        OpenAiTool::Function(ResponsesApiTool {
+            name: SHELL_SESSION_KILL_TOOL_NAME,
+            description: "Kills a shell session and every process it started, and returns its remaining output.",
+            strict: false,
+            parameters: JsonSchema::Object {
+                properties: kill_properties,
+                required: &["session_id"],
+                additional_properties: false,
+            },
+        }),
+    ]
+});
+
+/// Returns JSON values that are compatible with Function Calling in the
+/// Responses API:
+/// https://platform.openai.com/docs/guides/function-calling?api-mode=responses
+pub(crate) fn create_tools_json_for_responses_api(
+    prompt: &Prompt,
+    model: &str,
+) -> crate::error::Result<Vec<serde_json::Value>> {
+    let default_tools = if model.starts_with("codex") {
+        &DEFAULT_CODEX_MODEL_TOOLS
+    } else {
+        &DEFAULT_TOOLS
+    };
+    create_tools_json(prompt, default_tools)
+}
+
+/// Assembles the tool list: `default_tools` + the built-in tools enabled for
+/// `prompt` + any extra tools from the prompt.
+fn create_tools_json(
+    prompt: &Prompt,
+    default_tools: &[OpenAiTool],
+) -> crate::error::Result<Vec<serde_json::Value>> {
+    let mut tools_json = Vec::with_capacity(default_tools.len() + prompt.extra_tools.len());
+    for t in default_tools.iter() {
+        tools_json.push(serde_json::to_value(t)?);
+    }
+    if prompt.include_mcp_resource_tools {
+        for t in MCP_RESOURCE_TOOLS.iter() {
+            tools_json.push(serde_json::to_value(t)?);
+        }
+    }
+    if prompt.include_shell_session_tools {
+        for t in SHELL_SESSION_TOOLS.iter() {
+            tools_json.push(serde_json::to_value(t)?);
+        }
+    }
+    tools_json.extend(
+        prompt
+            .extra_tools
+            .clone()
+            .into_iter()
+            .map(|(name, tool)| mcp_tool_to_openai_tool(name, tool)),
+    );
+
+    Ok(tools_json)
+}
+
+/// Returns JSON values that are compatible with Function Calling in the
+/// Chat Completions API:
+/// https://platform.openai.com/docs/guides/function-calling?api-mode=chat
+pub(crate) fn create_tools_json_for_chat_completions_api(
+    prompt: &Prompt,
+) -> crate::error::Result<Vec<serde_json::Value>> {
+    // We start with the JSON for the Responses API and then rewrite it to
+    // match the Chat Completions tool call format. `local_shell` has no Chat
+    // Completions equivalent, so the function-style `shell` tool is used for
+    // every model, including those that get `local_shell` on the Responses API.
+    let responses_api_tools_json = create_tools_json(prompt, &DEFAULT_TOOLS)?;
+    let tools_json = responses_api_tools_json
+        .into_iter()
+        .filter_map(|mut tool| {
+            if tool.get("type") != Some(&serde_json::Value::String("function".to_string())) {
+                return None;
+            }
+
+            if let Some(map) = tool.as_object_mut() {
+                // Remove "type" field as it is not needed in chat completions.
+                map.remove("type");
+                Some(json!({
+                    "type": "function",
+                    "function": map,
+                }))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<serde_json::Value>>();
+    Ok(tools_json)
+}
+
+fn mcp_tool_to_openai_tool(
+    fully_qualified_name: String,
+    tool: mcp_types::Tool,
+) -> serde_json::Value {
+    // TODO(mbolin): Change the contract of this function to return
+    // ResponsesApiTool.
+    json!({
+        "name": fully_qualified_name,
+        "description": tool.description,
+        "parameters": tool.input_schema,
+        "type": "function",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn chat_completions_tools_wrap_function_definitions() {
+        let prompt = Prompt::default();
+        let tools = create_tools_json_for_chat_completions_api(&prompt).unwrap();
+
+        assert_eq!(tools.len(), 1);
+        let tool = &tools[0];
+        assert_eq!(tool["type"], "function");
+        assert_eq!(tool["function"]["name"], "shell");
+        assert_eq!(tool["function"]["parameters"]["required"], json!(["command"]));
+        assert!(tool["function"].get("type").is_none());
+    }
+
+    #[test]
+    fn mcp_resource_tools_are_only_included_when_requested() {
+        let tool_names = |prompt: &Prompt| {
+            create_tools_json_for_responses_api(prompt, "gpt-4.1")
+                .unwrap()
+                .iter()
+                .map(|tool| tool["name"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let mut prompt = Prompt::default();
+        assert_eq!(tool_names(&prompt), vec!["shell"]);
+
+        prompt.include_mcp_resource_tools = true;
+        assert_eq!(
+            tool_names(&prompt),
+            vec!["shell", "list_mcp_resources", "read_mcp_resource"]
+        );
+    }
+
+    #[test]
+    fn shell_session_tools_are_only_included_when_requested() {
+        let mut prompt = Prompt {
+            include_shell_session_tools: true,
+            ..Default::default()
+        };
+        let tools = create_tools_json_for_chat_completions_api(&prompt).unwrap();
+        let tool_names: Vec<&str> = tools
+            .iter()
+            .map(|tool| tool["function"]["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            tool_names,
+            vec![
+                "shell",
+                "shell_session_start",
+                "shell_session_write",
+                "shell_session_read",
+                "shell_session_kill"
+            ]
+        );
+
+        prompt.include_shell_session_tools = false;
+        let tools = create_tools_json_for_chat_completions_api(&prompt).unwrap();
+        assert_eq!(tools.len(), 1);
+    }
+
+    #[test]
+    fn codex_models_get_function_shell_tool_on_chat_completions() {
+        let prompt = Prompt::default();
+        let responses_tools = create_tools_json_for_responses_api(&prompt, "codex-mini").unwrap();
+        assert_eq!(responses_tools, vec![json!({ "type": "local_shell" })]);
+
+        let tools = create_tools_json_for_chat_completions_api(&prompt).unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0]["type"], "function");
+        assert_eq!(tools[0]["function"]["name"], "shell");
+    }
+}
@@ -1,28 +1,40 @@
 //! Project-level documentation discovery.
 //!
-//! Project-level documentation can be stored in a file named `AGENTS.md`.
-//! Currently, we include only the contents of the first file found as follows:
+//! Project-level documentation is stored in files named `AGENTS.md` (or one of
+//! the other `Config::project_doc_filenames`). All of the following docs are
+//! included, in this order, so that more specific guidance comes later:
 //!
-//! 1.  Look for the doc file in the current working directory (as determined
-//!     by the `Config`).
-//! 2.  If not found, walk *upwards* until the Git repository root is reached
-//!     (detected by the presence of a `.git` directory/file), or failing that,
-//!     the filesystem root.
-//! 3.  If the Git root is encountered, look for the doc file there. If it
-//!     exists, the search stops – we do **not** walk past the Git root.
+//! 1.  The global doc in `Config::codex_home` (i.e., `~/.codex/AGENTS.md`).
+//! 2.  The doc of every directory from the Git repository root (detected by
+//!     the presence of a `.git` directory/file) down to the current working
+//!     directory (as determined by the `Config`). Outside a Git repository,
+//!     only the working directory is searched.
+//!
+//! In each directory, only the first candidate filename that exists is used.
+//! A line that consists of `@path/to/file.md` is replaced with the contents of
+//! that file, resolved relative to the doc. Together, the docs are limited to
+//! `Config::project_doc_max_bytes`, which goes to the most specific docs
+//! first: when they exceed it, the least specific ones are cut.
 
 use crate::config::Config;
+use futures::FutureExt;
+use futures::future::BoxFuture;
 use std::path::Path;
+use std::path::PathBuf;
 use tokio::io::AsyncReadExt;
 use tracing::error;
 
-/// Currently, we only match the filename `AGENTS.md` exactly.
-const CANDIDATE_FILENAMES: &[&str] = &["AGENTS.md"];
-
 /// When both `Config::instructions` and the project doc are present, they will
 /// be concatenated with the following separator.
 const PROJECT_DOC_SEPARATOR: &str = "\n\n--- project-doc ---\n\n";
 
+/// Separates the docs of different directories.
+const DOC_SEPARATOR: &str = "\n\n";
+
+/// `@path` lines in files included this deep are left as is, which also stops
+/// include cycles.
+const MAX_INCLUDE_DEPTH: usize = 5;
+
 /// Combines `Config::instructions` and `AGENTS.md` (if present) into a single
 /// string of instructions.
 pub(crate) async fn create_full_instructions(config: &Config) -> Option<String> {
@@ -41,86 +53,116 @@ pub(crate) async fn create_full_instructions(config: &Config) -> Option<String>
     }
 }
 
-/// Attempt to locate and load the project documentation. Currently, the search
-/// starts from `Config::cwd`, but if we may want to consider other directories
-/// in the future, e.g., additional writable directories in the `SandboxPolicy`.
+/// Attempt to locate and load the project documentation as described in the
+/// module docs. Currently, the search starts from `Config::cwd`, but we may
+/// want to consider other directories in the future, e.g., additional writable
+/// directories in the `SandboxPolicy`.
 ///
 /// On success returns `Ok(Some(contents))`. If no documentation file is found
 /// the function returns `Ok(None)`. Unexpected I/O failures bubble up as
 /// `Err` so callers can decide how to handle them.
 async fn find_project_doc(config: &Config) -> std::io::Result<Option<String>> {
     let max_bytes = config.project_doc_max_bytes;
+    if max_bytes == 0 {
+        return Ok(None);
+    }
+    let names = &config.project_doc_filenames;
 
-    // Attempt to load from the working directory first.
-    if let Some(doc) = load_first_candidate(&config.cwd, CANDIDATE_FILENAMES, max_bytes).await? {
-        return Ok(Some(doc));
+    let mut docs = Vec::new();
+    // `codex_home` does not have to exist.
+    if let Ok(codex_home) = config.codex_home.canonicalize() {
+        docs.extend(load_first_candidate(&codex_home, names, &codex_home, max_bytes).await?);
     }
 
-    // Walk up towards the filesystem root, stopping once we encounter the Git
-    // repository root. The presence of **either** a `.git` *file* or
-    // *directory* counts.
-    let mut dir = config.cwd.clone();
+    let (root, dirs) = project_doc_dirs(&config.cwd).await?;
+    for dir in dirs {
+        if let Some(doc) = load_first_candidate(&dir, names, &root, max_bytes).await? {
+            docs.push(doc);
+        }
+    }
 
-    // Canonicalize the path so that we do not end up in an infinite loop when
-    // `cwd` contains `..` components.
-    if let Ok(canon) = dir.canonicalize() {
-        dir = canon;
+    if docs.is_empty() {
+        return Ok(None);
     }
+    Ok(Some(join_docs(docs, max_bytes)))
+}
 
-    while let Some(parent) = dir.parent() {
-        // `.git` can be a *file* (for worktrees or submodules) or a *dir*.
-        let git_marker = dir.join(".git");
-        let git_exists = match tokio::fs::metadata(&git_marker).await {
-            Ok(_) => true,
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
-            Err(e) => return Err(e),
+/// Joins `docs`, which go from the least to the most specific, within
+/// `max_bytes`. The most specific docs are kept first: the doc that does not
+/// fit anymore is truncated and the ones before it are dropped.
+fn join_docs(docs: Vec<String>, max_bytes: usize) -> String {
+    let mut kept = Vec::new();
+    let mut remaining = max_bytes;
+    for mut doc in docs.into_iter().rev() {
+        let separator_len = if kept.is_empty() {
+            0
+        } else {
+            DOC_SEPARATOR.len()
         };
+        if doc.len() + separator_len <= remaining {
+            remaining -= doc.len() + separator_len;
+            kept.push(doc);
+            continue;
+        }
 
-        if git_exists {
-            // We are at the repo root – attempt one final load.
-            if let Some(doc) = load_first_candidate(&dir, CANDIDATE_FILENAMES, max_bytes).await? {
-                return Ok(Some(doc));
+        tracing::warn!("Project docs exceed {max_bytes} bytes - truncating.");
+        if remaining > separator_len {
+            let mut end = remaining - separator_len;
+            while !doc.is_char_boundary(end) {
+                end -= 1;
+            }
+            doc.truncate(end);
+            if !doc.is_empty() {
+                kept.push(doc);
             }
-            break;
         }
+        break;
+    }
+    kept.reverse();
+    kept.join(DOC_SEPARATOR)
+}
+
+/// Returns the root of the project that contains `cwd` along with the
+/// directories whose docs apply to `cwd`, starting with the root. The root is
+/// the Git repository root or, outside a repository, `cwd` itself.
+async fn project_doc_dirs(cwd: &Path) -> std::io::Result<(PathBuf, Vec<PathBuf>)> {
+    // Canonicalize the path so that `..` components do not get in the way of
+    // walking up the tree.
+    let cwd = cwd.canonicalize().unwrap_or_else(|_| cwd.to_path_buf());
+
+    let mut dirs = Vec::new();
+    for dir in cwd.ancestors() {
+        dirs.push(dir.to_path_buf());
 
-        dir = parent.to_path_buf();
+        // `.git` can be a *file* (for worktrees or submodules) or a *dir*.
+        match tokio::fs::metadata(dir.join(".git")).await {
+            Ok(_) => {
+                dirs.reverse();
+                return Ok((dir.to_path_buf(), dirs));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
     }
 
-    Ok(None)
+    Ok((cwd.clone(), vec![cwd]))
 }
 
-/// Attempt to load the first candidate file found in `dir`. Returns the file
-/// contents (truncated if it exceeds `max_bytes`) when successful.
+/// Attempt to load the first candidate file found in `dir`, with its includes
+/// expanded. Only files within `root` may be included.
 async fn load_first_candidate(
     dir: &Path,
-    names: &[&str],
+    names: &[String],
+    root: &Path,
     max_bytes: usize,
 ) -> std::io::Result<Option<String>> {
     for name in names {
         let candidate = dir.join(name);
-
-        let file = match tokio::fs::File::open(&candidate).await {
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
-            Err(e) => return Err(e),
-            Ok(f) => f,
+        let Some(contents) = read_doc(&candidate, max_bytes).await? else {
+            continue;
         };
 
-        let size = file.metadata().await?.len();
-
-        let reader = tokio::io::BufReader::new(file);
-        let mut data = Vec::with_capacity(std::cmp::min(size as usize, max_bytes));
-        let mut limited = reader.take(max_bytes as u64);
-        limited.read_to_end(&mut data).await?;
-
-        if size as usize > max_bytes {
-            tracing::warn!(
-                "Project doc `{}` exceeds {max_bytes} bytes - truncating.",
-                candidate.display(),
-            );
-        }
-
-        let contents = String::from_utf8_lossy(&data).to_string();
+        let contents = expand_includes(&candidate, contents, root, max_bytes, 0).await;
         if contents.trim().is_empty() {
             // Empty file – treat as not found.
             continue;
@@ -132,6 +174,93 @@ async fn load_first_candidate(
     Ok(None)
 }
 
+/// Reads the file at `path`, truncated if it exceeds `max_bytes`. Returns
+/// `Ok(None)` if it does not exist.
+async fn read_doc(path: &Path, max_bytes: usize) -> std::io::Result<Option<String>> {
+    let file = match tokio::fs::File::open(path).await {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+        Ok(f) => f,
+    };
+
+    let size = file.metadata().await?.len();
+
+    let reader = tokio::io::BufReader::new(file);
+    let mut data = Vec::with_capacity(std::cmp::min(size as usize, max_bytes));
+    let mut limited = reader.take(max_bytes as u64);
+    limited.read_to_end(&mut data).await?;
+
+    if size as usize > max_bytes {
+        tracing::warn!(
+            "Project doc `{}` exceeds {max_bytes} bytes - truncating.",
+            path.display(),
+        );
+    }
+
+    Ok(Some(String::from_utf8_lossy(&data).to_string()))
+}
+
+/// Replaces every line of `contents`, the contents of the file at `doc`, that
+/// consists of `@path` with the contents of `path`, resolved relative to
+/// `doc`. Lines that do not name a readable file within `root` are kept as is.
+fn expand_includes<'a>(
+    doc: &'a Path,
+    contents: String,
+    root: &'a Path,
+    max_bytes: usize,
+    depth: usize,
+) -> BoxFuture<'a, String> {
+    async move {
+        if depth >= MAX_INCLUDE_DEPTH {
+            return contents;
+        }
+
+        let mut expanded = String::with_capacity(contents.len());
+        for line in contents.split_inclusive('\n') {
+            // Anything past `max_bytes` is truncated anyway.
+            if expanded.len() >= max_bytes {
+                break;
+            }
+
+            if let Some(path) = resolve_include(doc, line, root) {
+                match read_doc(&path, max_bytes).await {
+                    Ok(Some(included)) => {
+                        let included =
+                            expand_includes(&path, included, root, max_bytes, depth + 1).await;
+                        expanded.push_str(&included);
+                        if line.ends_with('\n') && !included.ends_with('\n') {
+                            expanded.push('\n');
+                        }
+                        continue;
+                    }
+                    Ok(None) => {}
+                    Err(e) => tracing::warn!(
+                        "failed to read `{}` included from `{}`: {e}",
+                        path.display(),
+                        doc.display(),
+                    ),
+                }
+            }
+            expanded.push_str(line);
+        }
+        expanded
+    }
+    .boxed()
+}
+
+/// If `line` of the doc at `doc` is an include, returns the path of the
+/// included file, provided that it exists and is within `root`.
+fn resolve_include(doc: &Path, line: &str, root: &Path) -> Option<PathBuf> {
+    let target = line.trim().strip_prefix('@')?;
+    if target.is_empty() || target.contains(char::is_whitespace) {
+        return None;
+    }
+    // Canonicalizing resolves `..` and symlinks, so the check below cannot be
+    // circumvented.
+    let path = doc.parent()?.join(target).canonicalize().ok()?;
+    path.starts_with(root).then_some(path)
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::expect_used, clippy::unwrap_used)]
@@ -237,6 +366,113 @@ mod tests {
         assert_eq!(res, "root level doc");
     }
 
+    /// Every doc from the repository root down to `cwd` is included, the more
+    /// specific ones last.
+    #[tokio::test]
+    async fn merges_docs_from_repo_root_to_cwd() {
+        let outer = tempfile::tempdir().expect("tempdir");
+        fs::write(outer.path().join("AGENTS.md"), "outside the repo").unwrap();
+        let repo = outer.path().join("repo");
+        let nested = repo.join("crates/core");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::create_dir_all(repo.join(".git")).unwrap();
+        std::fs::create_dir_all(repo.join("docs")).unwrap();
+
+        fs::write(repo.join("AGENTS.md"), "root doc").unwrap();
+        fs::write(repo.join("crates/AGENTS.md"), "crates doc").unwrap();
+        fs::write(nested.join("AGENTS.md"), "core doc").unwrap();
+        fs::write(repo.join("docs/AGENTS.md"), "unrelated doc").unwrap();
+
+        let mut cfg = make_config(&outer, 4096, None);
+        cfg.cwd = nested;
+
+        let res = create_full_instructions(&cfg).await.expect("doc expected");
+        assert_eq!(res, "root doc\n\ncrates doc\n\ncore doc");
+
+        // The limit applies to the docs combined, and goes to the most
+        // specific ones first.
+        cfg.project_doc_max_bytes = "crates\n\ncore doc".len();
+        let res = create_full_instructions(&cfg).await.expect("doc expected");
+        assert_eq!(res, "crates\n\ncore doc");
+    }
+
+    /// The global doc and the docs close to the repository root are cut
+    /// before the doc of `cwd`, however large they are.
+    #[tokio::test]
+    async fn most_specific_docs_are_kept_over_the_limit() {
+        let codex_home = tempfile::tempdir().expect("tempdir");
+        fs::write(codex_home.path().join("AGENTS.md"), "G".repeat(4096)).unwrap();
+        let repo = tempfile::tempdir().expect("tempdir");
+        let nested = repo.path().join("crates/core");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::create_dir_all(repo.path().join(".git")).unwrap();
+        fs::write(repo.path().join("AGENTS.md"), "R".repeat(4096)).unwrap();
+        fs::write(nested.join("AGENTS.md"), "core doc").unwrap();
+
+        const LIMIT: usize = 1024;
+        let mut cfg = make_config(&repo, LIMIT, None);
+        cfg.codex_home = codex_home.path().to_path_buf();
+        cfg.cwd = nested;
+
+        let res = create_full_instructions(&cfg).await.expect("doc expected");
+        assert_eq!(res.len(), LIMIT);
+        let expected_root = "R".repeat(LIMIT - "\n\ncore doc".len());
+        assert_eq!(res, format!("{expected_root}\n\ncore doc"));
+    }
+
+    /// The global doc in `codex_home` comes first, and the candidate filenames
+    /// are configurable, in order of preference.
+    #[tokio::test]
+    async fn includes_global_doc_and_configured_filenames() {
+        let codex_home = tempfile::tempdir().expect("tempdir");
+        fs::write(codex_home.path().join("AGENTS.md"), "global doc").unwrap();
+        let tmp = tempfile::tempdir().expect("tempdir");
+        fs::write(tmp.path().join("CLAUDE.md"), "claude doc").unwrap();
+        std::fs::create_dir_all(tmp.path().join(".codex")).unwrap();
+        fs::write(tmp.path().join(".codex/instructions.md"), "codex doc").unwrap();
+
+        let mut cfg = make_config(&tmp, 4096, None);
+        cfg.codex_home = codex_home.path().to_path_buf();
+        let res = create_full_instructions(&cfg).await.expect("doc expected");
+        assert_eq!(res, "global doc");
+
+        cfg.project_doc_filenames = vec![
+            "AGENTS.md".to_string(),
+            ".codex/instructions.md".to_string(),
+            "CLAUDE.md".to_string(),
+        ];
+        let res = create_full_instructions(&cfg).await.expect("doc expected");
+        assert_eq!(res, "global doc\n\ncodex doc");
+    }
+
+    /// `@path` lines are replaced with the file they name, relative to the
+    /// doc, unless it is outside the repository or missing.
+    #[tokio::test]
+    async fn expands_includes_within_repo() {
+        let outer = tempfile::tempdir().expect("tempdir");
+        fs::write(outer.path().join("secret.md"), "secret").unwrap();
+        let repo = outer.path().join("repo");
+        std::fs::create_dir_all(repo.join(".git")).unwrap();
+        std::fs::create_dir_all(repo.join("docs")).unwrap();
+
+        fs::write(
+            repo.join("AGENTS.md"),
+            "intro\n@docs/style.md\n@../secret.md\n@missing.md\nend",
+        )
+        .unwrap();
+        fs::write(repo.join("docs/style.md"), "style\n@testing.md").unwrap();
+        fs::write(repo.join("docs/testing.md"), "testing").unwrap();
+
+        let mut cfg = make_config(&outer, 4096, None);
+        cfg.cwd = repo;
+
+        let res = create_full_instructions(&cfg).await.expect("doc expected");
+        assert_eq!(
+            res,
+            "intro\nstyle\ntesting\n@../secret.md\n@missing.md\nend"
+        );
+    }
+
     /// Explicitly setting the byte-limit to zero disables project docs.
     #[tokio::test]
     async fn zero_byte_limit_disables_docs() {
@@ -8,6 +8,8 @@ use std::path::Path;
 use std::path::PathBuf;
 
 use mcp_types::CallToolResult;
+use mcp_types::GetPromptResult;
+use mcp_types::Prompt as McpPrompt;
 use serde::Deserialize;
 use serde::Serialize;
 use uuid::Uuid;
@@ -90,6 +92,14 @@ pub enum Op {
         decision: ReviewDecision,
     },
 
+    /// Approve an MCP tool call
+    McpToolCallApproval {
+        /// The id of the submission we are approving
+        id: String,
+        /// The user's decision in response to the request.
+        decision: ReviewDecision,
+    },
+
     /// Append an entry to the persistent cross-session message history.
     ///
     /// Note the entry is not guaranteed to be logged if the user has
@@ -101,6 +111,24 @@ pub enum Op {
 
     /// Request a single history entry identified by `log_id` + `offset`.
     GetHistoryEntryRequest { offset: usize, log_id: u64 },
+
+    /// Ask the model to summarize the conversation so far and replace the
+    /// history that is sent on every turn with that summary, freeing up room
+    /// in the context window.
+    Compact,
+
+    /// Expand a prompt offered by an MCP server. The server replies with a
+    /// `GetMcpPromptResponse` event (or an `Error` event if the expansion
+    /// failed); it is up to the client to submit the result as user input.
+    GetMcpPromptRequest {
+        /// Name of the MCP server, as configured in `mcp_servers`.
+        server: String,
+        /// Name of the prompt on that server.
+        name: String,
+        /// Values for the prompt's arguments, keyed by argument name.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        arguments: Option<HashMap<String, String>>,
+    },
 }
 
 /// Determines how liberally commands are auto‑approved by the system.
@@ -230,7 +258,7 @@ impl SandboxPolicy {
                 DiskWriteFolder { folder } => {
                     writable_roots.push(folder.clone());
                 }
-                DiskFullReadAccess | NetworkFullAccess => {}
+                DiskFullReadAccess | DiskReadFolder { .. } | NetworkFullAccess => {}
                 DiskFullWriteAccess => {
                     // Currently, we expect callers to only invoke this method
                     // after verifying has_full_disk_write_access() is false.
@@ -240,11 +268,53 @@ impl SandboxPolicy {
         writable_roots
     }
 
+    /// Folders that may be read when the policy does not grant full read
+    /// access (in addition to the writable roots): the cwd and the folders
+    /// granted by `DiskReadFolder`.
+    pub fn get_readable_roots_with_cwd(&self, cwd: &Path) -> Vec<PathBuf> {
+        let mut readable_roots = vec![cwd.to_path_buf()];
+        for perm in &self.permissions {
+            if let SandboxPermission::DiskReadFolder { folder } = perm {
+                readable_roots.push(folder.clone());
+            }
+        }
+        readable_roots
+    }
+
     pub fn is_unrestricted(&self) -> bool {
         self.has_full_disk_read_access()
             && self.has_full_disk_write_access()
             && self.has_full_network_access()
     }
+
+    /// Returns a copy of this policy that can also read `readable_roots` when
+    /// it does not grant full read access.
+    pub fn with_readable_roots(&self, readable_roots: &[PathBuf]) -> Self {
+        let mut permissions = self.permissions.clone();
+        for folder in readable_roots {
+            let permission = SandboxPermission::DiskReadFolder {
+                folder: folder.clone(),
+            };
+            if !permissions.contains(&permission) {
+                permissions.push(permission);
+            }
+        }
+        Self { permissions }
+    }
+
+    /// Returns a copy of this policy that can also write to `writable_roots`.
+    pub fn with_writable_roots(&self, writable_roots: &[PathBuf]) -> Self {
+        let mut permissions = self.permissions.clone();
+        for folder in writable_roots {
+            let permission = SandboxPermission::DiskWriteFolder {
+                folder: folder.clone(),
+            };
+            if !permissions.contains(&permission) {
+                permissions.push(permission);
+            }
+        }
+        Self { permissions }
+    }
 }
 
 /// Permissions that should be granted to the sandbox in which the agent
@@ -255,6 +325,10 @@ pub enum SandboxPermission {
     /// Is allowed to read all files on disk.
     DiskFullReadAccess,
 
+    /// Is allowed to read the specified folder when `DiskFullReadAccess` is
+    /// not granted. `PathBuf` must be an absolute path.
+    DiskReadFolder { folder: PathBuf },
+
     /// Is allowed to write to the operating system's temp dir that
     /// is restricted to the user the agent is running as. For
     /// example, on macOS, this is generally something under
@@ -326,9 +400,17 @@ pub enum EventMsg {
     /// Agent text output message
     AgentMessage(AgentMessageEvent),
 
+    /// Incremental chunk of the agent's text output. The complete text is
+    /// still delivered in a subsequent `AgentMessage`.
+    AgentMessageDelta(AgentMessageDeltaEvent),
+
     /// Reasoning event from agent.
     AgentReasoning(AgentReasoningEvent),
 
+    /// Incremental chunk of the agent's reasoning. The complete text is still
+    /// delivered in a subsequent `AgentReasoning`.
+    AgentReasoningDelta(AgentReasoningDeltaEvent),
+
     /// Ack the client's configure message.
     SessionConfigured(SessionConfiguredEvent),
 
@@ -339,12 +421,17 @@ pub enum EventMsg {
     /// Notification that the server is about to execute a command.
     ExecCommandBegin(ExecCommandBeginEvent),
 
+    /// Incremental chunk of output from a running command.
+    ExecCommandOutputDelta(ExecCommandOutputDeltaEvent),
+
     ExecCommandEnd(ExecCommandEndEvent),
 
     ExecApprovalRequest(ExecApprovalRequestEvent),
 
     ApplyPatchApprovalRequest(ApplyPatchApprovalRequestEvent),
 
+    McpToolCallApprovalRequest(McpToolCallApprovalRequestEvent),
+
     BackgroundEvent(BackgroundEventEvent),
 
     /// Notification that the agent is about to apply a code patch. Mirrors
@@ -356,6 +443,12 @@ pub enum EventMsg {
 
     /// Response to GetHistoryEntryRequest.
     GetHistoryEntryResponse(GetHistoryEntryResponseEvent),
+
+    /// Response to GetMcpPromptRequest.
+    GetMcpPromptResponse(GetMcpPromptResponseEvent),
+
+    /// Token usage reported by the model, sent after every model response.
+    TokenCount(TokenCountEvent),
 }
 
 // Individual event payload types matching each `EventMsg` variant.
@@ -370,11 +463,21 @@ pub struct AgentMessageEvent {
     pub message: String,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AgentMessageDeltaEvent {
+    pub delta: String,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct AgentReasoningEvent {
     pub text: String,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AgentReasoningDeltaEvent {
+    pub delta: String,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct McpToolCallBeginEvent {
     /// Identifier so this can be paired with the McpToolCallEnd event.
@@ -407,6 +510,26 @@ pub struct ExecCommandBeginEvent {
     pub cwd: PathBuf,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecOutputStream {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ExecCommandOutputDeltaEvent {
+    /// Identifier for the ExecCommandBegin that produced this chunk.
+    pub call_id: String,
+    /// Which stream the chunk was read from.
+    pub stream: ExecOutputStream,
+    /// Output read since the previous delta for this stream. Only the
+    /// beginning of the output of the command, within the same limits as the
+    /// output sent to the model, is streamed; the last delta then ends with a
+    /// marker saying that the output was truncated.
+    pub chunk: String,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ExecCommandEndEvent {
     /// Identifier for the ExecCommandBegin that finished.
@@ -441,6 +564,18 @@ pub struct ApplyPatchApprovalRequestEvent {
     pub grant_root: Option<PathBuf>,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct McpToolCallApprovalRequestEvent {
+    /// Identifier of the tool call awaiting approval.
+    pub call_id: String,
+    /// Name of the MCP server as defined in the config.
+    pub server: String,
+    /// Name of the tool as given by the MCP server.
+    pub tool: String,
+    /// Arguments to the tool call.
+    pub arguments: Option<serde_json::Value>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct BackgroundEventEvent {
     pub message: String,
@@ -477,6 +612,14 @@ pub struct GetHistoryEntryResponseEvent {
     pub entry: Option<HistoryEntry>,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GetMcpPromptResponseEvent {
+    pub server: String,
+    pub name: String,
+    /// The messages the prompt expanded to.
+    pub result: GetPromptResult,
+}
+
 #[derive(Debug, Default, Clone, Deserialize, Serialize)]
 pub struct SessionConfiguredEvent {
     /// Unique id for this session.
@@ -490,6 +633,58 @@ pub struct SessionConfiguredEvent {
 
     /// Current number of entries in the history log.
     pub history_entry_count: usize,
+
+    /// When the session was resumed from a rollout file, the messages of the
+    /// conversation so far (oldest first) so clients can show them again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resumed_messages: Option<Vec<ResumedMessage>>,
+
+    /// Prompts offered by the configured MCP servers, keyed by server name.
+    /// Clients can offer these to the user, e.g., as slash commands.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub mcp_prompts: HashMap<String, Vec<McpPrompt>>,
+}
+
+/// Number of tokens used by one or more model responses.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    /// Portion of `input_tokens` that was served from the prompt cache.
+    pub cached_input_tokens: u64,
+    pub output_tokens: u64,
+    /// Portion of `output_tokens` that was spent on reasoning.
+    pub reasoning_output_tokens: u64,
+    pub total_tokens: u64,
+}
+
+impl std::ops::AddAssign for TokenUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.input_tokens += other.input_tokens;
+        self.cached_input_tokens += other.cached_input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.reasoning_output_tokens += other.reasoning_output_tokens;
+        self.total_tokens += other.total_tokens;
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TokenCountEvent {
+    /// Usage of the model response that just finished.
+    pub last_turn: TokenUsage,
+    /// Cumulative usage for the whole session, including `last_turn`.
+    pub session_total: TokenUsage,
+    /// Estimated cost of `session_total` in US dollars. Only present when a
+    /// price for the session's model is configured in `model_prices`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_cost_usd: Option<f64>,
+}
+
+/// A message from a previous session that was restored from its rollout file.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(tag = "role", rename_all = "snake_case")]
+pub enum ResumedMessage {
+    User { text: String },
+    Assistant { text: String },
 }
 
 /// User's decision in response to an ExecApprovalRequest.
@@ -552,6 +747,8 @@ mod tests {
                 model: "o4-mini".to_string(),
                 history_log_id: 0,
                 history_entry_count: 0,
+                resumed_messages: None,
+                mcp_prompts: HashMap::new(),
             }),
         };
         let serialized = serde_json::to_string(&event).unwrap();
@@ -560,4 +757,17 @@ mod tests {
             r#"{"id":"1234","msg":{"type":"session_configured","session_id":"67e55044-10b1-426f-9247-bb680e5fe0c8","model":"o4-mini","history_log_id":0,"history_entry_count":0}}"#
         );
     }
+
+    #[test]
+    fn with_writable_roots_adds_each_root_once() {
+        let cwd = PathBuf::from("/repo");
+        let extra = PathBuf::from("/tmp/extra");
+        let policy = SandboxPolicy::new_full_auto_policy()
+            .with_writable_roots(&[extra.clone(), extra.clone()]);
+        assert_eq!(
+            policy.get_writable_roots_with_cwd(&cwd),
+            vec![cwd.clone(), extra]
+        );
+        assert!(!policy.has_full_disk_write_access());
+    }
 }
@@ -5,8 +5,12 @@
 
 use std::fs::File;
 use std::fs::{self};
+use std::io::BufRead;
 use std::io::Error as IoError;
+use std::path::Path;
+use std::path::PathBuf;
 
+use serde::Deserialize;
 use serde::Serialize;
 use time::OffsetDateTime;
 use time::format_description::FormatItem;
@@ -22,12 +26,22 @@ use crate::models::ResponseItem;
 /// Folder inside `~/.codex` that holds saved rollouts.
 const SESSIONS_SUBDIR: &str = "sessions";
 
-#[derive(Serialize)]
-struct SessionMeta {
-    id: String,
-    timestamp: String,
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct SessionMeta {
+    pub(crate) id: String,
+    pub(crate) timestamp: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    instructions: Option<String>,
+    pub(crate) instructions: Option<String>,
+}
+
+/// Contents of a rollout file that was read back from disk so that the
+/// session can be resumed.
+#[derive(Debug, Clone)]
+pub(crate) struct SavedSession {
+    /// The [`SessionMeta`] written at the top of the file.
+    pub(crate) session: SessionMeta,
+    /// Every item recorded after the metadata, oldest first.
+    pub(crate) items: Vec<ResponseItem>,
 }
 
 /// Records all [`ResponseItem`]s for a session and flushes them to disk after
@@ -73,6 +87,22 @@ impl RolloutRecorder {
             instructions,
         };
 
+        let recorder = Self::spawn_writer(file);
+        // Ensure SessionMeta is the first item in the file.
+        recorder.record_item(&meta).await?;
+        Ok(recorder)
+    }
+
+    /// Re-open the rollout file at `path` so that a previous session can be
+    /// resumed. Returns the recorder, which appends to the same file, along
+    /// with the session metadata and every item recorded so far.
+    pub async fn resume(path: &Path) -> std::io::Result<(Self, SavedSession)> {
+        let saved = read_rollout_file(path)?;
+        let file = std::fs::OpenOptions::new().append(true).open(path)?;
+        Ok((Self::spawn_writer(file), saved))
+    }
+
+    fn spawn_writer(file: File) -> Self {
         // A reasonably-sized bounded channel. If the buffer fills up the send
         // future will yield, which is fine – we only need to ensure we do not
         // perform *blocking* I/O on the caller’s thread.
@@ -101,10 +131,7 @@ impl RolloutRecorder {
             }
         });
 
-        let recorder = Self { tx };
-        // Ensure SessionMeta is the first item in the file.
-        recorder.record_item(&meta).await?;
-        Ok(recorder)
+        Self { tx }
     }
 
     /// Append `items` to the rollout file.
@@ -183,3 +210,202 @@ fn create_log_file(config: &Config, session_id: Uuid) -> std::io::Result<LogFile
         timestamp,
     })
 }
+
+/// Parse the rollout file at `path`. The first line must be the
+/// [`SessionMeta`]; lines that do not parse as a [`ResponseItem`] are skipped
+/// so that a partially-written trailing line (e.g. after a crash) does not
+/// prevent the session from being resumed.
+fn read_rollout_file(path: &Path) -> std::io::Result<SavedSession> {
+    let file = File::open(path)?;
+    let mut lines = std::io::BufReader::new(file).lines();
+
+    let first_line = lines.next().transpose()?.ok_or_else(|| {
+        IoError::new(
+            std::io::ErrorKind::InvalidData,
+            format!("rollout file {} is empty", path.display()),
+        )
+    })?;
+    let session = serde_json::from_str::<SessionMeta>(&first_line).map_err(|e| {
+        IoError::new(
+            std::io::ErrorKind::InvalidData,
+            format!("failed to parse session metadata: {e}"),
+        )
+    })?;
+
+    let mut items = Vec::new();
+    for line in lines {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<ResponseItem>(&line) {
+            Ok(ResponseItem::Other) => {}
+            Ok(item) => items.push(item),
+            Err(e) => {
+                tracing::warn!("skipping unparseable rollout line: {e}");
+            }
+        }
+    }
+
+    Ok(SavedSession { session, items })
+}
+
+/// Resolve the argument to `codex resume` to a rollout file. `session` may
+/// either be a path to a rollout file or the id of a session whose rollout
+/// lives in `~/.codex/sessions`.
+pub(crate) fn find_rollout_file(codex_home: &Path, session: &str) -> std::io::Result<PathBuf> {
+    let path = PathBuf::from(session);
+    if path.is_file() {
+        return Ok(path);
+    }
+    find_session_rollout(codex_home, session)
+}
+
+/// A rollout file in `~/.codex/sessions`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionRollout {
+    /// Id of the session recorded in the rollout.
+    pub session_id: String,
+    pub path: PathBuf,
+}
+
+/// List the rollouts in `~/.codex/sessions`, most recent first. Files whose
+/// names do not follow the rollout naming scheme are skipped.
+pub fn list_session_rollouts(codex_home: &Path) -> Vec<SessionRollout> {
+    let dir = codex_home.join(SESSIONS_SUBDIR);
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+
+    let mut rollouts: Vec<SessionRollout> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let session_id = session_id_from_rollout_filename(&name.to_string_lossy())?;
+            Some(SessionRollout {
+                session_id,
+                path: entry.path(),
+            })
+        })
+        .collect();
+
+    // Filenames start with the creation timestamp.
+    rollouts.sort_by(|a, b| b.path.cmp(&a.path));
+    rollouts
+}
+
+/// Find the rollout of the session with the given id in `~/.codex/sessions`.
+/// Should there ever be more than one, the most recent one is returned.
+pub fn find_session_rollout(codex_home: &Path, session_id: &str) -> std::io::Result<PathBuf> {
+    list_session_rollouts(codex_home)
+        .into_iter()
+        .find(|rollout| rollout.session_id == session_id)
+        .map(|rollout| rollout.path)
+        .ok_or_else(|| {
+            IoError::new(
+                std::io::ErrorKind::NotFound,
+                format!(
+                    "no rollout found for session `{session_id}` in {}",
+                    codex_home.join(SESSIONS_SUBDIR).display()
+                ),
+            )
+        })
+}
+
+/// Extract the session id from a filename created by [`create_log_file`],
+/// i.e., `rollout-<YYYY-MM-DDThh-mm-ss>-<session id>.jsonl`.
+fn session_id_from_rollout_filename(name: &str) -> Option<String> {
+    let stem = name.strip_prefix("rollout-")?.strip_suffix(".jsonl")?;
+    // Skip the timestamp and the `-` that follows it.
+    let session_id = stem.get("YYYY-MM-DDThh-mm-ss-".len()..)?;
+    Uuid::parse_str(session_id).ok()?;
+    Some(session_id.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::models::ContentItem;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    #[test]
+    fn reads_back_session_meta_and_items() {
+        let codex_home = TempDir::new().unwrap();
+        let sessions = codex_home.path().join(SESSIONS_SUBDIR);
+        fs::create_dir_all(&sessions).unwrap();
+        let session_id = "5973b6c0-94b8-487b-a530-2aeb6098ae0e";
+        let path = sessions.join(format!("rollout-2025-05-07T17-24-21-{session_id}.jsonl"));
+        let contents = [
+            format!(r#"{{"id":"{session_id}","timestamp":"2025-05-07T17:24:21.123Z"}}"#),
+            r#"{"type":"message","role":"user","content":[{"type":"input_text","text":"hi"}]}"#
+                .to_string(),
+            r#"{"type":"message","role":"assistant","content":[{"type":"output_text","text":"hello"}]}"#
+                .to_string(),
+            // Truncated line, e.g. from a crash mid-write.
+            r#"{"type":"message","role":"ass"#.to_string(),
+        ]
+        .join("\n");
+        fs::write(&path, contents).unwrap();
+
+        assert_eq!(
+            find_rollout_file(codex_home.path(), session_id).unwrap(),
+            path
+        );
+        assert_eq!(
+            find_rollout_file(codex_home.path(), path.to_str().unwrap()).unwrap(),
+            path
+        );
+        assert!(find_rollout_file(codex_home.path(), "does-not-exist").is_err());
+
+        let saved = read_rollout_file(&path).unwrap();
+        assert_eq!(saved.session.id, session_id);
+        assert_eq!(saved.items.len(), 2);
+        match &saved.items[1] {
+            ResponseItem::Message { role, content } => {
+                assert_eq!(role, "assistant");
+                assert!(matches!(
+                    &content[0],
+                    ContentItem::OutputText { text } if text == "hello"
+                ));
+            }
+            other => panic!("unexpected item: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn lists_session_rollouts_most_recent_first() {
+        let codex_home = TempDir::new().unwrap();
+        let sessions = codex_home.path().join(SESSIONS_SUBDIR);
+        fs::create_dir_all(&sessions).unwrap();
+        let older_id = "5973b6c0-94b8-487b-a530-2aeb6098ae0e";
+        let newer_id = "0b0e3c1e-2f4a-4c55-9d8e-7a6b5c4d3e2f";
+        let older = sessions.join(format!("rollout-2025-05-07T17-24-21-{older_id}.jsonl"));
+        let newer = sessions.join(format!("rollout-2025-05-08T09-00-00-{newer_id}.jsonl"));
+        for path in [&older, &newer] {
+            fs::write(path, "").unwrap();
+        }
+        fs::write(sessions.join("notes.txt"), "").unwrap();
+
+        assert_eq!(
+            list_session_rollouts(codex_home.path()),
+            vec![
+                SessionRollout {
+                    session_id: newer_id.to_string(),
+                    path: newer,
+                },
+                SessionRollout {
+                    session_id: older_id.to_string(),
+                    path: older.clone(),
+                },
+            ]
+        );
+        assert_eq!(
+            find_session_rollout(codex_home.path(), older_id).unwrap(),
+            older
+        );
+        assert!(find_session_rollout(codex_home.path(), "../history").is_err());
+    }
+}
@@ -1,10 +1,15 @@
 use std::collections::HashSet;
+use std::ffi::OsString;
 use std::path::Component;
 use std::path::Path;
 use std::path::PathBuf;
 
 use codex_apply_patch::ApplyPatchAction;
 use codex_apply_patch::ApplyPatchFileChange;
+use codex_execpolicy::ExecCall;
+use codex_execpolicy::ExecvChecker;
+use codex_execpolicy::MatchedExec;
+use codex_execpolicy::PolicyParser;
 
 use crate::exec::SandboxType;
 use crate::is_safe_command::is_known_safe_command;
@@ -68,11 +73,44 @@ pub fn assess_command_safety(
     approval_policy: AskForApproval,
     sandbox_policy: &SandboxPolicy,
     approved: &HashSet<Vec<String>>,
+    exec_policy: Option<&ExecvChecker>,
+    cwd: &Path,
 ) -> SafetyCheck {
     let approve_without_sandbox = || SafetyCheck::AutoApprove {
         sandbox_type: SandboxType::None,
     };
 
+    // The user's exec policy, if any, takes precedence over the built-in
+    // allow-list so that it can forbid commands we would otherwise run.
+    if let Some(checker) = exec_policy {
+        match assess_command_with_exec_policy(command, checker, sandbox_policy, cwd) {
+            // The command only touches files the sandbox allows, but it still
+            // runs inside it in case the policy's spec of it is incomplete.
+            Some(ExecPolicyCheck::Safe) => {
+                return match get_platform_sandbox() {
+                    Some(sandbox_type) if !sandbox_policy.is_unrestricted() => {
+                        SafetyCheck::AutoApprove { sandbox_type }
+                    }
+                    _ => approve_without_sandbox(),
+                };
+            }
+            Some(ExecPolicyCheck::Forbidden { reason }) => {
+                return SafetyCheck::Reject { reason };
+            }
+            Some(ExecPolicyCheck::OutsideWritableRoots) if !approved.contains(command) => {
+                return match approval_policy {
+                    AskForApproval::Never => SafetyCheck::Reject {
+                        reason: "command accesses files outside of the writable roots".to_string(),
+                    },
+                    _ => SafetyCheck::AskUser,
+                };
+            }
+            // The user already approved this command for the session, or the
+            // policy says nothing about it: fall back to the default checks.
+            Some(ExecPolicyCheck::OutsideWritableRoots) | None => {}
+        }
+    }
+
     // Previously approved or allow-listed commands
     // All approval modes allow these commands to continue without sandboxing
     if is_known_safe_command(command) || approved.contains(command) {
@@ -103,6 +141,74 @@ pub fn assess_command_safety(
     }
 }
 
+/// Reads and parses the `.policy` file at `path` (see the `codex-execpolicy`
+/// crate for the format).
+pub fn load_exec_policy(path: &Path) -> anyhow::Result<ExecvChecker> {
+    let unparsed_policy = std::fs::read_to_string(path)?;
+    let policy_source = path.to_string_lossy();
+    let policy = PolicyParser::new(&policy_source, &unparsed_policy)
+        .parse()
+        .map_err(|err| err.into_anyhow())?;
+    Ok(ExecvChecker::new(policy))
+}
+
+enum ExecPolicyCheck {
+    /// The policy matched the command and the sandbox policy allows it to read
+    /// and write every file it does.
+    Safe,
+    /// The policy matched the command, but it reads or writes files the
+    /// sandbox policy does not allow it to.
+    OutsideWritableRoots,
+    /// The policy explicitly forbids the command.
+    Forbidden { reason: String },
+}
+
+/// Returns `None` if the policy has no opinion on `command`, e.g. because it
+/// does not define the program or the arguments do not match its spec.
+fn assess_command_with_exec_policy(
+    command: &[String],
+    checker: &ExecvChecker,
+    sandbox_policy: &SandboxPolicy,
+    cwd: &Path,
+) -> Option<ExecPolicyCheck> {
+    let (program, args) = command.split_first()?;
+    let exec_call = ExecCall {
+        program: program.clone(),
+        args: args.to_vec(),
+    };
+    match checker.r#match(&exec_call).ok()? {
+        MatchedExec::Match { exec } => {
+            // The checker compares canonical paths, so the folders must be
+            // canonical too.
+            let canonicalize = |folders: Vec<PathBuf>| -> Vec<PathBuf> {
+                folders
+                    .into_iter()
+                    .filter_map(|folder| cwd.join(folder).canonicalize().ok())
+                    .collect()
+            };
+            let writable_folders = if sandbox_policy.has_full_disk_write_access() {
+                canonicalize(vec![PathBuf::from("/")])
+            } else {
+                canonicalize(sandbox_policy.get_writable_roots_with_cwd(cwd))
+            };
+            let readable_folders = if sandbox_policy.has_full_disk_read_access() {
+                canonicalize(vec![PathBuf::from("/")])
+            } else {
+                let mut readable_folders =
+                    canonicalize(sandbox_policy.get_readable_roots_with_cwd(cwd));
+                readable_folders.extend(writable_folders.iter().cloned());
+                readable_folders
+            };
+            let cwd = Some(OsString::from(cwd.as_os_str()));
+            match checker.check(exec, &cwd, &readable_folders, &writable_folders) {
+                Ok(_) => Some(ExecPolicyCheck::Safe),
+                Err(_) => Some(ExecPolicyCheck::OutsideWritableRoots),
+            }
+        }
+        MatchedExec::Forbidden { reason, .. } => Some(ExecPolicyCheck::Forbidden { reason }),
+    }
+}
+
 pub fn get_platform_sandbox() -> Option<SandboxType> {
     if cfg!(target_os = "macos") {
         Some(SandboxType::MacosSeatbelt)
@@ -191,6 +297,7 @@ fn is_write_patch_constrained_to_writable_paths(
 mod tests {
     #![allow(clippy::unwrap_used)]
     use super::*;
+    use crate::protocol::SandboxPermission;
 
     #[test]
     fn test_writable_roots_constraint() {
@@ -224,4 +331,78 @@ mod tests {
             &cwd,
         ))
     }
+
+    #[test]
+    fn test_exec_policy_is_consulted() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path().to_path_buf();
+        let policy_path = root.join("exec.policy");
+        std::fs::write(
+            &policy_path,
+            r#"
+define_program(program="cp", args=[ARG_RFILE, ARG_WFILE])
+forbid_program_regex(regex="^rm$", reason="use trash instead")
+"#,
+        )
+        .unwrap();
+        let checker = load_exec_policy(&policy_path).unwrap();
+
+        let outside = tempfile::TempDir::new().unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(outside.path(), root.join("link")).unwrap();
+
+        let assess = |command: &[&str], sandbox_policy: &SandboxPolicy| {
+            let command: Vec<String> = command.iter().map(|s| s.to_string()).collect();
+            assess_command_safety(
+                &command,
+                AskForApproval::Never,
+                sandbox_policy,
+                &HashSet::new(),
+                Some(&checker),
+                &root,
+            )
+        };
+        let writable_cwd_policy = SandboxPolicy::from(vec![
+            SandboxPermission::DiskFullReadAccess,
+            SandboxPermission::DiskWriteCwd,
+        ]);
+
+        // Matched commands still run in the sandbox.
+        match assess(&["cp", "a.txt", "b.txt"], &writable_cwd_policy) {
+            SafetyCheck::AutoApprove { sandbox_type } => {
+                assert_eq!(
+                    sandbox_type,
+                    get_platform_sandbox().unwrap_or(SandboxType::None)
+                );
+            }
+            other => panic!("expected approval, got {other:?}"),
+        }
+        assert!(matches!(
+            assess(&["cp", "a.txt", "/outside/b.txt"], &writable_cwd_policy),
+            SafetyCheck::Reject { .. }
+        ));
+        #[cfg(unix)]
+        assert!(matches!(
+            assess(&["cp", "a.txt", "link/b.txt"], &writable_cwd_policy),
+            SafetyCheck::Reject { .. }
+        ));
+        // The read-only policy does not allow writing anywhere.
+        assert!(matches!(
+            assess(
+                &["cp", "a.txt", "b.txt"],
+                &SandboxPolicy::new_read_only_policy()
+            ),
+            SafetyCheck::Reject { .. }
+        ));
+        match assess(&["rm", "a.txt"], &writable_cwd_policy) {
+            SafetyCheck::Reject { reason } => assert_eq!(reason, "use trash instead"),
+            other => panic!("expected rejection, got {other:?}"),
+        }
+        // Commands the policy does not know about fall back to the default
+        // checks.
+        assert!(matches!(
+            assess(&["pwd"], &writable_cwd_policy),
+            SafetyCheck::AutoApprove { .. }
+        ));
+    }
 }
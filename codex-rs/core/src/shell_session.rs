@@ -0,0 +1,485 @@
+//! Persistent, interactive shell sessions for the model. Unlike a `shell`
+//! call, which runs a fresh process, a session keeps a command (usually a
+//! shell or a REPL) running in a pseudoterminal across tool calls, so the
+//! working directory, exported variables, and long-running processes survive
+//! from one call to the next.
+
+// Poisoned mutex should fail the program
+#![allow(clippy::unwrap_used)]
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::sync::watch;
+
+use crate::error::Result;
+use crate::exec::ExecParams;
+use crate::exec::SandboxType;
+use crate::protocol::SandboxPolicy;
+
+pub(crate) const SHELL_SESSION_START_TOOL_NAME: &str = "shell_session_start";
+pub(crate) const SHELL_SESSION_WRITE_TOOL_NAME: &str = "shell_session_write";
+pub(crate) const SHELL_SESSION_READ_TOOL_NAME: &str = "shell_session_read";
+pub(crate) const SHELL_SESSION_KILL_TOOL_NAME: &str = "shell_session_kill";
+
+/// How long to collect output after starting, writing to, or reading from a
+/// session if the model does not specify a `timeout`.
+const DEFAULT_OUTPUT_TIMEOUT_MS: u64 = 1_000;
+
+/// How long to wait for the remaining output of a killed session.
+const KILL_OUTPUT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long a write may block because the command is not reading its input.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Output that the model has not read yet is capped at this many bytes; the
+/// oldest output is dropped first.
+const MAX_UNREAD_OUTPUT: usize = 10 * 1024;
+
+/// Size of the pseudoterminal. It is wider than usual so that less output is
+/// wrapped.
+#[cfg(unix)]
+const TERMINAL_ROWS: u16 = 40;
+#[cfg(unix)]
+const TERMINAL_COLS: u16 = 120;
+
+#[derive(Deserialize)]
+pub(crate) struct ShellSessionWriteArgs {
+    pub session_id: u32,
+    pub input: String,
+    #[serde(rename = "timeout")]
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct ShellSessionReadArgs {
+    pub session_id: u32,
+    #[serde(rename = "timeout")]
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct ShellSessionKillArgs {
+    pub session_id: u32,
+}
+
+/// Result of a shell session tool call, serialized as JSON for the model.
+#[derive(Debug, Serialize)]
+pub(crate) struct ShellSessionOutput {
+    pub session_id: u32,
+    /// Output produced since the previous call for this session.
+    pub output: String,
+    /// Set once the command has exited.
+    pub exit_code: Option<i32>,
+}
+
+/// Time to collect output for, given the `timeout` passed by the model,
+/// capped at `max_timeout_ms`.
+pub(crate) fn output_timeout(timeout_ms: Option<u64>, max_timeout_ms: Option<u64>) -> Duration {
+    let timeout_ms = timeout_ms.unwrap_or(DEFAULT_OUTPUT_TIMEOUT_MS);
+    Duration::from_millis(match max_timeout_ms {
+        Some(max_timeout_ms) => timeout_ms.min(max_timeout_ms),
+        None => timeout_ms,
+    })
+}
+
+/// The shell sessions of a Codex session. Dropping the manager kills all of
+/// them.
+#[derive(Default)]
+pub(crate) struct ShellSessionManager {
+    next_session_id: AtomicU32,
+    sessions: Mutex<HashMap<u32, Arc<ShellSession>>>,
+}
+
+impl ShellSessionManager {
+    /// Starts `params.command` in a new pseudoterminal under `sandbox_type`.
+    /// `params.timeout_ms` is ignored: sessions run until they exit or are
+    /// killed.
+    pub(crate) fn start(
+        &self,
+        params: ExecParams,
+        sandbox_type: SandboxType,
+        sandbox_policy: &SandboxPolicy,
+        input_needs_approval: bool,
+    ) -> Result<Arc<ShellSession>> {
+        let command = params.command.clone();
+        let cwd = params.cwd.clone();
+        let (child, terminal) = spawn_in_terminal(params, sandbox_type, sandbox_policy)?;
+
+        let id = self.next_session_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let (closed_tx, closed_rx) = watch::channel(false);
+        let session = Arc::new(ShellSession {
+            id,
+            command,
+            cwd,
+            input_needs_approval: AtomicBool::new(input_needs_approval),
+            terminal: Arc::new(Mutex::new(terminal.try_clone()?)),
+            output: Arc::new(Mutex::new(UnreadOutput::default())),
+            closed: closed_rx,
+            child: Mutex::new(child),
+        });
+        spawn_output_reader(terminal, session.output.clone(), closed_tx);
+
+        self.sessions.lock().unwrap().insert(id, session.clone());
+        Ok(session)
+    }
+
+    pub(crate) fn get(&self, id: u32) -> Option<Arc<ShellSession>> {
+        self.sessions.lock().unwrap().get(&id).cloned()
+    }
+
+    /// Kills the session `id` and returns the rest of its output.
+    pub(crate) async fn kill(&self, id: u32) -> Option<ShellSessionOutput> {
+        let session = self.sessions.lock().unwrap().remove(&id)?;
+        session.kill();
+        Some(session.read(KILL_OUTPUT_TIMEOUT).await)
+    }
+}
+
+pub(crate) struct ShellSession {
+    id: u32,
+    command: Vec<String>,
+    cwd: PathBuf,
+
+    /// Whether the user must approve input before it is written, because the
+    /// command runs outside the sandbox. Cleared once the user approves input
+    /// for the rest of the session.
+    input_needs_approval: AtomicBool,
+
+    /// Main side of the pseudoterminal, for writing input.
+    terminal: Arc<Mutex<File>>,
+    output: Arc<Mutex<UnreadOutput>>,
+
+    /// Becomes `true` once all the output has been read, i.e., once every
+    /// process using the terminal has exited.
+    closed: watch::Receiver<bool>,
+
+    child: Mutex<std::process::Child>,
+}
+
+impl ShellSession {
+    pub(crate) fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub(crate) fn command(&self) -> &[String] {
+        &self.command
+    }
+
+    pub(crate) fn cwd(&self) -> &Path {
+        &self.cwd
+    }
+
+    pub(crate) fn input_needs_approval(&self) -> bool {
+        self.input_needs_approval.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn approve_input(&self) {
+        self.input_needs_approval.store(false, Ordering::Relaxed);
+    }
+
+    pub(crate) async fn write(&self, input: &str) -> io::Result<()> {
+        let terminal = self.terminal.clone();
+        let input = input.as_bytes().to_vec();
+        let write = tokio::task::spawn_blocking(move || terminal.lock().unwrap().write_all(&input));
+        match tokio::time::timeout(WRITE_TIMEOUT, write).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(e)) => Err(io::Error::other(e)),
+            Err(_) => Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "the command is not reading its input",
+            )),
+        }
+    }
+
+    /// Collects output for `timeout`, or until the command exits, and returns
+    /// everything that has not been read yet.
+    pub(crate) async fn read(&self, timeout: Duration) -> ShellSessionOutput {
+        let mut closed = self.closed.clone();
+        let _ = tokio::time::timeout(timeout, closed.wait_for(|closed| *closed)).await;
+
+        let output = self.output.lock().unwrap().take();
+        let mut exit_code = self.try_exit_code();
+        // The terminal is closed as the command exits, so it may not have
+        // been reaped yet.
+        for _ in 0..10 {
+            if exit_code.is_some() || !*closed.borrow() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            exit_code = self.try_exit_code();
+        }
+        ShellSessionOutput {
+            session_id: self.id,
+            output,
+            exit_code,
+        }
+    }
+
+    fn try_exit_code(&self) -> Option<i32> {
+        match self.child.lock().unwrap().try_wait() {
+            Ok(Some(status)) => Some(status.code().unwrap_or(-1)),
+            Ok(None) | Err(_) => None,
+        }
+    }
+
+    fn kill(&self) {
+        let mut child = self.child.lock().unwrap();
+        if let Ok(None) = child.try_wait() {
+            kill_process_group(child.id());
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+impl Drop for ShellSession {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
+
+/// Output of a session that the model has not read yet.
+#[derive(Default)]
+struct UnreadOutput {
+    bytes: Vec<u8>,
+    /// Number of bytes dropped because of [`MAX_UNREAD_OUTPUT`].
+    dropped: usize,
+}
+
+impl UnreadOutput {
+    fn push(&mut self, chunk: &[u8]) {
+        self.bytes.extend_from_slice(chunk);
+        if self.bytes.len() > MAX_UNREAD_OUTPUT {
+            let excess = self.bytes.len() - MAX_UNREAD_OUTPUT;
+            self.bytes.drain(..excess);
+            self.dropped += excess;
+        }
+    }
+
+    /// Takes the unread output, except for a trailing incomplete UTF-8
+    /// character, which is kept until the rest of it has been read.
+    fn take(&mut self) -> String {
+        let len = match std::str::from_utf8(&self.bytes) {
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Ok(_) | Err(_) => self.bytes.len(),
+        };
+        let bytes: Vec<u8> = self.bytes.drain(..len).collect();
+        let text = String::from_utf8_lossy(&bytes);
+        match std::mem::take(&mut self.dropped) {
+            0 => text.into_owned(),
+            dropped => format!("[... {dropped} bytes of earlier output dropped ...]\n{text}"),
+        }
+    }
+}
+
+/// Reads the output of a session from the main side of its pseudoterminal on
+/// a dedicated thread until every process using the terminal has exited.
+fn spawn_output_reader(
+    mut terminal: File,
+    output: Arc<Mutex<UnreadOutput>>,
+    closed: watch::Sender<bool>,
+) {
+    std::thread::spawn(move || {
+        let mut buf = [0u8; 8192];
+        loop {
+            match terminal.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => output.lock().unwrap().push(&buf[..n]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                // Linux reports `EIO` once the subsidiary side is closed.
+                Err(_) => break,
+            }
+        }
+        let _ = closed.send(true);
+    });
+}
+
+/// Spawns the command of `params` with a new pseudoterminal as its controlling
+/// terminal, using the same sandbox backends as `process_exec_tool_call()`.
+/// Returns the child and the main side of the terminal.
+#[cfg(unix)]
+fn spawn_in_terminal(
+    params: ExecParams,
+    sandbox_type: SandboxType,
+    sandbox_policy: &SandboxPolicy,
+) -> Result<(std::process::Child, File)> {
+    use crate::exec::create_seatbelt_command;
+    use crate::exec::create_terminal_command;
+    use crate::exec_linux::spawn_terminal_child_linux;
+
+    let ExecParams {
+        command, cwd, env, ..
+    } = params;
+    let (terminal, subsidiary) = open_terminal()?;
+    let command = match sandbox_type {
+        SandboxType::MacosSeatbelt => create_seatbelt_command(command, sandbox_policy, &cwd),
+        _ => command,
+    };
+    let mut cmd = create_terminal_command(command, cwd.clone(), sandbox_policy, env, &subsidiary)?;
+    let child = match sandbox_type {
+        SandboxType::None | SandboxType::MacosSeatbelt => cmd.spawn()?,
+        SandboxType::LinuxSeccomp => spawn_terminal_child_linux(cmd, &cwd, sandbox_policy, false)?,
+        SandboxType::LinuxNetworkNamespace => {
+            spawn_terminal_child_linux(cmd, &cwd, sandbox_policy, true)?
+        }
+    };
+    Ok((child, terminal))
+}
+
+#[cfg(not(unix))]
+fn spawn_in_terminal(
+    _params: ExecParams,
+    _sandbox_type: SandboxType,
+    _sandbox_policy: &SandboxPolicy,
+) -> Result<(std::process::Child, File)> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "shell sessions are not supported on this platform",
+    )
+    .into())
+}
+
+/// Opens a new pseudoterminal and returns its main and subsidiary sides.
+#[cfg(unix)]
+fn open_terminal() -> io::Result<(File, File)> {
+    use std::os::fd::FromRawFd;
+
+    let mut main = -1;
+    let mut subsidiary = -1;
+    let mut size = libc::winsize {
+        ws_row: TERMINAL_ROWS,
+        ws_col: TERMINAL_COLS,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: `openpty()` only writes the two file descriptors.
+    // macOS declares the size as `*mut winsize`.
+    #[allow(clippy::unnecessary_mut_passed)]
+    let rc = unsafe {
+        libc::openpty(
+            &mut main,
+            &mut subsidiary,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            &mut size,
+        )
+    };
+    if rc == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: both file descriptors were just opened and nothing else owns
+    // them.
+    let (main, subsidiary) = unsafe { (File::from_raw_fd(main), File::from_raw_fd(subsidiary)) };
+
+    // Unlike the standard library, `openpty()` does not set `FD_CLOEXEC`, so
+    // other commands would inherit the terminal.
+    for file in [&main, &subsidiary] {
+        use std::os::fd::AsRawFd;
+
+        // SAFETY: `file` is a valid, open file descriptor.
+        if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok((main, subsidiary))
+}
+
+/// Kills the process group led by `pid`. The command of a session leads its
+/// own process group, so this also kills the processes it started.
+#[cfg(unix)]
+fn kill_process_group(pid: u32) {
+    if let Ok(pid) = libc::pid_t::try_from(pid) {
+        // SAFETY: `killpg()` has no memory safety requirements.
+        unsafe {
+            libc::killpg(pid, libc::SIGKILL);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(_pid: u32) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ShellEnvironmentPolicy;
+    use crate::exec_env::create_env;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn output_timeout_is_capped_at_the_max_timeout() {
+        assert_eq!(
+            output_timeout(None, None),
+            Duration::from_millis(DEFAULT_OUTPUT_TIMEOUT_MS)
+        );
+        assert_eq!(
+            output_timeout(Some(600_000), None),
+            Duration::from_millis(600_000)
+        );
+        assert_eq!(
+            output_timeout(Some(600_000), Some(5_000)),
+            Duration::from_millis(5_000)
+        );
+    }
+
+    #[test]
+    fn unread_output_drops_oldest_bytes_and_keeps_partial_characters() {
+        let mut output = UnreadOutput::default();
+        output.push(&vec![b'a'; MAX_UNREAD_OUTPUT]);
+        output.push(b"bc");
+        // "é" is two bytes; only the first one has been read so far.
+        output.push(&"é".as_bytes()[..1]);
+
+        let text = output.take();
+        assert!(text.starts_with("[... 3 bytes of earlier output dropped ...]\n"));
+        assert!(text.ends_with("abc"));
+
+        output.push(&"é".as_bytes()[1..]);
+        assert_eq!(output.take(), "é");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn session_keeps_state_between_writes() {
+        let manager = ShellSessionManager::default();
+        let params = ExecParams {
+            command: vec!["sh".to_string()],
+            cwd: std::env::temp_dir(),
+            timeout_ms: None,
+            env: create_env(&ShellEnvironmentPolicy::default()),
+            stdin: None,
+        };
+        let session = manager
+            .start(
+                params,
+                SandboxType::None,
+                &SandboxPolicy::new_full_auto_policy(),
+                false,
+            )
+            .unwrap();
+
+        session.write("GREETING=hello\n").await.unwrap();
+        session.write("echo \"$GREETING from $$\"\n").await.unwrap();
+        session.write("exit 3\n").await.unwrap();
+        let output = session.read(Duration::from_secs(10)).await;
+        assert!(output.output.contains("hello from "), "{output:?}");
+        assert_eq!(output.exit_code, Some(3));
+
+        assert!(manager.kill(session.id()).await.is_some());
+        assert!(manager.get(session.id()).is_none());
+    }
+}
@@ -0,0 +1,176 @@
+use std::time::Duration;
+
+use codex_core::Codex;
+use codex_core::ModelProviderInfo;
+use codex_core::exec::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+use codex_core::protocol::ErrorEvent;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+mod test_support;
+use serde_json::Value;
+use serde_json::json;
+use tempfile::TempDir;
+use test_support::load_default_config_for_test;
+use tokio::time::timeout;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::Request;
+use wiremock::Respond;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+/// Marker of the request that asks the model for a summary.
+const COMPACT_PROMPT_START: &str = "You are about to run out of context.";
+
+/// Build an SSE stream with one assistant message and the token usage.
+fn sse_message(id: &str, text: &str, input_tokens: u64) -> String {
+    let item = json!({
+        "type": "response.output_item.done",
+        "item": {
+            "type": "message",
+            "role": "assistant",
+            "content": [{ "type": "output_text", "text": text }],
+        },
+    });
+    let completed = json!({
+        "type": "response.completed",
+        "response": {
+            "id": id,
+            "usage": {
+                "input_tokens": input_tokens,
+                "output_tokens": 1,
+                "total_tokens": input_tokens + 1,
+            },
+        },
+    });
+    format!(
+        "event: response.output_item.done\ndata: {item}\n\n\
+event: response.completed\ndata: {completed}\n\n\n"
+    )
+}
+
+/// Answers the compaction request with a summary, and reports the request of
+/// the third task as taking up most of the context window.
+struct ModelResponder;
+
+impl Respond for ModelResponder {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let body = String::from_utf8_lossy(&request.body);
+        let sse = if body.contains(COMPACT_PROMPT_START) {
+            sse_message("resp_summary", "SUMMARY-OF-ALPHA", 10)
+        } else if body.contains("charlie-request") && !body.contains("delta-request") {
+            sse_message("resp_charlie", "charlie-reply", 200_000)
+        } else {
+            sse_message("resp_other", "other-reply", 10)
+        };
+        ResponseTemplate::new(200)
+            .insert_header("content-type", "text/event-stream")
+            .set_body_raw(sse, "text/event-stream")
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn compacts_server_side_conversation_from_reported_token_count() {
+    #![allow(clippy::unwrap_used)]
+
+    if std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(ModelResponder)
+        .mount(&server)
+        .await;
+
+    // Update environment – `set_var` is `unsafe` starting with the 2024
+    // edition so we group the calls into a single `unsafe { … }` block.
+    unsafe {
+        std::env::set_var("OPENAI_REQUEST_MAX_RETRIES", "0");
+        std::env::set_var("OPENAI_STREAM_MAX_RETRIES", "0");
+    }
+    let model_provider = ModelProviderInfo {
+        name: "openai".into(),
+        base_url: format!("{}/v1", server.uri()),
+        // Environment variable that should exist in the test environment.
+        env_key: Some("PATH".into()),
+        env_key_instructions: None,
+        wire_api: codex_core::WireApi::Responses,
+    };
+
+    // The Responses API keeps the conversation on the server, so there is no
+    // transcript whose size could be estimated.
+    let codex_home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&codex_home);
+    config.model_provider = model_provider;
+    config.disable_response_storage = false;
+    config.auto_compact_token_limit = 100_000;
+    let ctrl_c = std::sync::Arc::new(tokio::sync::Notify::new());
+    let (codex, _init_id) = Codex::spawn(config, ctrl_c.clone()).await.unwrap();
+
+    for text in [
+        "alpha-request",
+        "bravo-request",
+        "charlie-request",
+        "delta-request",
+    ] {
+        codex
+            .submit(Op::UserInput {
+                items: vec![InputItem::Text { text: text.into() }],
+            })
+            .await
+            .unwrap();
+        loop {
+            let ev = timeout(Duration::from_secs(5), codex.next_event())
+                .await
+                .unwrap()
+                .unwrap();
+            match ev.msg {
+                EventMsg::TaskComplete => break,
+                EventMsg::Error(ErrorEvent { message }) => {
+                    panic!("unexpected error: {message}")
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let bodies: Vec<Value> = server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| serde_json::from_slice(&request.body).unwrap())
+        .collect();
+    assert_eq!(bodies.len(), 5, "expected four turns and one compaction");
+
+    // Only the turns before the last two are summarized...
+    let compact_input = bodies[3]["input"].to_string();
+    assert!(compact_input.contains(COMPACT_PROMPT_START));
+    assert!(compact_input.contains("alpha-request"));
+    assert!(!compact_input.contains("bravo-request"));
+    assert!(!compact_input.contains("charlie-request"));
+    assert!(bodies[3].get("previous_response_id").is_none());
+
+    // ...and the last two are kept verbatim after the summary.
+    let turn_input = bodies[4]["input"].to_string();
+    assert!(bodies[4].get("previous_response_id").is_none());
+    assert!(!turn_input.contains("alpha-request"));
+    let positions: Vec<usize> = [
+        "SUMMARY-OF-ALPHA",
+        "bravo-request",
+        "charlie-request",
+        "charlie-reply",
+        "delta-request",
+    ]
+    .iter()
+    .map(|text| turn_input.find(text).unwrap())
+    .collect();
+    assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+}
@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use codex_core::Codex;
+use codex_core::ModelProviderInfo;
+use codex_core::exec::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+use codex_core::mcp_server_config::McpServerConfig;
+use codex_core::mcp_server_config::McpServerTransportConfig;
+use codex_core::mcp_server_config::McpToolApproval;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::ErrorEvent;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::ReviewDecision;
+mod test_support;
+use serde_json::json;
+use tempfile::TempDir;
+use test_support::load_default_config_for_test;
+use tokio::time::timeout;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::Request;
+use wiremock::Respond;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+/// A stdio MCP server that offers the tools `echo` and `free` and answers
+/// every call to them with the text `called`.
+const MCP_SERVER_SCRIPT: &str = r#"
+while IFS= read -r line; do
+  id=$(printf '%s' "$line" | sed -n 's/.*"id":\([0-9]*\).*/\1/p')
+  [ -z "$id" ] && continue
+  case "$line" in
+    *'"method":"initialize"'*)
+      echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":{\"capabilities\":{\"tools\":{}},\"protocolVersion\":\"2025-03-26\",\"serverInfo\":{\"name\":\"test\",\"version\":\"0.0.0\"}}}" ;;
+    *'"method":"tools/list"'*)
+      echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":{\"tools\":[{\"name\":\"echo\",\"inputSchema\":{\"type\":\"object\"}},{\"name\":\"free\",\"inputSchema\":{\"type\":\"object\"}}]}}" ;;
+    *'"method":"tools/call"'*)
+      echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":{\"content\":[{\"type\":\"text\",\"text\":\"called\"}]}}" ;;
+    *)
+      echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"error\":{\"code\":-32601,\"message\":\"method not found\"}}" ;;
+  esac
+done
+"#;
+
+/// The MCP tool calls the model makes, one per request, in order.
+const TOOL_CALLS: [(&str, &str); 4] = [
+    ("alpha", "echo"),
+    ("alpha", "echo"),
+    ("alpha", "free"),
+    ("beta", "echo"),
+];
+
+fn sh_server_config(
+    approval: McpToolApproval,
+    tool_approval: &[(&str, McpToolApproval)],
+) -> McpServerConfig {
+    McpServerConfig {
+        transport: McpServerTransportConfig::Stdio {
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), MCP_SERVER_SCRIPT.to_string()],
+            env: None,
+        },
+        startup_timeout_ms: None,
+        tool_timeout_ms: Some(5_000),
+        approval: Some(approval),
+        tool_approval: tool_approval
+            .iter()
+            .map(|(tool, approval)| (tool.to_string(), *approval))
+            .collect(),
+    }
+}
+
+fn sse(item: serde_json::Value, id: &str) -> String {
+    let completed = json!({
+        "type": "response.completed",
+        "response": { "id": id },
+    });
+    format!(
+        "event: response.output_item.done\ndata: {}\n\n\
+event: response.completed\ndata: {completed}\n\n\n",
+        json!({ "type": "response.output_item.done", "item": item })
+    )
+}
+
+/// Makes the calls in `TOOL_CALLS`, one per request, and then ends the turn.
+struct ModelResponder {
+    requests: AtomicUsize,
+}
+
+impl Respond for ModelResponder {
+    fn respond(&self, _request: &Request) -> ResponseTemplate {
+        let n = self.requests.fetch_add(1, Ordering::SeqCst);
+        let id = format!("resp_{n}");
+        let body = match TOOL_CALLS.get(n) {
+            Some((server, tool)) => sse(
+                json!({
+                    "type": "function_call",
+                    "name": format!("{server}__OAI_CODEX_MCP__{tool}"),
+                    "arguments": "{}",
+                    "call_id": format!("call-{n}"),
+                }),
+                &id,
+            ),
+            None => sse(
+                json!({
+                    "type": "message",
+                    "role": "assistant",
+                    "content": [{ "type": "output_text", "text": "done" }],
+                }),
+                &id,
+            ),
+        };
+        ResponseTemplate::new(200)
+            .insert_header("content-type", "text/event-stream")
+            .set_body_raw(body, "text/event-stream")
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn session_approvals_are_per_server_and_denials_reach_the_model() {
+    #![allow(clippy::unwrap_used)]
+
+    if std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(ModelResponder {
+            requests: AtomicUsize::new(0),
+        })
+        .mount(&server)
+        .await;
+
+    // Update environment – `set_var` is `unsafe` starting with the 2024
+    // edition so we group the calls into a single `unsafe { … }` block.
+    unsafe {
+        std::env::set_var("OPENAI_REQUEST_MAX_RETRIES", "0");
+        std::env::set_var("OPENAI_STREAM_MAX_RETRIES", "0");
+    }
+    let model_provider = ModelProviderInfo {
+        name: "openai".into(),
+        base_url: format!("{}/v1", server.uri()),
+        // Environment variable that should exist in the test environment.
+        env_key: Some("PATH".into()),
+        env_key_instructions: None,
+        wire_api: codex_core::WireApi::Responses,
+    };
+
+    let codex_home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&codex_home);
+    config.model_provider = model_provider;
+    config.approval_policy = AskForApproval::UnlessAllowListed;
+    config.mcp_servers = HashMap::from([
+        (
+            "alpha".to_string(),
+            sh_server_config(McpToolApproval::Ask, &[("free", McpToolApproval::Always)]),
+        ),
+        (
+            "beta".to_string(),
+            sh_server_config(McpToolApproval::Ask, &[]),
+        ),
+    ]);
+    let ctrl_c = std::sync::Arc::new(tokio::sync::Notify::new());
+    let (codex, _init_id) = Codex::spawn(config, ctrl_c.clone()).await.unwrap();
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "call the tools".into(),
+            }],
+        })
+        .await
+        .unwrap();
+
+    let mut approval_requests = Vec::new();
+    let mut calls = Vec::new();
+    loop {
+        let ev = timeout(Duration::from_secs(10), codex.next_event())
+            .await
+            .unwrap()
+            .unwrap();
+        match ev.msg {
+            EventMsg::McpToolCallApprovalRequest(request) => {
+                // Approve `alpha` for the rest of the session, deny `beta`.
+                let decision = if request.server == "alpha" {
+                    ReviewDecision::ApprovedForSession
+                } else {
+                    ReviewDecision::Denied
+                };
+                approval_requests.push((request.server, request.tool));
+                codex
+                    .submit(Op::McpToolCallApproval {
+                        id: ev.id.clone(),
+                        decision,
+                    })
+                    .await
+                    .unwrap();
+            }
+            EventMsg::McpToolCallBegin(begin) => calls.push((begin.server, begin.tool)),
+            EventMsg::TaskComplete => break,
+            EventMsg::Error(ErrorEvent { message }) => {
+                panic!("unexpected error: {message}")
+            }
+            _ => {}
+        }
+    }
+
+    let pairs = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(server, tool)| (server.to_string(), tool.to_string()))
+            .collect()
+    };
+    // The session approval of `alpha/echo` covers its second call but not
+    // `beta/echo`, and `alpha/free` never needs approval.
+    assert_eq!(
+        approval_requests,
+        pairs(&[("alpha", "echo"), ("beta", "echo")])
+    );
+    assert_eq!(
+        calls,
+        pairs(&[("alpha", "echo"), ("alpha", "echo"), ("alpha", "free")])
+    );
+
+    // The denial is reported to the model as the output of the call.
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), TOOL_CALLS.len() + 1);
+    let last: serde_json::Value = requests.last().unwrap().body_json().unwrap();
+    let denial = last["input"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|item| item["type"] == "function_call_output" && item["call_id"] == "call-3")
+        .unwrap();
+    assert_eq!(denial["output"], "tool call rejected by user");
+}
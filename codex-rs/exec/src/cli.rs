@@ -37,6 +37,25 @@ pub struct Cli {
     #[arg(long = "disable-response-storage", default_value_t = false)]
     pub disable_response_storage: bool,
 
+    /// Resume a previous session, given either its session id or the path to
+    /// its rollout file under `~/.codex/sessions`.
+    #[arg(long = "resume", value_name = "SESSION_ID|PATH")]
+    pub resume: Option<String>,
+
+    /// Print the output of commands to stderr while they run, instead of
+    /// only the truncated output once they finish.
+    #[arg(long = "stream-exec-output", default_value_t = false)]
+    pub stream_exec_output: bool,
+
+    /// Print each event to stdout as a line of JSON instead of human-readable
+    /// text.
+    #[arg(long = "json", default_value_t = false)]
+    pub json: bool,
+
+    /// Write the last message from the agent to this file once the task ends.
+    #[arg(long = "output-last-message", value_name = "FILE")]
+    pub last_message_file: Option<PathBuf>,
+
     /// Specifies color settings for use in the output.
     #[arg(long = "color", value_enum, default_value_t = Color::Auto)]
     pub color: Color,
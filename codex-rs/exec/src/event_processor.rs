@@ -1,22 +1,28 @@
 use chrono::Utc;
 use codex_common::elapsed::format_elapsed;
+use codex_core::protocol::AgentMessageDeltaEvent;
 use codex_core::protocol::AgentMessageEvent;
+use codex_core::protocol::AgentReasoningDeltaEvent;
 use codex_core::protocol::BackgroundEventEvent;
 use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::ExecCommandBeginEvent;
 use codex_core::protocol::ExecCommandEndEvent;
+use codex_core::protocol::ExecCommandOutputDeltaEvent;
 use codex_core::protocol::FileChange;
 use codex_core::protocol::McpToolCallBeginEvent;
 use codex_core::protocol::McpToolCallEndEvent;
 use codex_core::protocol::PatchApplyBeginEvent;
 use codex_core::protocol::PatchApplyEndEvent;
+use codex_core::protocol::ResumedMessage;
 use codex_core::protocol::SessionConfiguredEvent;
+use codex_core::protocol::TokenCountEvent;
 use owo_colors::OwoColorize;
 use owo_colors::Style;
 use shlex::try_join;
 use std::collections::HashMap;
+use std::io::Write;
 use std::time::Instant;
 
 /// This should be configurable. When used in CI, users may not want to impose
@@ -32,6 +38,20 @@ pub(crate) struct EventProcessor {
     /// received.
     call_id_to_tool_call: HashMap<String, McpToolCallBegin>,
 
+    /// Whether deltas of the current agent message (or reasoning) have been
+    /// printed, in which case the complete text must not be printed again.
+    /// An item with several parts ends with one complete event per part, back
+    /// to back, so these are only cleared by an event of another kind.
+    agent_message_streamed: bool,
+    agent_reasoning_streamed: bool,
+
+    /// Set while the last line printed is a partially streamed message that
+    /// still needs to be terminated with a newline.
+    open_stream: Option<StreamKind>,
+
+    /// Whether to copy the output of running commands to stderr.
+    stream_exec_output: bool,
+
     // To ensure that --color=never is respected, ANSI escapes _must_ be added
     // using .style() with one of these fields. If you need a new style, add a
     // new field here.
@@ -44,7 +64,7 @@ pub(crate) struct EventProcessor {
 }
 
 impl EventProcessor {
-    pub(crate) fn create_with_ansi(with_ansi: bool) -> Self {
+    pub(crate) fn create_with_ansi(with_ansi: bool, stream_exec_output: bool) -> Self {
         let call_id_to_command = HashMap::new();
         let call_id_to_patch = HashMap::new();
         let call_id_to_tool_call = HashMap::new();
@@ -59,6 +79,10 @@ impl EventProcessor {
                 red: Style::new().red(),
                 green: Style::new().green(),
                 call_id_to_tool_call,
+                agent_message_streamed: false,
+                agent_reasoning_streamed: false,
+                open_stream: None,
+                stream_exec_output,
             }
         } else {
             Self {
@@ -70,11 +94,21 @@ impl EventProcessor {
                 red: Style::new(),
                 green: Style::new(),
                 call_id_to_tool_call,
+                agent_message_streamed: false,
+                agent_reasoning_streamed: false,
+                open_stream: None,
+                stream_exec_output,
             }
         }
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum StreamKind {
+    AgentMessage,
+    AgentReasoning,
+}
+
 struct ExecCommandBegin {
     command: Vec<String>,
     start_time: Instant,
@@ -93,6 +127,15 @@ struct PatchApplyBegin {
     auto_approved: bool,
 }
 
+macro_rules! ts_print {
+    ($($arg:tt)*) => {{
+        let now = Utc::now();
+        let formatted = now.format("%Y-%m-%dT%H:%M:%S").to_string();
+        print!("[{}] ", formatted);
+        print!($($arg)*);
+    }};
+}
+
 macro_rules! ts_println {
     ($($arg:tt)*) => {{
         let now = Utc::now();
@@ -105,6 +148,25 @@ macro_rules! ts_println {
 impl EventProcessor {
     pub(crate) fn process_event(&mut self, event: Event) {
         let Event { id, msg } = event;
+        let is_delta = matches!(
+            msg,
+            EventMsg::AgentMessageDelta(_) | EventMsg::AgentReasoningDelta(_)
+        );
+        if !is_delta && self.open_stream.take().is_some() {
+            println!();
+        }
+        if !matches!(
+            msg,
+            EventMsg::AgentMessage(_) | EventMsg::AgentMessageDelta(_)
+        ) {
+            self.agent_message_streamed = false;
+        }
+        if !matches!(
+            msg,
+            EventMsg::AgentReasoning(_) | EventMsg::AgentReasoningDelta(_)
+        ) {
+            self.agent_reasoning_streamed = false;
+        }
         match msg {
             EventMsg::Error(ErrorEvent { message }) => {
                 let prefix = "ERROR:".style(self.red);
@@ -122,8 +184,24 @@ impl EventProcessor {
                 ts_println!("{}", msg.style(self.bold));
             }
             EventMsg::AgentMessage(AgentMessageEvent { message }) => {
-                let prefix = "Agent message:".style(self.bold);
-                ts_println!("{prefix} {message}");
+                // Skip the text if it was already printed as it was streamed.
+                if !self.agent_message_streamed {
+                    let prefix = "Agent message:".style(self.bold);
+                    ts_println!("{prefix} {message}");
+                }
+            }
+            EventMsg::AgentMessageDelta(AgentMessageDeltaEvent { delta }) => {
+                if self.open_stream != Some(StreamKind::AgentMessage) {
+                    if self.open_stream.is_some() {
+                        println!();
+                    }
+                    let prefix = "Agent message:".style(self.bold);
+                    ts_print!("{prefix} ");
+                    self.open_stream = Some(StreamKind::AgentMessage);
+                    self.agent_message_streamed = true;
+                }
+                print!("{delta}");
+                let _ = std::io::stdout().flush();
             }
             EventMsg::ExecCommandBegin(ExecCommandBeginEvent {
                 call_id,
@@ -144,6 +222,15 @@ impl EventProcessor {
                     cwd.to_string_lossy(),
                 );
             }
+            EventMsg::ExecCommandOutputDelta(ExecCommandOutputDeltaEvent {
+                call_id: _,
+                stream: _,
+                chunk,
+            }) => {
+                if self.stream_exec_output {
+                    eprint!("{chunk}");
+                }
+            }
             EventMsg::ExecCommandEnd(ExecCommandEndEvent {
                 call_id,
                 stdout,
@@ -372,8 +459,25 @@ impl EventProcessor {
             EventMsg::ApplyPatchApprovalRequest(_) => {
                 // Should we exit?
             }
+            EventMsg::McpToolCallApprovalRequest(_) => {
+                // Should we exit?
+            }
             EventMsg::AgentReasoning(agent_reasoning_event) => {
-                println!("thinking: {}", agent_reasoning_event.text);
+                if !self.agent_reasoning_streamed {
+                    println!("thinking: {}", agent_reasoning_event.text);
+                }
+            }
+            EventMsg::AgentReasoningDelta(AgentReasoningDeltaEvent { delta }) => {
+                if self.open_stream != Some(StreamKind::AgentReasoning) {
+                    if self.open_stream.is_some() {
+                        println!();
+                    }
+                    print!("thinking: ");
+                    self.open_stream = Some(StreamKind::AgentReasoning);
+                    self.agent_reasoning_streamed = true;
+                }
+                print!("{delta}");
+                let _ = std::io::stdout().flush();
             }
             EventMsg::SessionConfigured(session_configured_event) => {
                 let SessionConfiguredEvent {
@@ -381,12 +485,38 @@ impl EventProcessor {
                     model,
                     history_log_id: _,
                     history_entry_count: _,
+                    resumed_messages,
+                    mcp_prompts: _,
                 } = session_configured_event;
                 println!("session {session_id} with model {model}");
+
+                for message in resumed_messages.unwrap_or_default() {
+                    match message {
+                        ResumedMessage::User { text } => {
+                            let prefix = "User message (resumed):".style(self.bold);
+                            println!("{prefix} {text}");
+                        }
+                        ResumedMessage::Assistant { text } => {
+                            let prefix = "Agent message (resumed):".style(self.bold);
+                            println!("{prefix} {text}");
+                        }
+                    }
+                }
             }
-            EventMsg::GetHistoryEntryResponse(_) => {
+            EventMsg::GetHistoryEntryResponse(_) | EventMsg::GetMcpPromptResponse(_) => {
                 // Currently ignored in exec output.
             }
+            EventMsg::TokenCount(TokenCountEvent {
+                session_total,
+                estimated_cost_usd,
+                ..
+            }) => {
+                let mut msg = format!("tokens used: {}", session_total.total_tokens);
+                if let Some(cost) = estimated_cost_usd {
+                    msg.push_str(&format!(" (~${cost:.4})"));
+                }
+                ts_println!("{}", msg.style(self.dimmed));
+            }
         }
     }
 }
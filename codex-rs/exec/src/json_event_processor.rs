@@ -0,0 +1,16 @@
+use codex_core::protocol::Event;
+use tracing::error;
+
+/// Used instead of [`crate::event_processor::EventProcessor`] when `--json`
+/// is passed: every event is written to stdout as a single line of JSON, in
+/// the same shape as `codex proto`, so that scripts can consume it.
+pub(crate) struct JsonEventProcessor;
+
+impl JsonEventProcessor {
+    pub(crate) fn process_event(&mut self, event: &Event) {
+        match serde_json::to_string(event) {
+            Ok(line) => println!("{line}"),
+            Err(e) => error!("Failed to serialize event: {e}"),
+        }
+    }
+}
@@ -1,13 +1,16 @@
 mod cli;
 mod event_processor;
+mod json_event_processor;
 
 use std::io::IsTerminal;
+use std::io::Write;
 use std::sync::Arc;
 
 pub use cli::Cli;
 use codex_core::codex_wrapper;
 use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
+use codex_core::protocol::AgentMessageEvent;
 use codex_core::protocol::AskForApproval;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
@@ -16,6 +19,7 @@ use codex_core::protocol::Op;
 use codex_core::protocol::SandboxPolicy;
 use codex_core::util::is_inside_git_repo;
 use event_processor::EventProcessor;
+use json_event_processor::JsonEventProcessor;
 use tracing::debug;
 use tracing::error;
 use tracing::info;
@@ -31,6 +35,10 @@ pub async fn run_main(cli: Cli) -> anyhow::Result<()> {
         cwd,
         skip_git_repo_check,
         disable_response_storage,
+        resume,
+        stream_exec_output,
+        json,
+        last_message_file,
         color,
         prompt,
     } = cli;
@@ -65,6 +73,7 @@ pub async fn run_main(cli: Cli) -> anyhow::Result<()> {
         },
         cwd: cwd.map(|p| p.canonicalize().unwrap_or(p)),
         model_provider: None,
+        resume,
     };
     let config = Config::load_with_overrides(overrides)?;
 
@@ -90,6 +99,10 @@ pub async fn run_main(cli: Cli) -> anyhow::Result<()> {
     let (codex_wrapper, event, ctrl_c) = codex_wrapper::init_codex(config).await?;
     let codex = Arc::new(codex_wrapper);
     info!("Codex initialized with event: {event:?}");
+    let mut json_event_processor = json.then_some(JsonEventProcessor);
+    if let Some(json_event_processor) = &mut json_event_processor {
+        json_event_processor.process_event(&event);
+    }
 
     let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<Event>();
     {
@@ -148,16 +161,50 @@ pub async fn run_main(cli: Cli) -> anyhow::Result<()> {
     let initial_prompt_task_id = codex.submit(Op::UserInput { items }).await?;
     info!("Sent prompt with event ID: {initial_prompt_task_id}");
 
-    // Run the loop until the task is complete.
-    let mut event_processor = EventProcessor::create_with_ansi(stdout_with_ansi);
+    // Run the loop until the task is complete. A task that fails ends with an
+    // `Error` instead of `TaskComplete`; if the event stream closes first (for
+    // example, after Ctrl-C) the task did not complete either.
+    let mut event_processor =
+        EventProcessor::create_with_ansi(stdout_with_ansi, stream_exec_output);
+    let mut last_agent_message = None;
+    let mut task_completed = false;
     while let Some(event) = rx.recv().await {
-        let last_event =
-            event.id == initial_prompt_task_id && matches!(event.msg, EventMsg::TaskComplete);
-        event_processor.process_event(event);
+        let is_task_event = event.id == initial_prompt_task_id;
+        let last_event = match &event.msg {
+            EventMsg::TaskComplete => {
+                task_completed = is_task_event;
+                is_task_event
+            }
+            EventMsg::Error(_) => is_task_event,
+            EventMsg::AgentMessage(AgentMessageEvent { message }) => {
+                last_agent_message = Some(message.clone());
+                false
+            }
+            _ => false,
+        };
+        match &mut json_event_processor {
+            Some(json_event_processor) => json_event_processor.process_event(&event),
+            None => event_processor.process_event(event),
+        }
         if last_event {
             break;
         }
     }
 
+    if let Some(path) = last_message_file {
+        if last_agent_message.is_none() {
+            eprintln!("Warning: no last agent message; wrote empty content to {path:?}");
+        }
+        if let Err(e) = std::fs::write(&path, last_agent_message.unwrap_or_default()) {
+            eprintln!("Failed to write last message file {path:?}: {e}");
+        }
+    }
+
+    if !task_completed {
+        // `process::exit()` skips destructors, so flush what has been printed.
+        let _ = std::io::stdout().flush();
+        std::process::exit(1);
+    }
+
     Ok(())
 }
@@ -4,6 +4,7 @@ use codex_exec::run_main;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    codex_core::maybe_run_apply_patch_helper();
     let cli = Cli::parse();
     run_main(cli).await?;
 
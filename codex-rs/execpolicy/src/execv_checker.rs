@@ -97,22 +97,48 @@ impl ExecvChecker {
     }
 }
 
+/// Resolves `path` against `cwd` and canonicalizes it, so that a symlink in a
+/// folder cannot be used to reach a file outside of it. As a file that is about
+/// to be written may not exist yet, only the longest existing prefix of the
+/// path is canonicalized.
 fn ensure_absolute_path(path: &str, cwd: &Option<OsString>) -> Result<PathBuf> {
     let file = PathBuf::from(path);
-    let result = if file.is_relative() {
+    let file = if file.is_relative() {
         match cwd {
-            Some(cwd) => file.absolutize_from(cwd),
+            Some(cwd) => Path::new(cwd).join(file),
             None => return Err(CannotCheckRelativePath { file }),
         }
     } else {
-        file.absolutize()
+        file
     };
-    result
+    let cannot_canonicalize = |error: std::io::Error| CannotCanonicalizePath {
+        file: path.to_string(),
+        error: error.kind(),
+    };
+
+    for prefix in file.ancestors() {
+        match prefix.canonicalize() {
+            Ok(canonical_prefix) => {
+                let rest = file.strip_prefix(prefix).unwrap_or(Path::new(""));
+                // `rest` does not exist, so it contains no symlinks and its
+                // `..` components can be resolved lexically.
+                return canonical_prefix
+                    .join(rest)
+                    .absolutize()
+                    .map(|path| path.into_owned())
+                    .map_err(cannot_canonicalize);
+            }
+            // The prefix exists but cannot be resolved, e.g., because it is a
+            // dangling symlink, which could point anywhere.
+            Err(error) if prefix.symlink_metadata().is_ok() => {
+                return Err(cannot_canonicalize(error));
+            }
+            Err(_) => {}
+        }
+    }
+    file.absolutize()
         .map(|path| path.into_owned())
-        .map_err(|error| CannotCanonicalizePath {
-            file: path.to_string(),
-            error: error.kind(),
-        })
+        .map_err(cannot_canonicalize)
 }
 
 fn is_executable_file(path: &str) -> bool {
@@ -182,8 +208,9 @@ system_path=[{fake_cp:?}]
             std::fs::File::create(&fake_cp).unwrap();
         }
 
-        // Create root_path and reference to files under the root.
-        let root_path = temp_dir.path().to_path_buf();
+        // Create root_path and reference to files under the root. Paths are
+        // checked in canonical form.
+        let root_path = temp_dir.path().canonicalize().unwrap();
         let source_path = root_path.join("source");
         let dest_path = root_path.join("dest");
 
@@ -280,4 +307,55 @@ system_path=[{fake_cp:?}]
         );
         Ok(())
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_are_resolved() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let root_path = temp_dir.path().canonicalize().unwrap().join("root");
+        let outside_path = temp_dir.path().canonicalize().unwrap().join("outside");
+        std::fs::create_dir_all(&root_path).unwrap();
+        std::fs::create_dir_all(&outside_path).unwrap();
+        std::os::unix::fs::symlink(&outside_path, root_path.join("link")).unwrap();
+        std::os::unix::fs::symlink(temp_dir.path().join("missing"), root_path.join("dangling"))
+            .unwrap();
+
+        let checker = setup(&root_path.join("cp"));
+        let check = |dest: &str| {
+            let exec_call = ExecCall {
+                program: "cp".into(),
+                args: vec!["source".to_string(), dest.to_string()],
+            };
+            let MatchedExec::Match { exec } = checker.r#match(&exec_call).unwrap() else {
+                panic!("Expected a safe exec");
+            };
+            checker.check(
+                exec,
+                &Some(root_path.clone().into()),
+                &[root_path.clone()],
+                &[root_path.clone()],
+            )
+        };
+
+        assert!(check("new/../dest").is_ok());
+        assert_eq!(
+            check("link/dest"),
+            Err(WriteablePathNotInWriteableFolders {
+                file: outside_path.join("dest"),
+                folders: vec![root_path.clone()]
+            }),
+        );
+        assert_eq!(
+            check("link/../dest"),
+            Err(WriteablePathNotInWriteableFolders {
+                file: temp_dir.path().canonicalize().unwrap().join("dest"),
+                folders: vec![root_path.clone()]
+            }),
+        );
+        assert!(matches!(
+            check("dangling"),
+            Err(CannotCanonicalizePath { .. })
+        ));
+        Ok(())
+    }
 }
@@ -7,8 +7,10 @@
 //! ```
 //!
 //! Any additional arguments after the first one are forwarded to the spawned
-//! program. The utility connects, issues a `tools/list` request and prints the
-//! server's response as pretty JSON.
+//! program. If the first argument is an `http://` or `https://` URL, the
+//! utility connects to it using the streamable HTTP transport instead. The
+//! utility connects, issues a `tools/list` request and prints the server's
+//! response as pretty JSON.
 
 use std::time::Duration;
 
@@ -27,17 +29,24 @@ async fn main() -> Result<()> {
     let mut args: Vec<String> = std::env::args().skip(1).collect();
 
     if args.is_empty() || args[0] == "--help" || args[0] == "-h" {
-        eprintln!("Usage: mcp-client <program> [args..]\n\nExample: mcp-client codex-mcp-server");
+        eprintln!(
+            "Usage: mcp-client <program> [args..]\n       mcp-client <url>\n\nExample: mcp-client codex-mcp-server"
+        );
         std::process::exit(1);
     }
     let original_args = args.clone();
 
-    // Spawn the subprocess and connect the client.
+    // Spawn the subprocess (or connect to the URL) and connect the client.
     let program = args.remove(0);
-    let env = None;
-    let client = McpClient::new_stdio_client(program, args, env)
-        .await
-        .with_context(|| format!("failed to spawn subprocess: {original_args:?}"))?;
+    let client = if program.starts_with("http://") || program.starts_with("https://") {
+        let bearer_token = None;
+        McpClient::new_streamable_http_client(program, bearer_token)?
+    } else {
+        let env = None;
+        McpClient::new_stdio_client(program, args, env)
+            .await
+            .with_context(|| format!("failed to spawn subprocess: {original_args:?}"))?
+    };
 
     let params = InitializeRequestParams {
         capabilities: ClientCapabilities {
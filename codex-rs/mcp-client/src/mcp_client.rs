@@ -2,10 +2,13 @@
 //!
 //! The client is intentionally lightweight – it is only capable of:
 //!   1. Spawning a subprocess that launches a conforming MCP server that
-//!      communicates over stdio.
+//!      communicates over stdio, or connecting to a server that uses the
+//!      streamable HTTP transport.
 //!   2. Sending MCP requests and pairing them with their corresponding
 //!      responses.
-//!   3. Offering a convenience helper for the common `tools/list` request.
+//!   3. Offering convenience helpers for common requests such as
+//!      `tools/list`, `resources/read`, and `prompts/get`.
+//!   4. Forwarding notifications sent by the server to subscribers.
 //!
 //! The crate hides all JSON‐RPC framing details behind a typed API. Users
 //! interact with the [`ModelContextProtocolRequest`] trait from `mcp-types` to
@@ -20,8 +23,13 @@ use std::time::Duration;
 use anyhow::Context;
 use anyhow::Result;
 use anyhow::anyhow;
+use eventsource_stream::Eventsource;
+use futures::StreamExt;
 use mcp_types::CallToolRequest;
 use mcp_types::CallToolRequestParams;
+use mcp_types::GetPromptRequest;
+use mcp_types::GetPromptRequestParams;
+use mcp_types::GetPromptResult;
 use mcp_types::InitializeRequest;
 use mcp_types::InitializeRequestParams;
 use mcp_types::InitializedNotification;
@@ -30,12 +38,23 @@ use mcp_types::JSONRPCMessage;
 use mcp_types::JSONRPCNotification;
 use mcp_types::JSONRPCRequest;
 use mcp_types::JSONRPCResponse;
+use mcp_types::ListPromptsRequest;
+use mcp_types::ListPromptsRequestParams;
+use mcp_types::ListPromptsResult;
+use mcp_types::ListResourcesRequest;
+use mcp_types::ListResourcesRequestParams;
+use mcp_types::ListResourcesResult;
 use mcp_types::ListToolsRequest;
 use mcp_types::ListToolsRequestParams;
 use mcp_types::ListToolsResult;
 use mcp_types::ModelContextProtocolNotification;
 use mcp_types::ModelContextProtocolRequest;
+use mcp_types::ReadResourceRequest;
+use mcp_types::ReadResourceRequestParams;
+use mcp_types::ReadResourceResult;
 use mcp_types::RequestId;
+use reqwest::header::ACCEPT;
+use reqwest::header::CONTENT_TYPE;
 use serde::Serialize;
 use serde::de::DeserializeOwned;
 use tokio::io::AsyncBufReadExt;
@@ -43,8 +62,10 @@ use tokio::io::AsyncWriteExt;
 use tokio::io::BufReader;
 use tokio::process::Command;
 use tokio::sync::Mutex;
+use tokio::sync::broadcast;
 use tokio::sync::mpsc;
 use tokio::sync::oneshot;
+use tokio::sync::watch;
 use tokio::time;
 use tracing::debug;
 use tracing::error;
@@ -55,6 +76,10 @@ use tracing::warn;
 /// client API and the IO tasks.
 const CHANNEL_CAPACITY: usize = 128;
 
+/// Header used by the streamable HTTP transport to identify the session
+/// assigned by the server in its response to `initialize`.
+const MCP_SESSION_ID_HEADER: &str = "Mcp-Session-Id";
+
 /// Internal representation of a pending request sender.
 type PendingSender = oneshot::Sender<JSONRPCMessage>;
 
@@ -63,8 +88,8 @@ pub struct McpClient {
     /// Retain this child process until the client is dropped. The Tokio runtime
     /// will make a "best effort" to reap the process after it exits, but it is
     /// not a guarantee. See the `kill_on_drop` documentation for details.
-    #[allow(dead_code)]
-    child: tokio::process::Child,
+    /// `None` for the streamable HTTP transport.
+    child: Option<tokio::process::Child>,
 
     /// Channel for sending JSON-RPC messages *to* the background writer task.
     outgoing_tx: mpsc::Sender<JSONRPCMessage>,
@@ -75,6 +100,13 @@ pub struct McpClient {
 
     /// Monotonically increasing counter used to generate request IDs.
     id_counter: AtomicI64,
+
+    /// Becomes `true` once the connection to the server is lost, e.g.,
+    /// because the server process exited.
+    closed_rx: watch::Receiver<bool>,
+
+    /// Notifications received from the server are sent to every subscriber.
+    notifications_tx: broadcast::Sender<JSONRPCNotification>,
 }
 
 impl McpClient {
@@ -110,6 +142,8 @@ impl McpClient {
 
         let (outgoing_tx, mut outgoing_rx) = mpsc::channel::<JSONRPCMessage>(CHANNEL_CAPACITY);
         let pending: Arc<Mutex<HashMap<i64, PendingSender>>> = Arc::new(Mutex::new(HashMap::new()));
+        let (closed_tx, closed_rx) = watch::channel(false);
+        let (notifications_tx, _) = broadcast::channel(CHANNEL_CAPACITY);
 
         // Spawn writer task. It listens on the `outgoing_rx` channel and
         // writes messages to the child's STDIN.
@@ -140,35 +174,24 @@ impl McpClient {
         };
 
         // Spawn reader task. It reads line-delimited JSON from the child's
-        // STDOUT and dispatches responses to the pending map.
+        // STDOUT and dispatches responses to the pending map. Once STDOUT is
+        // closed (typically because the child exited), no more responses can
+        // arrive, so the connection is marked as closed and every pending
+        // request fails instead of waiting forever.
         let reader_handle = {
             let pending = pending.clone();
+            let notifications_tx = notifications_tx.clone();
             let mut lines = BufReader::new(stdout).lines();
 
             tokio::spawn(async move {
                 while let Ok(Some(line)) = lines.next_line().await {
-                    debug!("MCP message from server: {line}");
-                    match serde_json::from_str::<JSONRPCMessage>(&line) {
-                        Ok(JSONRPCMessage::Response(resp)) => {
-                            Self::dispatch_response(resp, &pending).await;
-                        }
-                        Ok(JSONRPCMessage::Error(err)) => {
-                            Self::dispatch_error(err, &pending).await;
-                        }
-                        Ok(JSONRPCMessage::Notification(JSONRPCNotification { .. })) => {
-                            // For now we only log server-initiated notifications.
-                            info!("<- notification: {}", line);
-                        }
-                        Ok(other) => {
-                            // Batch responses and requests are currently not
-                            // expected from the server – log and ignore.
-                            info!("<- unhandled message: {:?}", other);
-                        }
-                        Err(e) => {
-                            error!("failed to deserialize JSONRPCMessage: {e}; line = {}", line)
-                        }
-                    }
+                    Self::dispatch_incoming(&line, &pending, &notifications_tx).await;
                 }
+                info!("MCP server closed its stdout");
+                // Mark the connection closed *before* clearing the pending map
+                // so that `send_request()` observes one or the other.
+                let _ = closed_tx.send(true);
+                pending.lock().await.clear();
             })
         };
 
@@ -179,13 +202,106 @@ impl McpClient {
         let _ = (writer_handle, reader_handle);
 
         Ok(Self {
-            child,
+            child: Some(child),
+            outgoing_tx,
+            pending,
+            id_counter: AtomicI64::new(1),
+            closed_rx,
+            notifications_tx,
+        })
+    }
+
+    /// Connect to an MCP server that uses the streamable HTTP transport at
+    /// `url`. Every message is POSTed to `url`; the server replies with either
+    /// a single JSON message or an SSE stream of messages. If `bearer_token`
+    /// is set, it is sent in the `Authorization` header of every request.
+    /// Caller is responsible for sending the `initialize` request. See
+    /// [`initialize`](Self::initialize) for details.
+    pub fn new_streamable_http_client(url: String, bearer_token: Option<String>) -> Result<Self> {
+        let http_client = reqwest::Client::builder()
+            .build()
+            .context("failed to create HTTP client")?;
+        let (outgoing_tx, mut outgoing_rx) = mpsc::channel::<JSONRPCMessage>(CHANNEL_CAPACITY);
+        let pending: Arc<Mutex<HashMap<i64, PendingSender>>> = Arc::new(Mutex::new(HashMap::new()));
+        // There is no persistent connection to lose: every message is sent
+        // with its own HTTP request, so the connection is never closed.
+        let (closed_tx, closed_rx) = watch::channel(false);
+        let (notifications_tx, _) = broadcast::channel(CHANNEL_CAPACITY);
+        let transport = Arc::new(HttpTransport {
+            http_client,
+            url,
+            bearer_token,
+            session_id: Mutex::new(None),
+            notifications_tx: notifications_tx.clone(),
+        });
+
+        // Spawn writer task. Requests are POSTed concurrently so that a slow
+        // tool call does not hold up other requests, while notifications are
+        // sent in order (the server acknowledges them immediately). As in
+        // `new_stdio_client()`, the task is detached and stops once
+        // `outgoing_tx` is dropped.
+        {
+            let pending = pending.clone();
+            tokio::spawn(async move {
+                let _closed_tx = closed_tx;
+                while let Some(msg) = outgoing_rx.recv().await {
+                    let request_id = match &msg {
+                        JSONRPCMessage::Request(JSONRPCRequest {
+                            id: RequestId::Integer(id),
+                            ..
+                        }) => Some(*id),
+                        _ => None,
+                    };
+                    let transport = transport.clone();
+                    let pending = pending.clone();
+                    let post = async move {
+                        if let Err(e) = transport.post(&msg, &pending).await {
+                            error!("failed to POST message to MCP server: {e:#}");
+                            // Fail the request rather than leaving the caller
+                            // waiting for a response that will never arrive.
+                            if let Some(id) = request_id {
+                                pending.lock().await.remove(&id);
+                            }
+                        }
+                    };
+                    if request_id.is_some() {
+                        tokio::spawn(post);
+                    } else {
+                        post.await;
+                    }
+                }
+            });
+        }
+
+        Ok(Self {
+            child: None,
             outgoing_tx,
             pending,
             id_counter: AtomicI64::new(1),
+            closed_rx,
+            notifications_tx,
         })
     }
 
+    /// Returns a future that resolves once the connection to the server is
+    /// lost, e.g., because the server process exited. The future does not
+    /// keep the client alive, so it also resolves once the client is dropped.
+    pub fn closed(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut closed_rx = self.closed_rx.clone();
+        async move {
+            // An error means the sender is gone, which implies the
+            // connection is gone, too.
+            let _ = closed_rx.wait_for(|closed| *closed).await;
+        }
+    }
+
+    /// Subscribe to the notifications sent by the server, e.g.,
+    /// `notifications/tools/list_changed`. Only notifications received after
+    /// subscribing are delivered.
+    pub fn subscribe_notifications(&self) -> broadcast::Receiver<JSONRPCNotification> {
+        self.notifications_tx.subscribe()
+    }
+
     /// Send an arbitrary MCP request and await the typed result.
     ///
     /// If `timeout` is `None` the call waits indefinitely. If `Some(duration)`
@@ -233,6 +349,12 @@ impl McpClient {
             guard.insert(id, tx);
         }
 
+        // If the connection was lost, nobody will ever reply.
+        if *self.closed_rx.borrow() {
+            self.pending.lock().await.remove(&id);
+            return Err(anyhow!("connection to MCP server is closed"));
+        }
+
         // Send to writer task.
         if self.outgoing_tx.send(message).await.is_err() {
             return Err(anyhow!(
@@ -337,6 +459,48 @@ impl McpClient {
         self.send_request::<ListToolsRequest>(params, timeout).await
     }
 
+    /// Convenience wrapper around `resources/list`.
+    pub async fn list_resources(
+        &self,
+        params: Option<ListResourcesRequestParams>,
+        timeout: Option<Duration>,
+    ) -> Result<ListResourcesResult> {
+        self.send_request::<ListResourcesRequest>(params, timeout)
+            .await
+    }
+
+    /// Convenience wrapper around `resources/read`.
+    pub async fn read_resource(
+        &self,
+        uri: String,
+        timeout: Option<Duration>,
+    ) -> Result<ReadResourceResult> {
+        let params = ReadResourceRequestParams { uri };
+        self.send_request::<ReadResourceRequest>(params, timeout)
+            .await
+    }
+
+    /// Convenience wrapper around `prompts/list`.
+    pub async fn list_prompts(
+        &self,
+        params: Option<ListPromptsRequestParams>,
+        timeout: Option<Duration>,
+    ) -> Result<ListPromptsResult> {
+        self.send_request::<ListPromptsRequest>(params, timeout)
+            .await
+    }
+
+    /// Convenience wrapper around `prompts/get`.
+    pub async fn get_prompt(
+        &self,
+        name: String,
+        arguments: Option<serde_json::Value>,
+        timeout: Option<Duration>,
+    ) -> Result<GetPromptResult> {
+        let params = GetPromptRequestParams { arguments, name };
+        self.send_request::<GetPromptRequest>(params, timeout).await
+    }
+
     /// Convenience wrapper around `tools/call`.
     pub async fn call_tool(
         &self,
@@ -349,6 +513,37 @@ impl McpClient {
         self.send_request::<CallToolRequest>(params, timeout).await
     }
 
+    /// Internal helper: parse a message received from the server and route it
+    /// to the pending map.
+    async fn dispatch_incoming(
+        line: &str,
+        pending: &Arc<Mutex<HashMap<i64, PendingSender>>>,
+        notifications_tx: &broadcast::Sender<JSONRPCNotification>,
+    ) {
+        debug!("MCP message from server: {line}");
+        match serde_json::from_str::<JSONRPCMessage>(line) {
+            Ok(JSONRPCMessage::Response(resp)) => {
+                Self::dispatch_response(resp, pending).await;
+            }
+            Ok(JSONRPCMessage::Error(err)) => {
+                Self::dispatch_error(err, pending).await;
+            }
+            Ok(JSONRPCMessage::Notification(notification)) => {
+                info!("<- notification: {}", line);
+                // Sending fails if nobody is subscribed, which is fine.
+                let _ = notifications_tx.send(notification);
+            }
+            Ok(other) => {
+                // Batch responses and requests are currently not
+                // expected from the server – log and ignore.
+                info!("<- unhandled message: {:?}", other);
+            }
+            Err(e) => {
+                error!("failed to deserialize JSONRPCMessage: {e}; line = {}", line)
+            }
+        }
+    }
+
     /// Internal helper: route a JSON-RPC *response* object to the pending map.
     async fn dispatch_response(
         resp: JSONRPCResponse,
@@ -394,7 +589,78 @@ impl Drop for McpClient {
         // `kill_on_drop(true)` above, this extra check has the benefit of
         // forcing the process to be reaped immediately if it has already exited
         // instead of waiting for the Tokio runtime to reap it later.
-        let _ = self.child.try_wait();
+        if let Some(child) = &mut self.child {
+            let _ = child.try_wait();
+        }
+    }
+}
+
+/// Connection state of the streamable HTTP transport.
+struct HttpTransport {
+    http_client: reqwest::Client,
+    url: String,
+    bearer_token: Option<String>,
+
+    /// Session id assigned by the server, echoed back on every request.
+    session_id: Mutex<Option<String>>,
+
+    /// See [`McpClient::subscribe_notifications`].
+    notifications_tx: broadcast::Sender<JSONRPCNotification>,
+}
+
+impl HttpTransport {
+    /// POSTs `msg` to the server and dispatches the messages it replies with.
+    async fn post(
+        &self,
+        msg: &JSONRPCMessage,
+        pending: &Arc<Mutex<HashMap<i64, PendingSender>>>,
+    ) -> Result<()> {
+        debug!("MCP message to server: {}", serde_json::to_string(msg)?);
+        let mut request = self
+            .http_client
+            .post(&self.url)
+            .header(ACCEPT, "application/json, text/event-stream")
+            .json(msg);
+        if let Some(token) = &self.bearer_token {
+            request = request.bearer_auth(token);
+        }
+        if let Some(session_id) = self.session_id.lock().await.as_ref() {
+            request = request.header(MCP_SESSION_ID_HEADER, session_id);
+        }
+
+        let response = request.send().await?.error_for_status()?;
+
+        if let Some(session_id) = response
+            .headers()
+            .get(MCP_SESSION_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+        {
+            *self.session_id.lock().await = Some(session_id.to_string());
+        }
+
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        if content_type.starts_with("text/event-stream") {
+            let mut events = response.bytes_stream().eventsource();
+            while let Some(event) = events.next().await {
+                let event = event?;
+                if !event.data.is_empty() {
+                    McpClient::dispatch_incoming(&event.data, pending, &self.notifications_tx)
+                        .await;
+                }
+            }
+        } else if content_type.starts_with("application/json") {
+            let body = response.text().await?;
+            McpClient::dispatch_incoming(&body, pending, &self.notifications_tx).await;
+        }
+        // Otherwise, e.g. `202 Accepted` for a notification, there is no
+        // message to dispatch.
+
+        Ok(())
     }
 }
 
@@ -456,7 +722,19 @@ fn create_env_for_mcp_server(
 
 #[cfg(test)]
 mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+
     use super::*;
+    use mcp_types::ClientCapabilities;
+    use mcp_types::Implementation;
+    use mcp_types::MCP_SCHEMA_VERSION;
+    use serde_json::json;
+    use wiremock::Mock;
+    use wiremock::MockServer;
+    use wiremock::ResponseTemplate;
+    use wiremock::matchers::body_partial_json;
+    use wiremock::matchers::header;
+    use wiremock::matchers::method;
 
     #[test]
     fn test_create_env_for_mcp_server() {
@@ -468,4 +746,130 @@ mod tests {
         assert!(mcp_server_env.contains_key("PATH"));
         assert_eq!(Some(&env_var_new_value), mcp_server_env.get(env_var));
     }
+
+    #[tokio::test]
+    async fn test_stdio_client_detects_exited_server() {
+        // A "server" that exits immediately without replying to anything.
+        let client = McpClient::new_stdio_client("true".to_string(), Vec::new(), None)
+            .await
+            .unwrap();
+
+        time::timeout(Duration::from_secs(5), client.closed())
+            .await
+            .expect("client should notice that the server exited");
+        let err = client
+            .list_tools(None, None)
+            .await
+            .expect_err("request to an exited server should fail");
+        assert_eq!(err.to_string(), "connection to MCP server is closed");
+    }
+
+    #[tokio::test]
+    async fn test_stdio_client_forwards_notifications() {
+        // A "server" that sends a notification once it hears from the client.
+        let script = r#"read -r _; echo '{"jsonrpc":"2.0","method":"notifications/tools/list_changed"}'; read -r _"#;
+        let client = McpClient::new_stdio_client(
+            "sh".to_string(),
+            vec!["-c".to_string(), script.to_string()],
+            None,
+        )
+        .await
+        .unwrap();
+
+        let mut notifications = client.subscribe_notifications();
+        client
+            .send_notification::<InitializedNotification>(None)
+            .await
+            .unwrap();
+        let notification = time::timeout(Duration::from_secs(5), notifications.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            mcp_types::ToolListChangedNotification::METHOD,
+            notification.method
+        );
+    }
+
+    #[tokio::test]
+    async fn test_streamable_http_client() {
+        let server = MockServer::start().await;
+
+        // `initialize` is answered with a plain JSON body and assigns the
+        // session id.
+        Mock::given(method("POST"))
+            .and(header("authorization", "Bearer secret"))
+            .and(body_partial_json(json!({ "method": "initialize" })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header(MCP_SESSION_ID_HEADER, "session-1")
+                    .set_body_raw(
+                        json!({
+                            "jsonrpc": "2.0",
+                            "id": 1,
+                            "result": {
+                                "capabilities": {},
+                                "protocolVersion": MCP_SCHEMA_VERSION,
+                                "serverInfo": { "name": "test", "version": "0.0.0" },
+                            },
+                        })
+                        .to_string(),
+                        "application/json",
+                    ),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(header(MCP_SESSION_ID_HEADER, "session-1"))
+            .and(body_partial_json(
+                json!({ "method": "notifications/initialized" }),
+            ))
+            .respond_with(ResponseTemplate::new(202))
+            .expect(1)
+            .mount(&server)
+            .await;
+        // `tools/list` is answered with an SSE stream.
+        let tools_list_event = json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "result": {
+                "tools": [{ "name": "echo", "inputSchema": { "type": "object" } }],
+            },
+        });
+        Mock::given(method("POST"))
+            .and(header(MCP_SESSION_ID_HEADER, "session-1"))
+            .and(body_partial_json(json!({ "method": "tools/list" })))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                format!("event: message\ndata: {tools_list_event}\n\n"),
+                "text/event-stream",
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = McpClient::new_streamable_http_client(
+            format!("{}/mcp", server.uri()),
+            Some("secret".to_string()),
+        )
+        .unwrap();
+        let params = InitializeRequestParams {
+            capabilities: ClientCapabilities {
+                experimental: None,
+                roots: None,
+                sampling: None,
+            },
+            client_info: Implementation {
+                name: "test".to_owned(),
+                version: "0.0.0".to_owned(),
+            },
+            protocol_version: MCP_SCHEMA_VERSION.to_owned(),
+        };
+        let timeout = Some(Duration::from_secs(5));
+        client.initialize(params, None, timeout).await.unwrap();
+        let tools = client.list_tools(None, timeout).await.unwrap();
+
+        assert_eq!(1, tools.tools.len());
+        assert_eq!("echo", tools.tools[0].name);
+    }
 }
@@ -0,0 +1,131 @@
+//! Canned prompts published via `prompts/list` and `prompts/get`, so that
+//! other agents can hand common tasks to Codex with a well-tested wording.
+
+use mcp_types::GetPromptResult;
+use mcp_types::ListPromptsResult;
+use mcp_types::Prompt;
+use mcp_types::PromptArgument;
+use mcp_types::PromptMessage;
+use mcp_types::PromptMessageContent;
+use mcp_types::Role;
+use mcp_types::TextContent;
+
+struct CannedPrompt {
+    name: &'static str,
+    description: &'static str,
+    /// Name and description of the single, required argument.
+    argument: (&'static str, &'static str),
+    /// Text of the prompt; `{argument}` is replaced with the argument value.
+    template: &'static str,
+}
+
+const PROMPTS: &[CannedPrompt] = &[
+    CannedPrompt {
+        name: "review-diff",
+        description: "Review a diff for bugs, risky changes, and missing tests.",
+        argument: ("diff", "The diff to review, in unified diff format."),
+        template: "Review the following diff. Point out bugs, risky changes, and missing tests, \
+                   citing the relevant lines, and suggest concrete fixes.\n\n```diff\n{argument}\n```",
+    },
+    CannedPrompt {
+        name: "explain-file",
+        description: "Explain what a file does and how it fits into the project.",
+        argument: ("path", "Path of the file to explain."),
+        template: "Read `{argument}` and explain what it does: its purpose, its main types and \
+                   functions, and how it fits into the rest of the project.",
+    },
+    CannedPrompt {
+        name: "write-tests",
+        description: "Write tests for a file, following the project's conventions.",
+        argument: ("path", "Path of the file to write tests for."),
+        template: "Write tests for `{argument}` that follow the conventions of the existing tests \
+                   in this project, then run them and fix any failures.",
+    },
+];
+
+pub(crate) fn list_prompts() -> ListPromptsResult {
+    ListPromptsResult {
+        next_cursor: None,
+        prompts: PROMPTS
+            .iter()
+            .map(|prompt| {
+                let (name, description) = prompt.argument;
+                Prompt {
+                    arguments: Some(vec![PromptArgument {
+                        description: Some(description.to_string()),
+                        name: name.to_string(),
+                        required: Some(true),
+                    }]),
+                    description: Some(prompt.description.to_string()),
+                    name: prompt.name.to_string(),
+                }
+            })
+            .collect(),
+    }
+}
+
+/// Expand the prompt `name` with the given `arguments`, which must be an
+/// object with a string value for the prompt's argument. On failure, returns
+/// a message suitable for an "invalid params" error.
+pub(crate) fn get_prompt(
+    name: &str,
+    arguments: Option<&serde_json::Value>,
+) -> Result<GetPromptResult, String> {
+    let prompt = PROMPTS
+        .iter()
+        .find(|prompt| prompt.name == name)
+        .ok_or_else(|| format!("Unknown prompt '{name}'"))?;
+    let (argument, _) = prompt.argument;
+    let value = arguments
+        .and_then(|arguments| arguments.get(argument))
+        .and_then(serde_json::Value::as_str)
+        .ok_or_else(|| format!("Prompt '{name}' requires the string argument '{argument}'"))?;
+
+    Ok(GetPromptResult {
+        description: Some(prompt.description.to_string()),
+        messages: vec![PromptMessage {
+            content: PromptMessageContent::TextContent(TextContent {
+                r#type: "text".to_string(),
+                text: prompt.template.replace("{argument}", value),
+                annotations: None,
+            }),
+            role: Role::User,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn expands_prompt_arguments() {
+        let result = get_prompt("explain-file", Some(&json!({ "path": "src/lib.rs" }))).unwrap();
+        let [
+            PromptMessage {
+                content: PromptMessageContent::TextContent(content),
+                role: Role::User,
+            },
+        ] = result.messages.as_slice()
+        else {
+            panic!("expected a single user message");
+        };
+        assert!(content.text.starts_with("Read `src/lib.rs` and explain"));
+
+        assert_eq!(
+            get_prompt("explain-file", None).unwrap_err(),
+            "Prompt 'explain-file' requires the string argument 'path'"
+        );
+        assert!(get_prompt("no-such-prompt", None).is_err());
+    }
+
+    #[test]
+    fn every_prompt_is_listed() {
+        let names: Vec<String> = list_prompts().prompts.into_iter().map(|p| p.name).collect();
+        assert_eq!(names, vec!["review-diff", "explain-file", "write-tests"]);
+    }
+}
@@ -0,0 +1,214 @@
+//! Publishes Codex's persisted state as MCP resources: the rollout of every
+//! past session in `~/.codex/sessions` and the cross-session message history
+//! in `~/.codex/history.jsonl`.
+
+use std::path::Path;
+
+use codex_core::find_session_rollout;
+use codex_core::history_path;
+use codex_core::list_session_rollouts;
+use mcp_types::ListResourceTemplatesResult;
+use mcp_types::ListResourcesResult;
+use mcp_types::ReadResourceResult;
+use mcp_types::ReadResourceResultContents;
+use mcp_types::Resource;
+use mcp_types::ResourceTemplate;
+use mcp_types::TextResourceContents;
+
+const HISTORY_URI: &str = "codex://history";
+
+const SESSION_URI_PREFIX: &str = "codex://sessions/";
+
+/// Both kinds of resources are files with one JSON object per line.
+const JSONL_MIME_TYPE: &str = "application/jsonl";
+
+/// Maximum number of resources returned per `resources/list` page.
+const RESOURCES_PAGE_SIZE: usize = 100;
+
+/// Error returned when a resource cannot be listed or read.
+#[derive(Debug, PartialEq)]
+pub(crate) enum ResourceError {
+    NotFound(String),
+    InvalidCursor(String),
+    Io(String),
+}
+
+/// List one page of resources. `cursor` is the `nextCursor` of the previous
+/// page, i.e., the offset of the first resource on this page.
+pub(crate) fn list_resources(
+    codex_home: &Path,
+    cursor: Option<&str>,
+) -> Result<ListResourcesResult, ResourceError> {
+    let offset = match cursor {
+        Some(cursor) => cursor
+            .parse::<usize>()
+            .map_err(|_| ResourceError::InvalidCursor(cursor.to_string()))?,
+        None => 0,
+    };
+
+    let mut resources = Vec::new();
+    let history = history_path(codex_home);
+    if let Ok(metadata) = std::fs::metadata(&history) {
+        resources.push(Resource {
+            annotations: None,
+            description: Some(
+                "Messages sent to Codex across all sessions, one JSON object per line.".to_string(),
+            ),
+            mime_type: Some(JSONL_MIME_TYPE.to_string()),
+            name: "history.jsonl".to_string(),
+            size: i64::try_from(metadata.len()).ok(),
+            uri: HISTORY_URI.to_string(),
+        });
+    }
+    resources.extend(list_session_rollouts(codex_home).into_iter().map(|rollout| {
+        let size = std::fs::metadata(&rollout.path)
+            .ok()
+            .and_then(|metadata| i64::try_from(metadata.len()).ok());
+        let name = rollout.path.file_name().map_or_else(
+            || rollout.session_id.clone(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+        Resource {
+            annotations: None,
+            description: Some(format!(
+                "Rollout of Codex session {}: every item exchanged with the model, one JSON object per line.",
+                rollout.session_id
+            )),
+            mime_type: Some(JSONL_MIME_TYPE.to_string()),
+            name,
+            size,
+            uri: format!("{SESSION_URI_PREFIX}{}", rollout.session_id),
+        }
+    }));
+
+    let next_offset = offset.saturating_add(RESOURCES_PAGE_SIZE);
+    let next_cursor = (next_offset < resources.len()).then(|| next_offset.to_string());
+    let resources = resources
+        .into_iter()
+        .skip(offset)
+        .take(RESOURCES_PAGE_SIZE)
+        .collect();
+    Ok(ListResourcesResult {
+        next_cursor,
+        resources,
+    })
+}
+
+pub(crate) fn list_resource_templates() -> ListResourceTemplatesResult {
+    ListResourceTemplatesResult {
+        next_cursor: None,
+        resource_templates: vec![ResourceTemplate {
+            annotations: None,
+            description: Some("Rollout of the Codex session with the given id.".to_string()),
+            mime_type: Some(JSONL_MIME_TYPE.to_string()),
+            name: "Codex session rollout".to_string(),
+            uri_template: format!("{SESSION_URI_PREFIX}{{session_id}}"),
+        }],
+    }
+}
+
+pub(crate) async fn read_resource(
+    codex_home: &Path,
+    uri: &str,
+) -> Result<ReadResourceResult, ResourceError> {
+    let path = if uri == HISTORY_URI {
+        history_path(codex_home)
+    } else if let Some(session_id) = uri.strip_prefix(SESSION_URI_PREFIX) {
+        // Only sessions in `~/.codex/sessions` are found, so the id cannot be
+        // used to read files elsewhere.
+        find_session_rollout(codex_home, session_id)
+            .map_err(|_| ResourceError::NotFound(uri.to_string()))?
+    } else {
+        return Err(ResourceError::NotFound(uri.to_string()));
+    };
+
+    let text = match tokio::fs::read_to_string(&path).await {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(ResourceError::NotFound(uri.to_string()));
+        }
+        Err(e) => return Err(ResourceError::Io(format!("failed to read {uri}: {e}"))),
+    };
+    Ok(ReadResourceResult {
+        contents: vec![ReadResourceResultContents::TextResourceContents(
+            TextResourceContents {
+                mime_type: Some(JSONL_MIME_TYPE.to_string()),
+                text,
+                uri: uri.to_string(),
+            },
+        )],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    const SESSION_ID: &str = "5973b6c0-94b8-487b-a530-2aeb6098ae0e";
+
+    fn codex_home_with_state() -> TempDir {
+        let codex_home = TempDir::new().unwrap();
+        let sessions = codex_home.path().join("sessions");
+        std::fs::create_dir_all(&sessions).unwrap();
+        std::fs::write(
+            sessions.join(format!("rollout-2025-05-07T17-24-21-{SESSION_ID}.jsonl")),
+            "{\"id\":\"session\"}\n",
+        )
+        .unwrap();
+        std::fs::write(history_path(codex_home.path()), "{\"text\":\"hi\"}\n").unwrap();
+        codex_home
+    }
+
+    #[test]
+    fn lists_history_and_sessions() {
+        let codex_home = codex_home_with_state();
+
+        let result = list_resources(codex_home.path(), None).unwrap();
+        let uris: Vec<String> = result.resources.iter().map(|r| r.uri.clone()).collect();
+        assert_eq!(
+            uris,
+            vec![
+                HISTORY_URI.to_string(),
+                format!("{SESSION_URI_PREFIX}{SESSION_ID}")
+            ]
+        );
+        assert_eq!(result.next_cursor, None);
+
+        let result = list_resources(codex_home.path(), Some("1")).unwrap();
+        assert_eq!(result.resources.len(), 1);
+        assert_eq!(
+            list_resources(codex_home.path(), Some("x")).unwrap_err(),
+            ResourceError::InvalidCursor("x".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn reads_only_known_resources() {
+        let codex_home = codex_home_with_state();
+
+        let result = read_resource(codex_home.path(), HISTORY_URI).await.unwrap();
+        let [ReadResourceResultContents::TextResourceContents(contents)] =
+            result.contents.as_slice()
+        else {
+            panic!("expected a single text resource");
+        };
+        assert_eq!(contents.text, "{\"text\":\"hi\"}\n");
+
+        let uri = format!("{SESSION_URI_PREFIX}{SESSION_ID}");
+        assert!(read_resource(codex_home.path(), &uri).await.is_ok());
+
+        for uri in [
+            format!("{SESSION_URI_PREFIX}../history.jsonl"),
+            "file:///etc/passwd".to_string(),
+        ] {
+            assert_eq!(
+                read_resource(codex_home.path(), &uri).await.unwrap_err(),
+                ResourceError::NotFound(uri)
+            );
+        }
+    }
+}
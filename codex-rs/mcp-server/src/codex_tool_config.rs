@@ -32,7 +32,9 @@ pub(crate) struct CodexToolCallParam {
     pub cwd: Option<String>,
 
     /// Execution approval policy expressed as the kebab-case variant name
-    /// (`unless-allow-listed`, `auto-edit`, `on-failure`, `never`).
+    /// (`unless-allow-listed`, `auto-edit`, `on-failure`, `never`). Defaults
+    /// to `never` if the client does not support elicitation, which any other
+    /// value requires.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub approval_policy: Option<CodexToolCallApprovalPolicy>,
 
@@ -114,34 +116,66 @@ impl From<CodexToolCallSandboxPermission> for codex_core::protocol::SandboxPermi
 }
 
 pub(crate) fn create_tool_for_codex_tool_call_param() -> Tool {
+    Tool {
+        name: "codex".to_string(),
+        input_schema: create_tool_input_schema::<CodexToolCallParam>(),
+        description: Some(
+            "Run a Codex session. Accepts configuration parameters matching the Codex Config struct."
+                .to_string(),
+        ),
+        annotations: None,
+    }
+}
+
+/// Client-supplied parameters for a `codex-reply` tool-call.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct CodexToolCallReplyParam {
+    /// The id of the Codex session to continue, as returned by the `codex`
+    /// tool.
+    pub session_id: String,
+
+    /// The next user prompt in the Codex conversation.
+    pub prompt: String,
+}
+
+pub(crate) fn create_tool_for_codex_tool_call_reply_param() -> Tool {
+    Tool {
+        name: "codex-reply".to_string(),
+        input_schema: create_tool_input_schema::<CodexToolCallReplyParam>(),
+        description: Some("Continue a Codex session by sending it another prompt.".to_string()),
+        annotations: None,
+    }
+}
+
+fn create_tool_input_schema<T: JsonSchema>() -> ToolInputSchema {
     let schema = SchemaSettings::draft2019_09()
         .with(|s| {
             s.inline_subschemas = true;
             s.option_add_null_type = false
         })
         .into_generator()
-        .into_root_schema_for::<CodexToolCallParam>();
+        .into_root_schema_for::<T>();
 
     #[expect(clippy::expect_used)]
     let schema_value =
         serde_json::to_value(&schema).expect("Codex tool schema should serialise to JSON");
 
-    let tool_input_schema =
-        serde_json::from_value::<ToolInputSchema>(schema_value).unwrap_or_else(|e| {
-            panic!("failed to create Tool from schema: {e}");
-        });
-    Tool {
-        name: "codex".to_string(),
-        input_schema: tool_input_schema,
-        description: Some(
-            "Run a Codex session. Accepts configuration parameters matching the Codex Config struct."
-                .to_string(),
-        ),
-        annotations: None,
-    }
+    serde_json::from_value::<ToolInputSchema>(schema_value).unwrap_or_else(|e| {
+        panic!("failed to create Tool from schema: {e}");
+    })
 }
 
 impl CodexToolCallParam {
+    /// Whether the caller explicitly asked for a policy under which the user
+    /// may have to approve commands.
+    pub(crate) fn requests_approvals(&self) -> bool {
+        !matches!(
+            self.approval_policy,
+            None | Some(CodexToolCallApprovalPolicy::Never)
+        )
+    }
+
     /// Returns the initial user prompt to start the Codex conversation and the
     /// Config.
     pub fn into_config(self) -> std::io::Result<(String, codex_core::config::Config)> {
@@ -167,6 +201,7 @@ impl CodexToolCallParam {
             sandbox_policy,
             disable_response_storage,
             model_provider: None,
+            resume: None,
         };
 
         let cfg = codex_core::config::Config::load_with_overrides(overrides)?;
@@ -203,7 +238,7 @@ mod tests {
             "type": "object",
             "properties": {
               "approval-policy": {
-                "description": "Execution approval policy expressed as the kebab-case variant name (`unless-allow-listed`, `auto-edit`, `on-failure`, `never`).",
+                "description": "Execution approval policy expressed as the kebab-case variant name (`unless-allow-listed`, `auto-edit`, `on-failure`, `never`). Defaults to `never` if the client does not support elicitation, which any other value requires.",
                 "enum": [
                   "auto-edit",
                   "unless-allow-listed",
@@ -255,4 +290,50 @@ mod tests {
         });
         assert_eq!(expected_tool_json, tool_json);
     }
+
+    #[test]
+    fn verify_codex_reply_tool_json_schema() {
+        let tool = create_tool_for_codex_tool_call_reply_param();
+        #[expect(clippy::expect_used)]
+        let tool_json = serde_json::to_value(&tool).expect("tool serializes");
+        let expected_tool_json = serde_json::json!({
+          "name": "codex-reply",
+          "description": "Continue a Codex session by sending it another prompt.",
+          "inputSchema": {
+            "type": "object",
+            "properties": {
+              "prompt": {
+                "description": "The next user prompt in the Codex conversation.",
+                "type": "string"
+              },
+              "session-id": {
+                "description": "The id of the Codex session to continue, as returned by the `codex` tool.",
+                "type": "string"
+              }
+            },
+            "required": [
+              "prompt",
+              "session-id"
+            ]
+          }
+        });
+        assert_eq!(expected_tool_json, tool_json);
+    }
+
+    #[test]
+    fn only_policies_other_than_never_request_approvals() {
+        let requests_approvals = |approval_policy: serde_json::Value| {
+            #[expect(clippy::expect_used)]
+            let param: CodexToolCallParam = serde_json::from_value(serde_json::json!({
+                "prompt": "hello",
+                "approval-policy": approval_policy,
+            }))
+            .expect("param deserializes");
+            param.requests_approvals()
+        };
+        assert!(!requests_approvals(serde_json::Value::Null));
+        assert!(!requests_approvals(serde_json::json!("never")));
+        assert!(requests_approvals(serde_json::json!("on-failure")));
+        assert!(requests_approvals(serde_json::json!("unless-allow-listed")));
+    }
 }
@@ -2,21 +2,112 @@
 //! Tokio task. Separated from `message_processor.rs` to keep that file small
 //! and to make future feature-growth easier to manage.
 
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use codex_core::Codex;
 use codex_core::codex_wrapper::init_codex;
 use codex_core::config::Config as CodexConfig;
 use codex_core::protocol::AgentMessageEvent;
+use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::InputItem;
 use codex_core::protocol::Op;
+use codex_core::protocol::SessionConfiguredEvent;
 use mcp_types::CallToolResult;
 use mcp_types::CallToolResultContent;
 use mcp_types::JSONRPC_VERSION;
 use mcp_types::JSONRPCMessage;
 use mcp_types::JSONRPCResponse;
+use mcp_types::ModelContextProtocolNotification;
+use mcp_types::ProgressNotification;
+use mcp_types::ProgressNotificationParams;
+use mcp_types::ProgressToken;
 use mcp_types::RequestId;
 use mcp_types::TextContent;
-use tokio::sync::mpsc::Sender;
+use tokio::sync::Notify;
+use tokio::task::JoinSet;
+
+use crate::elicitation;
+use crate::outgoing_message::OutgoingMessageSender;
+
+/// Live Codex sessions, keyed by session id, that can be continued with the
+/// `codex-reply` tool.
+pub(crate) type CodexSessions = Arc<Mutex<CodexSessionMap>>;
+
+pub(crate) type CodexSessionMap = HashMap<String, Arc<CodexSession>>;
+
+/// A Codex session that outlives the tool call that started it.
+pub(crate) struct CodexSession {
+    codex: Codex,
+
+    /// Notified to stop the commands the session is running, like Ctrl-C
+    /// does in the CLI.
+    ctrl_c: Arc<Notify>,
+
+    /// Held by the tool call that is currently driving the session, so that
+    /// at most one tool call talks to the session at a time.
+    turn: tokio::sync::Mutex<()>,
+
+    /// When the last tool call on this session finished.
+    last_active: Mutex<Instant>,
+}
+
+impl CodexSession {
+    fn new(codex: Codex, ctrl_c: Arc<Notify>) -> Self {
+        Self {
+            codex,
+            ctrl_c,
+            turn: tokio::sync::Mutex::new(()),
+            last_active: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Whether no tool call is using the session and none has for at least
+    /// `timeout`.
+    pub(crate) fn is_idle_for(&self, timeout: Duration) -> bool {
+        #[expect(clippy::unwrap_used)]
+        let last_active = *self.last_active.lock().unwrap();
+        self.turn.try_lock().is_ok() && last_active.elapsed() >= timeout
+    }
+}
+
+/// Text of the result of a tool call that the client cancelled.
+const CANCELLED_MESSAGE: &str = "Codex tool call was cancelled.";
+
+/// How long a cancelled tool call waits for the interrupted task to end.
+const INTERRUPT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Waits, for at most [`INTERRUPT_TIMEOUT`], for the terminal event of the
+/// interrupted task `sub_id`, so that its remaining events are not picked up
+/// by the next tool call on the session.
+async fn wait_for_interrupted_task(codex: &Codex, sub_id: &str) {
+    let drain = async {
+        loop {
+            match codex.next_event().await {
+                Ok(Event {
+                    id,
+                    msg: EventMsg::TaskComplete | EventMsg::Error(_),
+                }) if id == sub_id => break,
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::error!("Codex runtime error while interrupting: {e}");
+                    break;
+                }
+            }
+        }
+    };
+    if tokio::time::timeout(INTERRUPT_TIMEOUT, drain)
+        .await
+        .is_err()
+    {
+        tracing::warn!("interrupted Codex task {sub_id} did not end in time");
+    }
+}
 
 /// Convert a Codex [`Event`] to an MCP notification.
 fn codex_event_to_notification(event: &Event) -> JSONRPCMessage {
@@ -31,14 +122,34 @@ fn codex_event_to_notification(event: &Event) -> JSONRPCMessage {
 /// Run a complete Codex session and stream events back to the client.
 ///
 /// On completion (success or error) the function sends the appropriate
-/// `tools/call` response so the LLM can continue the conversation.
+/// `tools/call` response so the LLM can continue the conversation. The
+/// session is added to `sessions` so that it can be continued with the
+/// `codex-reply` tool.
 pub async fn run_codex_tool_session(
     id: RequestId,
     initial_prompt: String,
     config: CodexConfig,
-    outgoing: Sender<JSONRPCMessage>,
+    outgoing: Arc<OutgoingMessageSender>,
+    sessions: CodexSessions,
+    cancel: Arc<Notify>,
+    progress_token: Option<ProgressToken>,
 ) {
-    let (codex, first_event, _ctrl_c) = match init_codex(config).await {
+    let init = tokio::select! {
+        init = init_codex(config) => init,
+        _ = cancel.notified() => {
+            let result = CallToolResult {
+                content: vec![CallToolResultContent::TextContent(TextContent {
+                    r#type: "text".to_string(),
+                    text: CANCELLED_MESSAGE.to_string(),
+                    annotations: None,
+                })],
+                is_error: Some(true),
+            };
+            send_tool_call_result(&outgoing, id, result).await;
+            return;
+        }
+    };
+    let (codex, first_event, ctrl_c) = match init {
         Ok(res) => res,
         Err(e) => {
             let result = CallToolResult {
@@ -49,125 +160,180 @@ pub async fn run_codex_tool_session(
                 })],
                 is_error: Some(true),
             };
-            let _ = outgoing
-                .send(JSONRPCMessage::Response(JSONRPCResponse {
-                    jsonrpc: JSONRPC_VERSION.into(),
-                    id,
-                    result: result.into(),
-                }))
-                .await;
+            send_tool_call_result(&outgoing, id, result).await;
             return;
         }
     };
 
     // Send initial SessionConfigured event.
-    let _ = outgoing
+    outgoing
         .send(codex_event_to_notification(&first_event))
         .await;
 
-    if let Err(e) = codex
+    // `init_codex` checks that the first event is `SessionConfigured`.
+    let session_id = match &first_event.msg {
+        EventMsg::SessionConfigured(SessionConfiguredEvent { session_id, .. }) => {
+            session_id.to_string()
+        }
+        msg => {
+            let result = CallToolResult {
+                content: vec![CallToolResultContent::TextContent(TextContent {
+                    r#type: "text".to_string(),
+                    text: format!("Failed to start Codex session: unexpected first event {msg:?}"),
+                    annotations: None,
+                })],
+                is_error: Some(true),
+            };
+            send_tool_call_result(&outgoing, id, result).await;
+            return;
+        }
+    };
+    let session = Arc::new(CodexSession::new(codex, ctrl_c));
+    {
+        #[expect(clippy::unwrap_used)]
+        let mut sessions = sessions.lock().unwrap();
+        sessions.insert(session_id.clone(), session.clone());
+    }
+
+    run_codex_tool_session_reply(
+        id,
+        session_id,
+        session,
+        initial_prompt,
+        outgoing,
+        cancel,
+        progress_token,
+    )
+    .await;
+}
+
+/// Send `prompt` to an existing Codex session and stream events back to the
+/// client until the session needs to pause for user interaction or completes
+/// the task, at which point the `tools/call` response is sent.
+///
+/// When `cancel` is notified, the commands the task is running and the
+/// processes they started are killed, the task is interrupted, and the tool
+/// call ends with a cancelled result. If
+/// the client supplied a `progress_token`, `notifications/progress` are sent
+/// as the task makes progress.
+pub async fn run_codex_tool_session_reply(
+    id: RequestId,
+    session_id: String,
+    session: Arc<CodexSession>,
+    prompt: String,
+    outgoing: Arc<OutgoingMessageSender>,
+    cancel: Arc<Notify>,
+    progress_token: Option<ProgressToken>,
+) {
+    let Ok(_turn) = session.turn.try_lock() else {
+        let result = CallToolResult {
+            content: vec![CallToolResultContent::TextContent(TextContent {
+                r#type: "text".to_string(),
+                text: format!(
+                    "Codex session '{session_id}' is busy with another tool call; try again once it has completed."
+                ),
+                annotations: None,
+            })],
+            is_error: Some(true),
+        };
+        send_tool_call_result(&outgoing, id, result).await;
+        return;
+    };
+
+    let codex = &session.codex;
+    let sub_id = match codex
         .submit(Op::UserInput {
-            items: vec![InputItem::Text {
-                text: initial_prompt.clone(),
-            }],
+            items: vec![InputItem::Text { text: prompt }],
         })
         .await
     {
-        tracing::error!("Failed to submit initial prompt: {e}");
-    }
+        Ok(sub_id) => sub_id,
+        Err(e) => {
+            let result = CallToolResult {
+                content: vec![CallToolResultContent::TextContent(TextContent {
+                    r#type: "text".to_string(),
+                    text: format!("Failed to submit prompt: {e}"),
+                    annotations: None,
+                })],
+                is_error: Some(true),
+            };
+            send_tool_call_result(&outgoing, id, result).await;
+            return;
+        }
+    };
 
     let mut last_agent_message: Option<String> = None;
+    let mut progress = ProgressReporter {
+        outgoing: outgoing.clone(),
+        token: progress_token,
+        progress: 0.0,
+    };
 
-    // Stream events until the task needs to pause for user interaction or
-    // completes.
-    loop {
-        match codex.next_event().await {
+    // Stream events until the task completes. Approval requests are forwarded
+    // to the client while the task waits for the decision; any that are still
+    // waiting when the tool call ends are dropped along with this set.
+    let mut approval_requests = JoinSet::new();
+    let (text, is_error) = loop {
+        let event = tokio::select! {
+            event = codex.next_event() => event,
+            _ = cancel.notified() => {
+                // Interrupting the task alone would not stop a command that
+                // runs in the Linux sandbox, which is waited for on its own
+                // thread.
+                session.ctrl_c.notify_waiters();
+                match codex.submit(Op::Interrupt).await {
+                    Ok(_) => wait_for_interrupted_task(codex, &sub_id).await,
+                    Err(e) => tracing::error!("Failed to interrupt Codex session: {e}"),
+                }
+                break (CANCELLED_MESSAGE.to_string(), Some(true));
+            }
+        };
+        match event {
             Ok(event) => {
-                let _ = outgoing.send(codex_event_to_notification(&event)).await;
+                outgoing.send(codex_event_to_notification(&event)).await;
+                progress.report(&event.msg).await;
 
                 match &event.msg {
                     EventMsg::AgentMessage(AgentMessageEvent { message }) => {
                         last_agent_message = Some(message.clone());
                     }
-                    EventMsg::ExecApprovalRequest(_) => {
-                        let result = CallToolResult {
-                            content: vec![CallToolResultContent::TextContent(TextContent {
-                                r#type: "text".to_string(),
-                                text: "EXEC_APPROVAL_REQUIRED".to_string(),
-                                annotations: None,
-                            })],
-                            is_error: None,
-                        };
-                        let _ = outgoing
-                            .send(JSONRPCMessage::Response(JSONRPCResponse {
-                                jsonrpc: JSONRPC_VERSION.into(),
-                                id: id.clone(),
-                                result: result.into(),
-                            }))
-                            .await;
-                        break;
+                    EventMsg::ExecApprovalRequest(_)
+                    | EventMsg::ApplyPatchApprovalRequest(_)
+                    | EventMsg::McpToolCallApprovalRequest(_) => {
+                        approval_requests.spawn(forward_approval_request(
+                            session.clone(),
+                            outgoing.clone(),
+                            event.clone(),
+                        ));
                     }
-                    EventMsg::ApplyPatchApprovalRequest(_) => {
-                        let result = CallToolResult {
-                            content: vec![CallToolResultContent::TextContent(TextContent {
-                                r#type: "text".to_string(),
-                                text: "PATCH_APPROVAL_REQUIRED".to_string(),
-                                annotations: None,
-                            })],
-                            is_error: None,
-                        };
-                        let _ = outgoing
-                            .send(JSONRPCMessage::Response(JSONRPCResponse {
-                                jsonrpc: JSONRPC_VERSION.into(),
-                                id: id.clone(),
-                                result: result.into(),
-                            }))
-                            .await;
-                        break;
+                    EventMsg::TaskComplete if event.id == sub_id => {
+                        break (last_agent_message.unwrap_or_default(), None);
                     }
-                    EventMsg::TaskComplete => {
-                        let result = if let Some(msg) = last_agent_message {
-                            CallToolResult {
-                                content: vec![CallToolResultContent::TextContent(TextContent {
-                                    r#type: "text".to_string(),
-                                    text: msg,
-                                    annotations: None,
-                                })],
-                                is_error: None,
-                            }
-                        } else {
-                            CallToolResult {
-                                content: vec![CallToolResultContent::TextContent(TextContent {
-                                    r#type: "text".to_string(),
-                                    text: String::new(),
-                                    annotations: None,
-                                })],
-                                is_error: None,
-                            }
-                        };
-                        let _ = outgoing
-                            .send(JSONRPCMessage::Response(JSONRPCResponse {
-                                jsonrpc: JSONRPC_VERSION.into(),
-                                id: id.clone(),
-                                result: result.into(),
-                            }))
-                            .await;
-                        break;
+                    // An error for the submission means that the task has
+                    // ended, e.g. because the user aborted it.
+                    EventMsg::Error(ErrorEvent { message }) if event.id == sub_id => {
+                        break (message.clone(), Some(true));
                     }
                     EventMsg::SessionConfigured(_) => {
                         tracing::error!("unexpected SessionConfigured event");
                     }
                     EventMsg::Error(_)
+                    | EventMsg::TaskComplete
                     | EventMsg::TaskStarted
+                    | EventMsg::AgentMessageDelta(_)
                     | EventMsg::AgentReasoning(_)
+                    | EventMsg::AgentReasoningDelta(_)
                     | EventMsg::McpToolCallBegin(_)
                     | EventMsg::McpToolCallEnd(_)
                     | EventMsg::ExecCommandBegin(_)
+                    | EventMsg::ExecCommandOutputDelta(_)
                     | EventMsg::ExecCommandEnd(_)
                     | EventMsg::BackgroundEvent(_)
                     | EventMsg::PatchApplyBegin(_)
                     | EventMsg::PatchApplyEnd(_)
-                    | EventMsg::GetHistoryEntryResponse(_) => {
+                    | EventMsg::GetHistoryEntryResponse(_)
+                    | EventMsg::GetMcpPromptResponse(_)
+                    | EventMsg::TokenCount(_) => {
                         // For now, we do not do anything extra for these
                         // events. Note that
                         // send(codex_event_to_notification(&event)) above has
@@ -178,23 +344,124 @@ pub async fn run_codex_tool_session(
                 }
             }
             Err(e) => {
-                let result = CallToolResult {
-                    content: vec![CallToolResultContent::TextContent(TextContent {
-                        r#type: "text".to_string(),
-                        text: format!("Codex runtime error: {e}"),
-                        annotations: None,
-                    })],
-                    is_error: Some(true),
-                };
-                let _ = outgoing
-                    .send(JSONRPCMessage::Response(JSONRPCResponse {
-                        jsonrpc: JSONRPC_VERSION.into(),
-                        id: id.clone(),
-                        result: result.into(),
-                    }))
-                    .await;
-                break;
+                break (format!("Codex runtime error: {e}"), Some(true));
             }
         }
+    };
+
+    {
+        #[expect(clippy::unwrap_used)]
+        let mut last_active = session.last_active.lock().unwrap();
+        *last_active = Instant::now();
     }
+
+    let result = CallToolResult {
+        content: vec![
+            CallToolResultContent::TextContent(TextContent {
+                r#type: "text".to_string(),
+                text,
+                annotations: None,
+            }),
+            CallToolResultContent::TextContent(TextContent {
+                r#type: "text".to_string(),
+                text: format!(
+                    "session-id: {session_id} (pass it to the `codex-reply` tool to continue this conversation)"
+                ),
+                annotations: None,
+            }),
+        ],
+        is_error,
+    };
+    send_tool_call_result(&outgoing, id, result).await;
+}
+
+/// Sends `notifications/progress` for the tool call if the client asked for
+/// them by supplying a progress token.
+struct ProgressReporter {
+    outgoing: Arc<OutgoingMessageSender>,
+    token: Option<ProgressToken>,
+    progress: f64,
+}
+
+impl ProgressReporter {
+    async fn report(&mut self, msg: &EventMsg) {
+        let Some(token) = &self.token else {
+            return;
+        };
+        let Some(message) = progress_message(msg) else {
+            return;
+        };
+        self.progress += 1.0;
+
+        let params = ProgressNotificationParams {
+            message: Some(message),
+            progress: self.progress,
+            progress_token: token.clone(),
+            total: None,
+        };
+        #[expect(clippy::expect_used)]
+        let params = serde_json::to_value(params).expect("progress params must serialize");
+        self.outgoing
+            .send(JSONRPCMessage::Notification(
+                mcp_types::JSONRPCNotification {
+                    jsonrpc: JSONRPC_VERSION.into(),
+                    method: ProgressNotification::METHOD.into(),
+                    params: Some(params),
+                },
+            ))
+            .await;
+    }
+}
+
+/// Describes the events that mark progress of the task; `None` for the rest.
+fn progress_message(msg: &EventMsg) -> Option<String> {
+    match msg {
+        EventMsg::TaskStarted => Some("Codex is working".to_string()),
+        EventMsg::ExecCommandBegin(event) => Some(format!("Running `{}`", event.command.join(" "))),
+        EventMsg::PatchApplyBegin(_) => Some("Applying a patch".to_string()),
+        EventMsg::McpToolCallBegin(event) => Some(format!(
+            "Calling tool `{}` on MCP server `{}`",
+            event.tool, event.server
+        )),
+        EventMsg::ExecApprovalRequest(_)
+        | EventMsg::ApplyPatchApprovalRequest(_)
+        | EventMsg::McpToolCallApprovalRequest(_) => Some("Waiting for approval".to_string()),
+        _ => None,
+    }
+}
+
+/// Ask the client to approve the request in `event` and pass its decision on
+/// to the session.
+async fn forward_approval_request(
+    session: Arc<CodexSession>,
+    outgoing: Arc<OutgoingMessageSender>,
+    event: Event,
+) {
+    let Some(decision) = elicitation::request_approval(&outgoing, &event).await else {
+        return;
+    };
+    let id = event.id;
+    let op = match event.msg {
+        EventMsg::ExecApprovalRequest(_) => Op::ExecApproval { id, decision },
+        EventMsg::ApplyPatchApprovalRequest(_) => Op::PatchApproval { id, decision },
+        EventMsg::McpToolCallApprovalRequest(_) => Op::McpToolCallApproval { id, decision },
+        _ => return,
+    };
+    if let Err(e) = session.codex.submit(op).await {
+        tracing::error!("Failed to submit approval decision: {e}");
+    }
+}
+
+async fn send_tool_call_result(
+    outgoing: &OutgoingMessageSender,
+    id: RequestId,
+    result: CallToolResult,
+) {
+    outgoing
+        .send(JSONRPCMessage::Response(JSONRPCResponse {
+            jsonrpc: JSONRPC_VERSION.into(),
+            id,
+            result: result.into(),
+        }))
+        .await;
 }
@@ -0,0 +1,189 @@
+//! Asks the MCP client to approve a command, patch, or MCP tool call on
+//! behalf of Codex. The request mirrors the `elicitation/create` request of
+//! newer MCP revisions: `message` is shown to the user and `requestedSchema`
+//! describes the single `decision` field the client should answer with. The
+//! original Codex event is attached as `codexEvent` so that clients can
+//! render a richer prompt.
+
+use codex_core::protocol::Event;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::ReviewDecision;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::outgoing_message::ClientResponse;
+use crate::outgoing_message::OutgoingMessageSender;
+
+pub(crate) const ELICITATION_CREATE_METHOD: &str = "elicitation/create";
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ElicitRequestParams<'a> {
+    message: String,
+    requested_schema: serde_json::Value,
+    codex_event: &'a Event,
+}
+
+#[derive(Debug, Deserialize)]
+struct ElicitResult {
+    action: ElicitAction,
+    #[serde(default)]
+    content: Option<ElicitContent>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ElicitAction {
+    Accept,
+    Decline,
+    Cancel,
+}
+
+#[derive(Debug, Deserialize)]
+struct ElicitContent {
+    decision: ReviewDecision,
+}
+
+/// Ask the client to approve the request described by `event`, which must be
+/// one of the `*ApprovalRequest` events. Returns `None` for other events.
+pub(crate) async fn request_approval(
+    outgoing: &OutgoingMessageSender,
+    event: &Event,
+) -> Option<ReviewDecision> {
+    let message = approval_message(&event.msg)?;
+    let params = ElicitRequestParams {
+        message,
+        requested_schema: json!({
+            "type": "object",
+            "properties": {
+                "decision": {
+                    "type": "string",
+                    "enum": ["approved", "approved_for_session", "denied", "abort"],
+                },
+            },
+            "required": ["decision"],
+        }),
+        codex_event: event,
+    };
+    let params = match serde_json::to_value(&params) {
+        Ok(params) => params,
+        Err(e) => {
+            tracing::error!("failed to serialize elicitation request: {e}");
+            return Some(ReviewDecision::Denied);
+        }
+    };
+
+    let response = outgoing
+        .send_request(ELICITATION_CREATE_METHOD, Some(params))
+        .await;
+    let decision = match response.await {
+        Ok(response) => decision_from_response(response),
+        // The server is shutting down.
+        Err(_) => ReviewDecision::Abort,
+    };
+    Some(decision)
+}
+
+fn approval_message(msg: &EventMsg) -> Option<String> {
+    let (mut message, reason) = match msg {
+        EventMsg::ExecApprovalRequest(event) => (
+            format!(
+                "Allow Codex to run `{}` in {}?",
+                event.command.join(" "),
+                event.cwd.display()
+            ),
+            event.reason.as_ref(),
+        ),
+        EventMsg::ApplyPatchApprovalRequest(event) => {
+            let mut message = format!(
+                "Allow Codex to apply changes to {} file(s)?",
+                event.changes.len()
+            );
+            if let Some(root) = &event.grant_root {
+                message.push_str(&format!(
+                    " This grants write access to {} for the rest of the session.",
+                    root.display()
+                ));
+            }
+            (message, event.reason.as_ref())
+        }
+        EventMsg::McpToolCallApprovalRequest(event) => (
+            format!(
+                "Allow Codex to call tool `{}` on MCP server `{}`?",
+                event.tool, event.server
+            ),
+            None,
+        ),
+        _ => return None,
+    };
+    if let Some(reason) = reason {
+        message.push_str(&format!(" Reason: {reason}"));
+    }
+    Some(message)
+}
+
+/// Errors and malformed responses deny the request rather than aborting the
+/// task, so that the model can try something else.
+fn decision_from_response(response: ClientResponse) -> ReviewDecision {
+    let result = match response {
+        Ok(result) => result,
+        Err(error) => {
+            tracing::warn!("client failed to answer approval request: {error:?}");
+            return ReviewDecision::Denied;
+        }
+    };
+    match serde_json::from_value::<ElicitResult>(result) {
+        Ok(ElicitResult {
+            action: ElicitAction::Accept,
+            content,
+        }) => content.map_or(ReviewDecision::Approved, |content| content.decision),
+        Ok(ElicitResult {
+            action: ElicitAction::Decline,
+            ..
+        }) => ReviewDecision::Denied,
+        Ok(ElicitResult {
+            action: ElicitAction::Cancel,
+            ..
+        }) => ReviewDecision::Abort,
+        Err(e) => {
+            tracing::warn!("malformed response to approval request: {e}");
+            ReviewDecision::Denied
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_types::JSONRPCErrorError;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_decision_from_response() {
+        let cases = [
+            (
+                json!({ "action": "accept", "content": { "decision": "approved_for_session" } }),
+                ReviewDecision::ApprovedForSession,
+            ),
+            (json!({ "action": "accept" }), ReviewDecision::Approved),
+            (json!({ "action": "decline" }), ReviewDecision::Denied),
+            (json!({ "action": "cancel" }), ReviewDecision::Abort),
+            (json!({ "decision": "approved" }), ReviewDecision::Denied),
+        ];
+        for (result, expected) in cases {
+            assert_eq!(
+                expected,
+                decision_from_response(Ok(result.clone())),
+                "{result}"
+            );
+        }
+
+        let error = JSONRPCErrorError {
+            code: -32601,
+            message: "Method not found".to_string(),
+            data: None,
+        };
+        assert_eq!(ReviewDecision::Denied, decision_from_response(Err(error)));
+    }
+}
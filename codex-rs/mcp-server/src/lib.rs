@@ -13,9 +13,13 @@ use tracing::debug;
 use tracing::error;
 use tracing::info;
 
+mod codex_prompts;
+mod codex_resources;
 mod codex_tool_config;
 mod codex_tool_runner;
+mod elicitation;
 mod message_processor;
+mod outgoing_message;
 
 use crate::message_processor::MessageProcessor;
 
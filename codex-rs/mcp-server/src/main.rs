@@ -2,6 +2,7 @@ use codex_mcp_server::run_main;
 
 #[tokio::main]
 async fn main() -> std::io::Result<()> {
+    codex_core::maybe_run_apply_patch_helper();
     run_main().await?;
     Ok(())
 }
@@ -1,7 +1,24 @@
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::Weak;
+use std::time::Duration;
+
+use crate::codex_prompts;
+use crate::codex_resources;
+use crate::codex_resources::ResourceError;
 use crate::codex_tool_config::CodexToolCallParam;
+use crate::codex_tool_config::CodexToolCallReplyParam;
 use crate::codex_tool_config::create_tool_for_codex_tool_call_param;
+use crate::codex_tool_config::create_tool_for_codex_tool_call_reply_param;
+use crate::codex_tool_runner::CodexSessionMap;
+use crate::codex_tool_runner::CodexSessions;
+use crate::outgoing_message::OutgoingMessageSender;
 
 use codex_core::config::Config as CodexConfig;
+use codex_core::config::find_codex_home;
+use codex_core::protocol::AskForApproval;
 use mcp_types::CallToolRequestParams;
 use mcp_types::CallToolResult;
 use mcp_types::CallToolResultContent;
@@ -17,32 +34,67 @@ use mcp_types::JSONRPCRequest;
 use mcp_types::JSONRPCResponse;
 use mcp_types::ListToolsResult;
 use mcp_types::ModelContextProtocolRequest;
+use mcp_types::ProgressToken;
 use mcp_types::RequestId;
+use mcp_types::ServerCapabilitiesPrompts;
+use mcp_types::ServerCapabilitiesResources;
 use mcp_types::ServerCapabilitiesTools;
 use mcp_types::ServerNotification;
 use mcp_types::TextContent;
 use serde_json::json;
+use tokio::sync::Notify;
 use tokio::sync::mpsc;
 use tokio::task;
 
+/// Codex sessions that have not been used for this long are shut down.
+const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// JSON-RPC error codes used in responses.
+const INVALID_PARAMS_ERROR_CODE: i64 = -32602;
+const INTERNAL_ERROR_CODE: i64 = -32603;
+/// Defined by MCP for `resources/read` of an unknown resource.
+const RESOURCE_NOT_FOUND_ERROR_CODE: i64 = -32002;
+
+/// How often to look for idle Codex sessions.
+const SESSION_EXPIRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Request id and cancellation signal of each in-flight `codex` or
+/// `codex-reply` tool call.
+type RunningToolCalls = Arc<Mutex<Vec<(RequestId, Arc<Notify>)>>>;
+
 pub(crate) struct MessageProcessor {
-    outgoing: mpsc::Sender<JSONRPCMessage>,
+    outgoing: Arc<OutgoingMessageSender>,
     initialized: bool,
+    /// Whether the client declared the `elicitation` capability, which
+    /// approval requests are sent with (see [`crate::elicitation`]).
+    client_supports_elicitation: bool,
+    sessions: CodexSessions,
+
+    running_tool_calls: RunningToolCalls,
 }
 
 impl MessageProcessor {
     /// Create a new `MessageProcessor`, retaining a handle to the outgoing
     /// `Sender` so handlers can enqueue messages to be written to stdout.
+    /// Must be called from within a Tokio runtime, which is used to expire
+    /// idle Codex sessions.
     pub(crate) fn new(outgoing: mpsc::Sender<JSONRPCMessage>) -> Self {
+        let sessions = CodexSessions::default();
+        task::spawn(expire_idle_sessions(Arc::downgrade(&sessions)));
         Self {
-            outgoing,
+            outgoing: Arc::new(OutgoingMessageSender::new(outgoing)),
             initialized: false,
+            client_supports_elicitation: false,
+            sessions,
+            running_tool_calls: Arc::default(),
         }
     }
 
     pub(crate) fn process_request(&mut self, request: JSONRPCRequest) {
         // Hold on to the ID so we can respond.
         let request_id = request.id.clone();
+        let progress_token = request_progress_token(&request);
+        let declares_elicitation = request_declares_elicitation(&request);
 
         let client_request = match ClientRequest::try_from(request) {
             Ok(client_request) => client_request,
@@ -55,19 +107,19 @@ impl MessageProcessor {
         // Dispatch to a dedicated handler for each request type.
         match client_request {
             ClientRequest::InitializeRequest(params) => {
-                self.handle_initialize(request_id, params);
+                self.handle_initialize(request_id, params, declares_elicitation);
             }
             ClientRequest::PingRequest(params) => {
                 self.handle_ping(request_id, params);
             }
             ClientRequest::ListResourcesRequest(params) => {
-                self.handle_list_resources(params);
+                self.handle_list_resources(request_id, params);
             }
             ClientRequest::ListResourceTemplatesRequest(params) => {
-                self.handle_list_resource_templates(params);
+                self.handle_list_resource_templates(request_id, params);
             }
             ClientRequest::ReadResourceRequest(params) => {
-                self.handle_read_resource(params);
+                self.handle_read_resource(request_id, params);
             }
             ClientRequest::SubscribeRequest(params) => {
                 self.handle_subscribe(params);
@@ -76,16 +128,16 @@ impl MessageProcessor {
                 self.handle_unsubscribe(params);
             }
             ClientRequest::ListPromptsRequest(params) => {
-                self.handle_list_prompts(params);
+                self.handle_list_prompts(request_id, params);
             }
             ClientRequest::GetPromptRequest(params) => {
-                self.handle_get_prompt(params);
+                self.handle_get_prompt(request_id, params);
             }
             ClientRequest::ListToolsRequest(params) => {
                 self.handle_list_tools(request_id, params);
             }
             ClientRequest::CallToolRequest(params) => {
-                self.handle_call_tool(request_id, params);
+                self.handle_call_tool(request_id, params, progress_token);
             }
             ClientRequest::SetLevelRequest(params) => {
                 self.handle_set_level(params);
@@ -99,6 +151,8 @@ impl MessageProcessor {
     /// Handle a standalone JSON-RPC response originating from the peer.
     pub(crate) fn process_response(&mut self, response: JSONRPCResponse) {
         tracing::info!("<- response: {:?}", response);
+        let JSONRPCResponse { id, result, .. } = response;
+        self.outgoing.notify_client_response(id, Ok(result));
     }
 
     /// Handle a fire-and-forget JSON-RPC notification.
@@ -156,6 +210,8 @@ impl MessageProcessor {
     /// Handle an error object received from the peer.
     pub(crate) fn process_error(&mut self, err: JSONRPCError) {
         tracing::error!("<- error: {:?}", err);
+        let JSONRPCError { id, error, .. } = err;
+        self.outgoing.notify_client_response(id, Err(error));
     }
 
     /// Handle a batch of responses/errors.
@@ -177,6 +233,7 @@ impl MessageProcessor {
         &mut self,
         id: RequestId,
         params: <mcp_types::InitializeRequest as ModelContextProtocolRequest>::Params,
+        declares_elicitation: bool,
     ) {
         tracing::info!("initialize -> params: {:?}", params);
 
@@ -199,6 +256,7 @@ impl MessageProcessor {
         }
 
         self.initialized = true;
+        self.client_supports_elicitation = declares_elicitation;
 
         // Build a minimal InitializeResult. Fill with placeholders.
         let result = mcp_types::InitializeResult {
@@ -206,8 +264,13 @@ impl MessageProcessor {
                 completions: None,
                 experimental: None,
                 logging: None,
-                prompts: None,
-                resources: None,
+                prompts: Some(ServerCapabilitiesPrompts {
+                    list_changed: Some(false),
+                }),
+                resources: Some(ServerCapabilitiesResources {
+                    list_changed: Some(false),
+                    subscribe: Some(false),
+                }),
                 tools: Some(ServerCapabilitiesTools {
                     list_changed: Some(true),
                 }),
@@ -240,6 +303,17 @@ impl MessageProcessor {
         }
     }
 
+    fn send_error(&self, id: RequestId, error: JSONRPCErrorError) {
+        let error = JSONRPCMessage::Error(JSONRPCError {
+            jsonrpc: JSONRPC_VERSION.into(),
+            id,
+            error,
+        });
+        if let Err(e) = self.outgoing.try_send(error) {
+            tracing::error!("Failed to send error: {e}");
+        }
+    }
+
     fn handle_ping(
         &self,
         id: RequestId,
@@ -252,24 +326,56 @@ impl MessageProcessor {
 
     fn handle_list_resources(
         &self,
+        id: RequestId,
         params: <mcp_types::ListResourcesRequest as mcp_types::ModelContextProtocolRequest>::Params,
     ) {
         tracing::info!("resources/list -> params: {:?}", params);
+        let Some(codex_home) = self.codex_home(&id) else {
+            return;
+        };
+        let cursor = params.and_then(|params| params.cursor);
+        match codex_resources::list_resources(&codex_home, cursor.as_deref()) {
+            Ok(result) => self.send_response::<mcp_types::ListResourcesRequest>(id, result),
+            Err(e) => self.send_error(id, resource_error(e)),
+        }
     }
 
     fn handle_list_resource_templates(
         &self,
+        id: RequestId,
         params:
             <mcp_types::ListResourceTemplatesRequest as mcp_types::ModelContextProtocolRequest>::Params,
     ) {
         tracing::info!("resources/templates/list -> params: {:?}", params);
+        self.send_response::<mcp_types::ListResourceTemplatesRequest>(
+            id,
+            codex_resources::list_resource_templates(),
+        );
     }
 
     fn handle_read_resource(
         &self,
+        id: RequestId,
         params: <mcp_types::ReadResourceRequest as mcp_types::ModelContextProtocolRequest>::Params,
     ) {
         tracing::info!("resources/read -> params: {:?}", params);
+        let Some(codex_home) = self.codex_home(&id) else {
+            return;
+        };
+
+        // Rollouts can be large, so read them without blocking the
+        // message-processing loop.
+        let outgoing = self.outgoing.clone();
+        task::spawn(async move {
+            match codex_resources::read_resource(&codex_home, &params.uri).await {
+                Ok(result) => {
+                    #[expect(clippy::unwrap_used)]
+                    let result = serde_json::to_value(result).unwrap();
+                    outgoing.send_response(id, result).await;
+                }
+                Err(e) => outgoing.send_error(id, resource_error(e)).await,
+            }
+        });
     }
 
     fn handle_subscribe(
@@ -288,16 +394,49 @@ impl MessageProcessor {
 
     fn handle_list_prompts(
         &self,
+        id: RequestId,
         params: <mcp_types::ListPromptsRequest as mcp_types::ModelContextProtocolRequest>::Params,
     ) {
         tracing::info!("prompts/list -> params: {:?}", params);
+        self.send_response::<mcp_types::ListPromptsRequest>(id, codex_prompts::list_prompts());
     }
 
     fn handle_get_prompt(
         &self,
+        id: RequestId,
         params: <mcp_types::GetPromptRequest as mcp_types::ModelContextProtocolRequest>::Params,
     ) {
         tracing::info!("prompts/get -> params: {:?}", params);
+        match codex_prompts::get_prompt(&params.name, params.arguments.as_ref()) {
+            Ok(result) => self.send_response::<mcp_types::GetPromptRequest>(id, result),
+            Err(message) => self.send_error(
+                id,
+                JSONRPCErrorError {
+                    code: INVALID_PARAMS_ERROR_CODE,
+                    message,
+                    data: None,
+                },
+            ),
+        }
+    }
+
+    /// Resolve `~/.codex`, responding to the request `id` with an error if
+    /// that fails.
+    fn codex_home(&self, id: &RequestId) -> Option<PathBuf> {
+        match find_codex_home() {
+            Ok(codex_home) => Some(codex_home),
+            Err(e) => {
+                self.send_error(
+                    id.clone(),
+                    JSONRPCErrorError {
+                        code: INTERNAL_ERROR_CODE,
+                        message: format!("failed to locate the Codex home directory: {e}"),
+                        data: None,
+                    },
+                );
+                None
+            }
+        }
     }
 
     fn handle_list_tools(
@@ -307,7 +446,10 @@ impl MessageProcessor {
     ) {
         tracing::trace!("tools/list -> {params:?}");
         let result = ListToolsResult {
-            tools: vec![create_tool_for_codex_tool_call_param()],
+            tools: vec![
+                create_tool_for_codex_tool_call_param(),
+                create_tool_for_codex_tool_call_reply_param(),
+            ],
             next_cursor: None,
         };
 
@@ -318,27 +460,52 @@ impl MessageProcessor {
         &self,
         id: RequestId,
         params: <mcp_types::CallToolRequest as mcp_types::ModelContextProtocolRequest>::Params,
+        progress_token: Option<ProgressToken>,
     ) {
         tracing::info!("tools/call -> params: {:?}", params);
         let CallToolRequestParams { name, arguments } = params;
 
-        // We only support the "codex" tool for now.
-        if name != "codex" {
-            // Tool not found – return error result so the LLM can react.
-            let result = CallToolResult {
-                content: vec![CallToolResultContent::TextContent(TextContent {
-                    r#type: "text".to_string(),
-                    text: format!("Unknown tool '{name}'"),
-                    annotations: None,
-                })],
-                is_error: Some(true),
-            };
-            self.send_response::<mcp_types::CallToolRequest>(id, result);
-            return;
+        match name.as_str() {
+            "codex" => self.handle_call_codex_tool(id, arguments, progress_token),
+            "codex-reply" => self.handle_call_codex_reply_tool(id, arguments, progress_token),
+            _ => {
+                // Tool not found – return error result so the LLM can react.
+                let result = CallToolResult {
+                    content: vec![CallToolResultContent::TextContent(TextContent {
+                        r#type: "text".to_string(),
+                        text: format!("Unknown tool '{name}'"),
+                        annotations: None,
+                    })],
+                    is_error: Some(true),
+                };
+                self.send_response::<mcp_types::CallToolRequest>(id, result);
+            }
         }
+    }
 
-        let (initial_prompt, config): (String, CodexConfig) = match arguments {
+    fn handle_call_codex_tool(
+        &self,
+        id: RequestId,
+        arguments: Option<serde_json::Value>,
+        progress_token: Option<ProgressToken>,
+    ) {
+        let (initial_prompt, mut config): (String, CodexConfig) = match arguments {
             Some(json_val) => match serde_json::from_value::<CodexToolCallParam>(json_val) {
+                Ok(tool_cfg)
+                    if tool_cfg.requests_approvals() && !self.client_supports_elicitation =>
+                {
+                    let result = CallToolResult {
+                        content: vec![CallToolResultContent::TextContent(TextContent {
+                            r#type: "text".to_owned(),
+                            text: "The requested `approval-policy` asks the user for approvals, which requires a client that supports elicitation; use `never` or omit it."
+                                .to_owned(),
+                            annotations: None,
+                        })],
+                        is_error: Some(true),
+                    };
+                    self.send_response::<mcp_types::CallToolRequest>(id, result);
+                    return;
+                }
                 Ok(tool_cfg) => match tool_cfg.into_config() {
                     Ok(cfg) => cfg,
                     Err(e) => {
@@ -385,15 +552,125 @@ impl MessageProcessor {
             }
         };
 
+        // Without elicitation, approval requests could not be answered, so
+        // the session must not ask for any. Calls that explicitly ask for
+        // approvals were rejected above.
+        if !self.client_supports_elicitation {
+            config.approval_policy = AskForApproval::Never;
+        }
+
         // Clone outgoing sender to move into async task.
         let outgoing = self.outgoing.clone();
+        let sessions = self.sessions.clone();
 
         // Spawn an async task to handle the Codex session so that we do not
         // block the synchronous message-processing loop.
-        task::spawn(async move {
+        self.spawn_tool_call(id.clone(), move |cancel| {
             // Run the Codex session and stream events back to the client.
-            crate::codex_tool_runner::run_codex_tool_session(id, initial_prompt, config, outgoing)
-                .await;
+            crate::codex_tool_runner::run_codex_tool_session(
+                id,
+                initial_prompt,
+                config,
+                outgoing,
+                sessions,
+                cancel,
+                progress_token,
+            )
+        });
+    }
+
+    fn handle_call_codex_reply_tool(
+        &self,
+        id: RequestId,
+        arguments: Option<serde_json::Value>,
+        progress_token: Option<ProgressToken>,
+    ) {
+        let CodexToolCallReplyParam { session_id, prompt } = match arguments
+            .map(serde_json::from_value::<CodexToolCallReplyParam>)
+        {
+            Some(Ok(params)) => params,
+            Some(Err(e)) => {
+                let result = CallToolResult {
+                    content: vec![CallToolResultContent::TextContent(TextContent {
+                        r#type: "text".to_owned(),
+                        text: format!("Failed to parse arguments for codex-reply tool: {e}"),
+                        annotations: None,
+                    })],
+                    is_error: Some(true),
+                };
+                self.send_response::<mcp_types::CallToolRequest>(id, result);
+                return;
+            }
+            None => {
+                let result = CallToolResult {
+                    content: vec![CallToolResultContent::TextContent(TextContent {
+                        r#type: "text".to_string(),
+                        text: "Missing arguments for codex-reply tool-call; the `session-id` and `prompt` fields are required."
+                            .to_string(),
+                        annotations: None,
+                    })],
+                    is_error: Some(true),
+                };
+                self.send_response::<mcp_types::CallToolRequest>(id, result);
+                return;
+            }
+        };
+
+        let session = {
+            #[expect(clippy::unwrap_used)]
+            let sessions = self.sessions.lock().unwrap();
+            sessions.get(&session_id).cloned()
+        };
+        let Some(session) = session else {
+            let result = CallToolResult {
+                content: vec![CallToolResultContent::TextContent(TextContent {
+                    r#type: "text".to_string(),
+                    text: format!(
+                        "No Codex session with id '{session_id}'; it may have expired after being idle."
+                    ),
+                    annotations: None,
+                })],
+                is_error: Some(true),
+            };
+            self.send_response::<mcp_types::CallToolRequest>(id, result);
+            return;
+        };
+
+        let outgoing = self.outgoing.clone();
+        self.spawn_tool_call(id.clone(), move |cancel| {
+            crate::codex_tool_runner::run_codex_tool_session_reply(
+                id,
+                session_id,
+                session,
+                prompt,
+                outgoing,
+                cancel,
+                progress_token,
+            )
+        });
+    }
+
+    /// Spawn the future returned by `run` for the tool call `id`, passing it
+    /// the signal that is notified if the client cancels the tool call.
+    fn spawn_tool_call<F, Fut>(&self, id: RequestId, run: F)
+    where
+        F: FnOnce(Arc<Notify>) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let cancel = Arc::new(Notify::new());
+        {
+            #[expect(clippy::unwrap_used)]
+            let mut running_tool_calls = self.running_tool_calls.lock().unwrap();
+            running_tool_calls.push((id.clone(), cancel.clone()));
+        }
+
+        let running_tool_calls = self.running_tool_calls.clone();
+        let tool_call = run(cancel);
+        task::spawn(async move {
+            tool_call.await;
+            #[expect(clippy::unwrap_used)]
+            let mut running_tool_calls = running_tool_calls.lock().unwrap();
+            running_tool_calls.retain(|(running_id, _)| running_id != &id);
         });
     }
 
@@ -420,6 +697,16 @@ impl MessageProcessor {
         params: <mcp_types::CancelledNotification as mcp_types::ModelContextProtocolNotification>::Params,
     ) {
         tracing::info!("notifications/cancelled -> params: {:?}", params);
+        #[expect(clippy::unwrap_used)]
+        let running_tool_calls = self.running_tool_calls.lock().unwrap();
+        // `notify_one` stores a permit, so a tool call that is not waiting
+        // for the signal yet still sees it.
+        for (_, cancel) in running_tool_calls
+            .iter()
+            .filter(|(id, _)| *id == params.request_id)
+        {
+            cancel.notify_one();
+        }
     }
 
     fn handle_progress_notification(
@@ -467,3 +754,66 @@ impl MessageProcessor {
         tracing::info!("notifications/message -> params: {:?}", params);
     }
 }
+
+/// Periodically drops the Codex sessions that have been idle for longer than
+/// [`SESSION_IDLE_TIMEOUT`], which shuts them down. Exits once the owning
+/// `MessageProcessor` is dropped.
+async fn expire_idle_sessions(sessions: Weak<Mutex<CodexSessionMap>>) {
+    let mut interval = tokio::time::interval(SESSION_EXPIRY_INTERVAL);
+    loop {
+        interval.tick().await;
+        let Some(sessions) = sessions.upgrade() else {
+            break;
+        };
+        #[expect(clippy::unwrap_used)]
+        let mut sessions = sessions.lock().unwrap();
+        sessions.retain(|session_id, session| {
+            let expired = session.is_idle_for(SESSION_IDLE_TIMEOUT);
+            if expired {
+                tracing::info!("shutting down idle Codex session {session_id}");
+            }
+            !expired
+        });
+    }
+}
+
+fn resource_error(error: ResourceError) -> JSONRPCErrorError {
+    let (code, message) = match error {
+        ResourceError::NotFound(uri) => (
+            RESOURCE_NOT_FOUND_ERROR_CODE,
+            format!("Resource not found: {uri}"),
+        ),
+        ResourceError::InvalidCursor(cursor) => (
+            INVALID_PARAMS_ERROR_CODE,
+            format!("Invalid cursor: {cursor}"),
+        ),
+        ResourceError::Io(message) => (INTERNAL_ERROR_CODE, message),
+    };
+    JSONRPCErrorError {
+        code,
+        message,
+        data: None,
+    }
+}
+
+/// Whether `request`, an `initialize` request, declares the `elicitation`
+/// client capability, which the generated `ClientCapabilities` predates.
+fn request_declares_elicitation(request: &JSONRPCRequest) -> bool {
+    request
+        .params
+        .as_ref()
+        .and_then(|params| params.get("capabilities")?.get("elicitation"))
+        .is_some()
+}
+
+/// The progress token of `request`, if the client supplied one. `_meta` is
+/// not part of the generated request types, so it is read from the raw
+/// request.
+fn request_progress_token(request: &JSONRPCRequest) -> Option<ProgressToken> {
+    let token = request
+        .params
+        .as_ref()?
+        .get("_meta")?
+        .get("progressToken")?;
+    serde_json::from_value(token.clone()).ok()
+}
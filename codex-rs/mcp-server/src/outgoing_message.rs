@@ -0,0 +1,207 @@
+//! Sends messages to the MCP client, including server-to-client requests
+//! whose responses are routed back to the code that sent the request.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicI64;
+use std::sync::atomic::Ordering;
+use std::task::Context;
+use std::task::Poll;
+
+use mcp_types::JSONRPC_VERSION;
+use mcp_types::JSONRPCError;
+use mcp_types::JSONRPCErrorError;
+use mcp_types::JSONRPCMessage;
+use mcp_types::JSONRPCRequest;
+use mcp_types::JSONRPCResponse;
+use mcp_types::RequestId;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::oneshot;
+
+/// The client's answer to a server-to-client request: the `result` of its
+/// response, or the `error` if it responded with an error.
+pub(crate) type ClientResponse = Result<serde_json::Value, JSONRPCErrorError>;
+
+type PendingRequests = Mutex<HashMap<i64, oneshot::Sender<ClientResponse>>>;
+
+pub(crate) struct OutgoingMessageSender {
+    sender: mpsc::Sender<JSONRPCMessage>,
+    next_request_id: AtomicI64,
+    pending_requests: Arc<PendingRequests>,
+}
+
+impl OutgoingMessageSender {
+    pub(crate) fn new(sender: mpsc::Sender<JSONRPCMessage>) -> Self {
+        Self {
+            sender,
+            next_request_id: AtomicI64::new(0),
+            pending_requests: Arc::default(),
+        }
+    }
+
+    /// Enqueue `message` to be written to stdout, waiting for room in the
+    /// channel if necessary. Errors are ignored: they only happen once the
+    /// writer has shut down.
+    pub(crate) async fn send(&self, message: JSONRPCMessage) {
+        let _ = self.sender.send(message).await;
+    }
+
+    pub(crate) fn try_send(
+        &self,
+        message: JSONRPCMessage,
+    ) -> Result<(), TrySendError<JSONRPCMessage>> {
+        self.sender.try_send(message)
+    }
+
+    pub(crate) async fn send_response(&self, id: RequestId, result: serde_json::Value) {
+        self.send(JSONRPCMessage::Response(JSONRPCResponse {
+            jsonrpc: JSONRPC_VERSION.into(),
+            id,
+            result,
+        }))
+        .await;
+    }
+
+    pub(crate) async fn send_error(&self, id: RequestId, error: JSONRPCErrorError) {
+        self.send(JSONRPCMessage::Error(JSONRPCError {
+            error,
+            id,
+            jsonrpc: JSONRPC_VERSION.into(),
+        }))
+        .await;
+    }
+
+    /// Send a request to the client. The returned [`PendingRequest`] resolves
+    /// once the client responds.
+    pub(crate) async fn send_request(
+        &self,
+        method: &str,
+        params: Option<serde_json::Value>,
+    ) -> PendingRequest {
+        let id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        {
+            #[expect(clippy::unwrap_used)]
+            let mut pending_requests = self.pending_requests.lock().unwrap();
+            pending_requests.insert(id, tx);
+        }
+
+        self.send(JSONRPCMessage::Request(JSONRPCRequest {
+            id: RequestId::Integer(id),
+            jsonrpc: JSONRPC_VERSION.into(),
+            method: method.to_string(),
+            params,
+        }))
+        .await;
+        PendingRequest {
+            id,
+            rx,
+            pending_requests: self.pending_requests.clone(),
+        }
+    }
+
+    /// Route the client's response to the request with the given `id` back
+    /// to its sender. Responses to unknown requests are ignored.
+    pub(crate) fn notify_client_response(&self, id: RequestId, response: ClientResponse) {
+        let RequestId::Integer(id) = id else {
+            tracing::warn!("response to unknown request: {id:?}");
+            return;
+        };
+        let tx = {
+            #[expect(clippy::unwrap_used)]
+            let mut pending_requests = self.pending_requests.lock().unwrap();
+            pending_requests.remove(&id)
+        };
+        match tx {
+            Some(tx) => {
+                let _ = tx.send(response);
+            }
+            None => tracing::warn!("response to unknown request: {id}"),
+        }
+    }
+}
+
+/// The client's response to a request sent with
+/// [`OutgoingMessageSender::send_request`]. Resolves to an error if the server
+/// shuts down before the client responds. Dropping it, e.g., because the tool
+/// call that needed the response was cancelled, forgets the request, so that
+/// a late response is ignored.
+pub(crate) struct PendingRequest {
+    id: i64,
+    rx: oneshot::Receiver<ClientResponse>,
+    pending_requests: Arc<PendingRequests>,
+}
+
+impl Future for PendingRequest {
+    type Output = Result<ClientResponse, oneshot::error::RecvError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.get_mut().rx).poll(cx)
+    }
+}
+
+impl Drop for PendingRequest {
+    fn drop(&mut self) {
+        #[expect(clippy::unwrap_used)]
+        let mut pending_requests = self.pending_requests.lock().unwrap();
+        pending_requests.remove(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[tokio::test]
+    #[expect(clippy::expect_used)]
+    async fn test_responses_are_routed_to_their_requests() {
+        let (tx, mut rx) = mpsc::channel(8);
+        let outgoing = OutgoingMessageSender::new(tx);
+
+        let first = outgoing.send_request("first", None).await;
+        let second = outgoing
+            .send_request("second", Some(json!({ "key": "value" })))
+            .await;
+
+        let mut request_ids = Vec::new();
+        for method in ["first", "second"] {
+            let Some(JSONRPCMessage::Request(request)) = rx.recv().await else {
+                panic!("expected a request");
+            };
+            assert_eq!(method, request.method);
+            request_ids.push(request.id);
+        }
+
+        let error = JSONRPCErrorError {
+            code: -32600,
+            message: "unsupported".to_string(),
+            data: None,
+        };
+        outgoing.notify_client_response(request_ids[1].clone(), Err(error.clone()));
+        outgoing.notify_client_response(request_ids[0].clone(), Ok(json!({ "ok": true })));
+
+        assert_eq!(
+            Ok(json!({ "ok": true })),
+            first.await.expect("first response")
+        );
+        assert_eq!(Err(error), second.await.expect("second response"));
+    }
+
+    #[tokio::test]
+    #[expect(clippy::unwrap_used)]
+    async fn test_dropped_requests_are_forgotten() {
+        let (tx, _rx) = mpsc::channel(8);
+        let outgoing = OutgoingMessageSender::new(tx);
+
+        let request = outgoing.send_request("first", None).await;
+        assert_eq!(1, outgoing.pending_requests.lock().unwrap().len());
+        drop(request);
+        assert!(outgoing.pending_requests.lock().unwrap().is_empty());
+    }
+}
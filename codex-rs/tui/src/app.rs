@@ -181,6 +181,11 @@ impl App<'_> {
                     SlashCommand::Clear => {
                         self.chat_widget.clear_conversation_history();
                     }
+                    SlashCommand::Compact => {
+                        if matches!(self.app_state, AppState::Chat) {
+                            self.chat_widget.submit_op(Op::Compact);
+                        }
+                    }
                     SlashCommand::ToggleMouseMode => {
                         if let Err(e) = mouse_capture.toggle() {
                             tracing::error!("Failed to toggle mouse mode: {e}");
@@ -1,4 +1,8 @@
+use std::collections::HashMap;
+
+use codex_core::protocol::Op;
 use crossterm::event::KeyEvent;
+use mcp_types::Prompt;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Alignment;
 use ratatui::layout::Rect;
@@ -18,6 +22,8 @@ use super::command_popup::CommandPopup;
 
 use crate::app_event::AppEvent;
 use crate::app_event_sender::AppEventSender;
+use crate::slash_command::CommandItem;
+use crate::slash_command::McpPromptCommand;
 
 /// Minimum number of visible text rows inside the textarea.
 const MIN_TEXTAREA_ROWS: usize = 1;
@@ -33,8 +39,13 @@ pub enum InputResult {
 pub(crate) struct ChatComposer<'a> {
     textarea: TextArea<'a>,
     command_popup: Option<CommandPopup>,
+    /// Prompts offered by MCP servers, shown in the slash-command popup.
+    mcp_prompts: Vec<McpPromptCommand>,
     app_event_tx: AppEventSender,
     history: ChatComposerHistory,
+    has_input_focus: bool,
+    /// Summary of the tokens used so far, shown in the bottom border.
+    token_usage_label: Option<String>,
 }
 
 impl ChatComposer<'_> {
@@ -46,8 +57,11 @@ impl ChatComposer<'_> {
         let mut this = Self {
             textarea,
             command_popup: None,
+            mcp_prompts: Vec::new(),
             app_event_tx,
             history: ChatComposerHistory::new(),
+            has_input_focus,
+            token_usage_label: None,
         };
         this.update_border(has_input_focus);
         this
@@ -72,10 +86,22 @@ impl ChatComposer<'_> {
             .on_entry_response(log_id, offset, entry, &mut self.textarea)
     }
 
+    /// Record the MCP prompts advertised by `SessionConfiguredEvent` so that
+    /// they can be invoked as slash commands.
+    pub(crate) fn set_mcp_prompts(&mut self, mcp_prompts: HashMap<String, Vec<Prompt>>) {
+        self.mcp_prompts = McpPromptCommand::from_prompts(mcp_prompts);
+    }
+
     pub fn set_input_focus(&mut self, has_focus: bool) {
         self.update_border(has_focus);
     }
 
+    /// Replace the token usage summary shown in the bottom border.
+    pub(crate) fn set_token_usage_label(&mut self, label: String) {
+        self.token_usage_label = Some(label);
+        self.update_border(self.has_input_focus);
+    }
+
     /// Handle a key event coming from the main UI.
     pub fn handle_key_event(&mut self, key_event: KeyEvent) -> (InputResult, bool) {
         let result = match self.command_popup {
@@ -133,8 +159,31 @@ impl ChatComposer<'_> {
                 ctrl: false,
             } => {
                 if let Some(cmd) = popup.selected_command() {
-                    // Send command to the app layer.
-                    self.app_event_tx.send(AppEvent::DispatchCommand(*cmd));
+                    match cmd {
+                        CommandItem::BuiltIn(cmd) => {
+                            // Send command to the app layer.
+                            self.app_event_tx.send(AppEvent::DispatchCommand(cmd));
+                        }
+                        CommandItem::McpPrompt(prompt) => {
+                            // Everything after the command token on the first
+                            // line is used as the prompt's arguments.
+                            let first_line = self
+                                .textarea
+                                .lines()
+                                .first()
+                                .map(|s| s.as_str())
+                                .unwrap_or("");
+                            let args = McpPromptCommand::arguments_of(first_line);
+                            // Ask the agent to expand the prompt; the result
+                            // is submitted as user input once it arrives.
+                            self.app_event_tx
+                                .send(AppEvent::CodexOp(Op::GetMcpPromptRequest {
+                                    server: prompt.server.clone(),
+                                    name: prompt.prompt.name.clone(),
+                                    arguments: prompt.parse_arguments(args),
+                                }));
+                        }
+                    }
 
                     // Clear textarea so no residual text remains.
                     self.textarea.select_all();
@@ -237,7 +286,9 @@ impl ChatComposer<'_> {
 
         if first_line.starts_with('/') {
             // Create popup lazily when the user starts a slash command.
-            let popup = self.command_popup.get_or_insert_with(CommandPopup::new);
+            let popup = self
+                .command_popup
+                .get_or_insert_with(|| CommandPopup::new(self.mcp_prompts.clone()));
 
             // Forward *only* the first line since `CommandPopup` only needs
             // the command token.
@@ -260,6 +311,8 @@ impl ChatComposer<'_> {
     }
 
     fn update_border(&mut self, has_focus: bool) {
+        self.has_input_focus = has_focus;
+
         struct BlockState {
             right_title: Line<'static>,
             border_style: Style,
@@ -278,13 +331,15 @@ impl ChatComposer<'_> {
             }
         };
 
-        self.textarea.set_block(
-            ratatui::widgets::Block::default()
-                .title_bottom(bs.right_title)
-                .borders(Borders::ALL)
-                .border_type(BorderType::Rounded)
-                .border_style(bs.border_style),
-        );
+        let mut block = ratatui::widgets::Block::default()
+            .title_bottom(bs.right_title)
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(bs.border_style);
+        if let Some(label) = &self.token_usage_label {
+            block = block.title_bottom(Line::from(label.clone()).dim());
+        }
+        self.textarea.set_block(block);
     }
 
     pub(crate) fn is_command_popup_visible(&self) -> bool {
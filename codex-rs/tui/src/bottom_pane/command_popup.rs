@@ -13,6 +13,8 @@ use ratatui::widgets::Table;
 use ratatui::widgets::Widget;
 use ratatui::widgets::WidgetRef;
 
+use crate::slash_command::CommandItem;
+use crate::slash_command::McpPromptCommand;
 use crate::slash_command::SlashCommand;
 use crate::slash_command::built_in_slash_commands;
 
@@ -25,14 +27,17 @@ use ratatui::style::Modifier;
 pub(crate) struct CommandPopup {
     command_filter: String,
     all_commands: HashMap<&'static str, SlashCommand>,
+    /// Prompts offered by MCP servers, listed after the built-in commands.
+    mcp_prompts: Vec<McpPromptCommand>,
     selected_idx: Option<usize>,
 }
 
 impl CommandPopup {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(mcp_prompts: Vec<McpPromptCommand>) -> Self {
         Self {
             command_filter: String::new(),
             all_commands: built_in_slash_commands(),
+            mcp_prompts,
             selected_idx: None,
         }
     }
@@ -81,25 +86,28 @@ impl CommandPopup {
     }
 
     /// Return the list of commands that match the current filter. Matching is
-    /// performed using a *prefix* comparison on the command name.
-    fn filtered_commands(&self) -> Vec<&SlashCommand> {
+    /// performed using a case-insensitive *prefix* comparison on the command
+    /// name.
+    fn filtered_commands(&self) -> Vec<CommandItem<'_>> {
+        let filter = self.command_filter.to_ascii_lowercase();
         let mut cmds: Vec<&SlashCommand> = self
             .all_commands
             .values()
-            .filter(|cmd| {
-                if self.command_filter.is_empty() {
-                    true
-                } else {
-                    cmd.command()
-                        .starts_with(&self.command_filter.to_ascii_lowercase())
-                }
-            })
+            .filter(|cmd| cmd.command().starts_with(&filter))
             .collect();
 
         // Sort the commands alphabetically so the order is stable and
-        // predictable.
+        // predictable. MCP prompts are already sorted.
         cmds.sort_by(|a, b| a.command().cmp(b.command()));
-        cmds
+        cmds.into_iter()
+            .map(|cmd| CommandItem::BuiltIn(*cmd))
+            .chain(
+                self.mcp_prompts
+                    .iter()
+                    .filter(|prompt| prompt.command().to_ascii_lowercase().starts_with(&filter))
+                    .map(CommandItem::McpPrompt),
+            )
+            .collect()
     }
 
     /// Move the selection cursor one step up.
@@ -139,7 +147,7 @@ impl CommandPopup {
     }
 
     /// Return currently selected command, if any.
-    pub(crate) fn selected_command(&self) -> Option<&SlashCommand> {
+    pub(crate) fn selected_command(&self) -> Option<CommandItem<'_>> {
         let matches = self.filtered_commands();
         self.selected_idx.and_then(|idx| matches.get(idx).copied())
     }
@@ -152,8 +160,7 @@ impl WidgetRef for CommandPopup {
         let matches = self.filtered_commands();
 
         let mut rows: Vec<Row> = Vec::new();
-        let visible_matches: Vec<&SlashCommand> =
-            matches.into_iter().take(MAX_POPUP_ROWS).collect();
+        let visible_matches: Vec<CommandItem> = matches.into_iter().take(MAX_POPUP_ROWS).collect();
 
         if visible_matches.is_empty() {
             rows.push(Row::new(vec![
@@ -170,7 +177,7 @@ impl WidgetRef for CommandPopup {
                 };
 
                 rows.push(Row::new(vec![
-                    Cell::from(cmd.command().to_string()).style(cmd_style),
+                    Cell::from(cmd.command()).style(cmd_style),
                     Cell::from(cmd.description().to_string()).style(style),
                 ]));
             }
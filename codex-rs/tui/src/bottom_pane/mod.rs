@@ -1,8 +1,11 @@
 //! Bottom pane: shows the ChatComposer or a BottomPaneView, if one is active.
 
+use std::collections::HashMap;
+
 use bottom_pane_view::BottomPaneView;
 use bottom_pane_view::ConditionalUpdate;
 use crossterm::event::KeyEvent;
+use mcp_types::Prompt;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
 use ratatui::widgets::WidgetRef;
@@ -93,6 +96,12 @@ impl BottomPane<'_> {
         }
     }
 
+    /// Update the token usage summary shown below the composer.
+    pub(crate) fn set_token_usage_label(&mut self, label: String) {
+        self.composer.set_token_usage_label(label);
+        self.request_redraw();
+    }
+
     /// Update the UI to reflect whether this `BottomPane` has input focus.
     pub(crate) fn set_input_focus(&mut self, has_focus: bool) {
         self.has_input_focus = has_focus;
@@ -167,6 +176,11 @@ impl BottomPane<'_> {
         self.active_view.is_none() && self.composer.is_command_popup_visible()
     }
 
+    /// Offer the prompts of the configured MCP servers as slash commands.
+    pub(crate) fn set_mcp_prompts(&mut self, mcp_prompts: HashMap<String, Vec<Prompt>>) {
+        self.composer.set_mcp_prompts(mcp_prompts);
+    }
+
     // --- History helpers ---
 
     pub(crate) fn set_history_metadata(&mut self, log_id: u64, entry_count: usize) {
@@ -3,7 +3,9 @@ use std::sync::Arc;
 
 use codex_core::codex_wrapper::init_codex;
 use codex_core::config::Config;
+use codex_core::protocol::AgentMessageDeltaEvent;
 use codex_core::protocol::AgentMessageEvent;
+use codex_core::protocol::AgentReasoningDeltaEvent;
 use codex_core::protocol::AgentReasoningEvent;
 use codex_core::protocol::ApplyPatchApprovalRequestEvent;
 use codex_core::protocol::ErrorEvent;
@@ -12,12 +14,20 @@ use codex_core::protocol::EventMsg;
 use codex_core::protocol::ExecApprovalRequestEvent;
 use codex_core::protocol::ExecCommandBeginEvent;
 use codex_core::protocol::ExecCommandEndEvent;
+use codex_core::protocol::ExecCommandOutputDeltaEvent;
+use codex_core::protocol::GetMcpPromptResponseEvent;
 use codex_core::protocol::InputItem;
+use codex_core::protocol::McpToolCallApprovalRequestEvent;
 use codex_core::protocol::McpToolCallBeginEvent;
 use codex_core::protocol::McpToolCallEndEvent;
 use codex_core::protocol::Op;
 use codex_core::protocol::PatchApplyBeginEvent;
+use codex_core::protocol::ResumedMessage;
+use codex_core::protocol::TokenCountEvent;
 use crossterm::event::KeyEvent;
+use mcp_types::EmbeddedResourceResource;
+use mcp_types::PromptMessage;
+use mcp_types::PromptMessageContent;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Constraint;
 use ratatui::layout::Direction;
@@ -219,10 +229,24 @@ impl ChatWidget<'_> {
                 self.conversation_history
                     .add_session_info(&self.config, event.clone());
 
+                // Replay the conversation of a resumed session.
+                for message in event.resumed_messages.unwrap_or_default() {
+                    match message {
+                        ResumedMessage::User { text } => {
+                            self.conversation_history.add_user_message(text);
+                        }
+                        ResumedMessage::Assistant { text } => {
+                            self.conversation_history
+                                .add_agent_message(&self.config, text);
+                        }
+                    }
+                }
+
                 // Forward history metadata to the bottom pane so the chat
                 // composer can navigate through past messages.
                 self.bottom_pane
                     .set_history_metadata(event.history_log_id, event.history_entry_count);
+                self.bottom_pane.set_mcp_prompts(event.mcp_prompts);
 
                 if let Some(user_message) = self.initial_user_message.take() {
                     // If the user provided an initial message, add it to the
@@ -237,11 +261,21 @@ impl ChatWidget<'_> {
                     .add_agent_message(&self.config, message);
                 self.request_redraw();
             }
+            EventMsg::AgentMessageDelta(AgentMessageDeltaEvent { delta }) => {
+                self.conversation_history
+                    .append_agent_message_delta(&self.config, &delta);
+                self.request_redraw();
+            }
             EventMsg::AgentReasoning(AgentReasoningEvent { text }) => {
                 self.conversation_history
                     .add_agent_reasoning(&self.config, text);
                 self.request_redraw();
             }
+            EventMsg::AgentReasoningDelta(AgentReasoningDeltaEvent { delta }) => {
+                self.conversation_history
+                    .append_agent_reasoning_delta(&self.config, &delta);
+                self.request_redraw();
+            }
             EventMsg::TaskStarted => {
                 self.bottom_pane.set_task_running(true);
                 self.request_redraw();
@@ -267,6 +301,20 @@ impl ChatWidget<'_> {
                 };
                 self.bottom_pane.push_approval_request(request);
             }
+            EventMsg::McpToolCallApprovalRequest(McpToolCallApprovalRequestEvent {
+                call_id: _,
+                server,
+                tool,
+                arguments,
+            }) => {
+                let request = ApprovalRequest::McpToolCall {
+                    id,
+                    server,
+                    tool,
+                    arguments,
+                };
+                self.bottom_pane.push_approval_request(request);
+            }
             EventMsg::ApplyPatchApprovalRequest(ApplyPatchApprovalRequestEvent {
                 changes,
                 reason,
@@ -306,6 +354,15 @@ impl ChatWidget<'_> {
                     .add_active_exec_command(call_id, command);
                 self.request_redraw();
             }
+            EventMsg::ExecCommandOutputDelta(ExecCommandOutputDeltaEvent {
+                call_id,
+                stream: _,
+                chunk,
+            }) => {
+                self.conversation_history
+                    .append_exec_command_output(&call_id, &chunk);
+                self.request_redraw();
+            }
             EventMsg::PatchApplyBegin(PatchApplyBeginEvent {
                 call_id: _,
                 auto_approved,
@@ -360,6 +417,24 @@ impl ChatWidget<'_> {
                 self.bottom_pane
                     .on_history_entry_response(log_id, offset, entry.map(|e| e.text));
             }
+            EventMsg::GetMcpPromptResponse(GetMcpPromptResponseEvent {
+                server,
+                name,
+                result,
+            }) => {
+                let text = prompt_messages_to_text(result.messages);
+                if text.is_empty() {
+                    self.conversation_history
+                        .add_error(format!("MCP prompt `{server}:{name}` expanded to no text"));
+                } else {
+                    self.submit_user_message(text.into());
+                }
+                self.request_redraw();
+            }
+            EventMsg::TokenCount(event) => {
+                self.bottom_pane
+                    .set_token_usage_label(format_token_usage(&event));
+            }
             event => {
                 self.conversation_history
                     .add_background_event(format!("{event:?}"));
@@ -412,3 +487,35 @@ impl WidgetRef for &ChatWidget<'_> {
         (&self.bottom_pane).render(chunks[1], buf);
     }
 }
+
+/// Summary of the session's token usage (and its estimated cost, if known)
+/// for the status line below the composer.
+fn format_token_usage(event: &TokenCountEvent) -> String {
+    let total = &event.session_total;
+    let mut label = format!(
+        "{} tokens used ({} in, {} out)",
+        total.total_tokens, total.input_tokens, total.output_tokens
+    );
+    if let Some(cost) = event.estimated_cost_usd {
+        label.push_str(&format!(" · ~${cost:.4}"));
+    }
+    label
+}
+
+/// Join the text of an expanded MCP prompt into a single user message. Only
+/// text content (including embedded text resources) is kept; images and
+/// audio are dropped, as are the roles of the individual messages.
+fn prompt_messages_to_text(messages: Vec<PromptMessage>) -> String {
+    messages
+        .into_iter()
+        .filter_map(|message| match message.content {
+            PromptMessageContent::TextContent(content) => Some(content.text),
+            PromptMessageContent::EmbeddedResource(resource) => match resource.resource {
+                EmbeddedResourceResource::TextResourceContents(contents) => Some(contents.text),
+                EmbeddedResourceResource::BlobResourceContents(_) => None,
+            },
+            PromptMessageContent::ImageContent(_) | PromptMessageContent::AudioContent(_) => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
@@ -43,4 +43,9 @@ pub struct Cli {
     /// Disable server‑side response storage (sends the full conversation context with every request)
     #[arg(long = "disable-response-storage", default_value_t = false)]
     pub disable_response_storage: bool,
+
+    /// Resume a previous session, given either its session id or the path to
+    /// its rollout file under `~/.codex/sessions`.
+    #[arg(long = "resume", value_name = "SESSION_ID|PATH")]
+    pub resume: Option<String>,
 }
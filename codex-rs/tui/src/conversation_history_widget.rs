@@ -193,12 +193,59 @@ impl ConversationHistoryWidget {
         self.add_to_history(HistoryCell::new_user_prompt(message));
     }
 
+    /// Adds the complete agent message, replacing the cell that displayed it
+    /// while it was being streamed, if any.
     pub fn add_agent_message(&mut self, config: &Config, message: String) {
-        self.add_to_history(HistoryCell::new_agent_message(config, message));
+        let cell = HistoryCell::new_agent_message(config, message);
+        match self
+            .last_entry_index(|cell| matches!(cell, HistoryCell::StreamingAgentMessage { .. }))
+        {
+            Some(idx) => self.replace_entry(idx, cell),
+            None => self.add_to_history(cell),
+        }
     }
 
+    pub fn append_agent_message_delta(&mut self, config: &Config, delta: &str) {
+        match self.entries.last().map(|entry| &entry.cell) {
+            Some(HistoryCell::StreamingAgentMessage { text, .. }) => {
+                let text = format!("{text}{delta}");
+                let idx = self.entries.len() - 1;
+                self.replace_entry(idx, HistoryCell::new_streaming_agent_message(config, text));
+            }
+            _ => self.add_to_history(HistoryCell::new_streaming_agent_message(
+                config,
+                delta.to_string(),
+            )),
+        }
+    }
+
+    /// Adds the complete reasoning, replacing the cell that displayed it while
+    /// it was being streamed, if any.
     pub fn add_agent_reasoning(&mut self, config: &Config, text: String) {
-        self.add_to_history(HistoryCell::new_agent_reasoning(config, text));
+        let cell = HistoryCell::new_agent_reasoning(config, text);
+        match self
+            .last_entry_index(|cell| matches!(cell, HistoryCell::StreamingAgentReasoning { .. }))
+        {
+            Some(idx) => self.replace_entry(idx, cell),
+            None => self.add_to_history(cell),
+        }
+    }
+
+    pub fn append_agent_reasoning_delta(&mut self, config: &Config, delta: &str) {
+        match self.entries.last().map(|entry| &entry.cell) {
+            Some(HistoryCell::StreamingAgentReasoning { text, .. }) => {
+                let text = format!("{text}{delta}");
+                let idx = self.entries.len() - 1;
+                self.replace_entry(
+                    idx,
+                    HistoryCell::new_streaming_agent_reasoning(config, text),
+                );
+            }
+            _ => self.add_to_history(HistoryCell::new_streaming_agent_reasoning(
+                config,
+                delta.to_string(),
+            )),
+        }
     }
 
     pub fn add_background_event(&mut self, message: String) {
@@ -248,12 +295,54 @@ impl ConversationHistoryWidget {
         });
     }
 
+    /// Index of the last entry, if it matches `predicate`. A streaming cell
+    /// further up belongs to an earlier item whose stream was interrupted, as
+    /// anything that happens after an item adds new entries.
+    fn last_entry_index(&self, predicate: impl Fn(&HistoryCell) -> bool) -> Option<usize> {
+        self.entries
+            .len()
+            .checked_sub(1)
+            .filter(|&idx| predicate(&self.entries[idx].cell))
+    }
+
+    fn replace_entry(&mut self, idx: usize, cell: HistoryCell) {
+        let width = self.cached_width.get();
+        let entry = &mut self.entries[idx];
+        entry.cell = cell;
+        if width > 0 {
+            entry
+                .line_count
+                .set(wrapped_line_count_for_cell(&entry.cell, width));
+        }
+    }
+
     /// Remove all history entries and reset scrolling.
     pub fn clear(&mut self) {
         self.entries.clear();
         self.scroll_position = usize::MAX;
     }
 
+    pub fn append_exec_command_output(&mut self, call_id: &str, chunk: &str) {
+        let width = self.cached_width.get();
+        let Some(entry) = self.entries.iter_mut().find(|entry| {
+            matches!(
+                &entry.cell,
+                HistoryCell::ActiveExecCommand { call_id: history_id, .. } if history_id == call_id
+            )
+        }) else {
+            return;
+        };
+
+        entry.cell.append_exec_output(chunk);
+
+        // Update cached line count.
+        if width > 0 {
+            entry
+                .line_count
+                .set(wrapped_line_count_for_cell(&entry.cell, width));
+        }
+    }
+
     pub fn record_completed_exec_command(
         &mut self,
         call_id: String,
@@ -42,15 +42,31 @@ pub(crate) enum HistoryCell {
     /// Message from the agent.
     AgentMessage { lines: Vec<Line<'static>> },
 
+    /// Message from the agent that is still being streamed.
+    StreamingAgentMessage {
+        /// Text received so far, so the next delta can be appended to it.
+        text: String,
+        lines: Vec<Line<'static>>,
+    },
+
     /// Reasoning event from the agent.
     AgentReasoning { lines: Vec<Line<'static>> },
 
+    /// Reasoning from the agent that is still being streamed.
+    StreamingAgentReasoning {
+        /// Text received so far, so the next delta can be appended to it.
+        text: String,
+        lines: Vec<Line<'static>>,
+    },
+
     /// An exec tool call that has not finished yet.
     ActiveExecCommand {
         call_id: String,
         /// The shell command, escaped and formatted.
         command: String,
         start: Instant,
+        /// Tail of the output the command has produced so far.
+        output: String,
         lines: Vec<Line<'static>>,
     },
 
@@ -105,6 +121,8 @@ impl HistoryCell {
             session_id,
             history_log_id: _,
             history_entry_count: _,
+            resumed_messages,
+            mcp_prompts: _,
         } = event;
         if is_first_event {
             let mut lines: Vec<Line<'static>> = vec![
@@ -115,7 +133,11 @@ impl HistoryCell {
                 ]),
                 Line::from(""),
                 Line::from(vec![
-                    "codex session".magenta().bold(),
+                    if resumed_messages.is_some() {
+                        "codex session (resumed)".magenta().bold()
+                    } else {
+                        "codex session".magenta().bold()
+                    },
                     " ".into(),
                     session_id.to_string().dim(),
                 ]),
@@ -164,6 +186,15 @@ impl HistoryCell {
         HistoryCell::AgentMessage { lines }
     }
 
+    pub(crate) fn new_streaming_agent_message(config: &Config, text: String) -> Self {
+        let mut lines: Vec<Line<'static>> = Vec::new();
+        lines.push(Line::from("codex".magenta().bold()));
+        append_markdown(&text, &mut lines, config);
+        lines.push(Line::from(""));
+
+        HistoryCell::StreamingAgentMessage { text, lines }
+    }
+
     pub(crate) fn new_agent_reasoning(config: &Config, text: String) -> Self {
         let mut lines: Vec<Line<'static>> = Vec::new();
         lines.push(Line::from("thinking".magenta().italic()));
@@ -173,24 +204,45 @@ impl HistoryCell {
         HistoryCell::AgentReasoning { lines }
     }
 
+    pub(crate) fn new_streaming_agent_reasoning(config: &Config, text: String) -> Self {
+        let mut lines: Vec<Line<'static>> = Vec::new();
+        lines.push(Line::from("thinking".magenta().italic()));
+        append_markdown(&text, &mut lines, config);
+        lines.push(Line::from(""));
+
+        HistoryCell::StreamingAgentReasoning { text, lines }
+    }
+
     pub(crate) fn new_active_exec_command(call_id: String, command: Vec<String>) -> Self {
         let command_escaped = escape_command(&command);
         let start = Instant::now();
-
-        let lines: Vec<Line<'static>> = vec![
-            Line::from(vec!["command".magenta(), " running...".dim()]),
-            Line::from(format!("$ {command_escaped}")),
-            Line::from(""),
-        ];
+        let lines = active_exec_command_lines(&command_escaped, "");
 
         HistoryCell::ActiveExecCommand {
             call_id,
             command: command_escaped,
             start,
+            output: String::new(),
             lines,
         }
     }
 
+    /// Append output streamed by a running command so the cell shows a live
+    /// tail of it. Has no effect on any other kind of cell.
+    pub(crate) fn append_exec_output(&mut self, chunk: &str) {
+        if let HistoryCell::ActiveExecCommand {
+            command,
+            output,
+            lines,
+            ..
+        } = self
+        {
+            output.push_str(chunk);
+            retain_last_lines(output, TOOL_CALL_MAX_LINES);
+            *lines = active_exec_command_lines(command, output);
+        }
+    }
+
     pub(crate) fn new_completed_exec_command(command: String, output: CommandOutput) -> Self {
         let CommandOutput {
             exit_code,
@@ -388,7 +440,9 @@ impl HistoryCell {
             HistoryCell::WelcomeMessage { lines, .. }
             | HistoryCell::UserPrompt { lines, .. }
             | HistoryCell::AgentMessage { lines, .. }
+            | HistoryCell::StreamingAgentMessage { lines, .. }
             | HistoryCell::AgentReasoning { lines, .. }
+            | HistoryCell::StreamingAgentReasoning { lines, .. }
             | HistoryCell::BackgroundEvent { lines, .. }
             | HistoryCell::ErrorEvent { lines, .. }
             | HistoryCell::SessionInfo { lines, .. }
@@ -401,6 +455,30 @@ impl HistoryCell {
     }
 }
 
+fn active_exec_command_lines(command: &str, output: &str) -> Vec<Line<'static>> {
+    let mut lines: Vec<Line<'static>> = vec![
+        Line::from(vec!["command".magenta(), " running...".dim()]),
+        Line::from(format!("$ {command}")),
+    ];
+    for raw in output.lines() {
+        lines.push(ansi_escape_line(raw).dim());
+    }
+    lines.push(Line::from(""));
+    lines
+}
+
+/// Drop everything but the last `max_lines` lines of `output`.
+fn retain_last_lines(output: &mut String, max_lines: usize) {
+    let trimmed = output.strip_suffix('\n').unwrap_or(output);
+    let cut = trimmed
+        .rmatch_indices('\n')
+        .nth(max_lines.saturating_sub(1))
+        .map(|(idx, _)| idx);
+    if let Some(idx) = cut {
+        output.drain(..=idx);
+    }
+}
+
 fn create_diff_summary(changes: HashMap<PathBuf, FileChange>) -> Vec<String> {
     // Build a concise, human‑readable summary list similar to the
     // `git status` short format so the user can reason about the
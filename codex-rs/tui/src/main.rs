@@ -4,6 +4,7 @@ use codex_tui::run_main;
 
 #[tokio::main]
 async fn main() -> std::io::Result<()> {
+    codex_core::maybe_run_apply_patch_helper();
     let cli = Cli::parse();
     run_main(cli)?;
     Ok(())
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use mcp_types::Prompt;
 use strum::IntoEnumIterator;
 use strum_macros::AsRefStr; // derive macro
 use strum_macros::EnumIter;
@@ -13,6 +14,7 @@ use strum_macros::IntoStaticStr;
 #[strum(serialize_all = "kebab-case")]
 pub enum SlashCommand {
     Clear,
+    Compact,
     ToggleMouseMode,
     Quit,
 }
@@ -22,6 +24,9 @@ impl SlashCommand {
     pub fn description(self) -> &'static str {
         match self {
             SlashCommand::Clear => "Clear the chat history.",
+            SlashCommand::Compact => {
+                "Summarize the conversation so far to free up the context window."
+            }
             SlashCommand::ToggleMouseMode => {
                 "Toggle mouse mode (enable for scrolling, disable for text selection)"
             }
@@ -40,3 +45,150 @@ impl SlashCommand {
 pub fn built_in_slash_commands() -> HashMap<&'static str, SlashCommand> {
     SlashCommand::iter().map(|c| (c.command(), c)).collect()
 }
+
+/// A prompt offered by an MCP server, invoked as `/<server>:<prompt>`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct McpPromptCommand {
+    pub server: String,
+    pub prompt: Prompt,
+}
+
+impl McpPromptCommand {
+    /// Flatten the prompts advertised by `SessionConfiguredEvent` into a list
+    /// of commands sorted by their command string.
+    pub fn from_prompts(prompts: HashMap<String, Vec<Prompt>>) -> Vec<Self> {
+        let mut commands: Vec<Self> = prompts
+            .into_iter()
+            .flat_map(|(server, prompts)| {
+                prompts.into_iter().map(move |prompt| Self {
+                    server: server.clone(),
+                    prompt,
+                })
+            })
+            .collect();
+        commands.sort_by_key(|c| c.command());
+        commands
+    }
+
+    /// Command string without the leading '/'.
+    pub fn command(&self) -> String {
+        format!("{}:{}", self.server, self.prompt.name)
+    }
+
+    /// User-visible description shown in the popup.
+    pub fn description(&self) -> &str {
+        self.prompt
+            .description
+            .as_deref()
+            .unwrap_or("Prompt provided by an MCP server.")
+    }
+
+    /// The text after the command token of `line`, e.g. `42 tests` for
+    /// `/github:rev 42 tests`. The popup also matches partially typed commands
+    /// in any case, so the token is not required to match [`Self::command`].
+    pub fn arguments_of(line: &str) -> &str {
+        line.trim_start()
+            .split_once(char::is_whitespace)
+            .map_or("", |(_, args)| args)
+    }
+
+    /// Assign the whitespace-separated words in `args` to the prompt's
+    /// arguments in the order they are declared. The last argument receives
+    /// the remainder of the text so it may contain spaces.
+    pub fn parse_arguments(&self, args: &str) -> Option<HashMap<String, String>> {
+        let declared = self.prompt.arguments.as_deref().unwrap_or_default();
+        let mut rest = args.trim();
+        let mut arguments = HashMap::new();
+        for (idx, argument) in declared.iter().enumerate() {
+            if rest.is_empty() {
+                break;
+            }
+            let value = if idx + 1 == declared.len() {
+                std::mem::take(&mut rest)
+            } else {
+                let (value, remainder) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                rest = remainder.trim_start();
+                value
+            };
+            arguments.insert(argument.name.clone(), value.to_string());
+        }
+        (!arguments.is_empty()).then_some(arguments)
+    }
+}
+
+/// An entry in the slash-command popup.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum CommandItem<'a> {
+    BuiltIn(SlashCommand),
+    McpPrompt(&'a McpPromptCommand),
+}
+
+impl CommandItem<'_> {
+    pub fn command(&self) -> String {
+        match self {
+            CommandItem::BuiltIn(cmd) => cmd.command().to_string(),
+            CommandItem::McpPrompt(prompt) => prompt.command(),
+        }
+    }
+
+    pub fn description(&self) -> &str {
+        match self {
+            CommandItem::BuiltIn(cmd) => cmd.description(),
+            CommandItem::McpPrompt(prompt) => prompt.description(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_types::PromptArgument;
+    use pretty_assertions::assert_eq;
+
+    fn review_prompt() -> McpPromptCommand {
+        let argument = |name: &str| PromptArgument {
+            description: None,
+            name: name.to_string(),
+            required: None,
+        };
+        McpPromptCommand {
+            server: "github".to_string(),
+            prompt: Prompt {
+                arguments: Some(vec![argument("pr"), argument("focus")]),
+                description: None,
+                name: "review".to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn mcp_prompt_arguments_are_assigned_in_order() {
+        let prompt = review_prompt();
+        assert_eq!(prompt.command(), "github:review");
+        assert_eq!(prompt.parse_arguments("  "), None);
+        assert_eq!(
+            prompt.parse_arguments("42"),
+            Some(HashMap::from([("pr".to_string(), "42".to_string())]))
+        );
+        assert_eq!(
+            prompt.parse_arguments("42  error handling in the client"),
+            Some(HashMap::from([
+                ("pr".to_string(), "42".to_string()),
+                (
+                    "focus".to_string(),
+                    "error handling in the client".to_string()
+                ),
+            ]))
+        );
+    }
+
+    #[test]
+    fn mcp_prompt_arguments_follow_any_command_token() {
+        assert_eq!(McpPromptCommand::arguments_of("/github:review 42"), "42");
+        assert_eq!(
+            McpPromptCommand::arguments_of(" /GitHub:Rev 42 tests"),
+            "42 tests"
+        );
+        assert_eq!(McpPromptCommand::arguments_of("/github:review"), "");
+    }
+}
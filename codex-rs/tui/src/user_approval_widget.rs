@@ -45,6 +45,12 @@ pub(crate) enum ApprovalRequest {
         reason: Option<String>,
         grant_root: Option<PathBuf>,
     },
+    McpToolCall {
+        id: String,
+        server: String,
+        tool: String,
+        arguments: Option<serde_json::Value>,
+    },
 }
 
 /// Options displayed in the *select* mode.
@@ -175,6 +181,31 @@ impl UserApprovalWidget<'_> {
                 contents.push(Line::from("Allow changes?"));
                 contents.push(Line::from(""));
 
+                Paragraph::new(contents)
+            }
+            ApprovalRequest::McpToolCall {
+                server,
+                tool,
+                arguments,
+                ..
+            } => {
+                let mut contents: Vec<Line> = vec![
+                    Line::from("MCP Tool Call".bold()),
+                    Line::from(""),
+                    Line::from(vec![format!("{server}.").dim(), Span::from(tool.clone())]),
+                    Line::from(""),
+                ];
+
+                if let Some(arguments) = arguments {
+                    let pretty = serde_json::to_string_pretty(arguments)
+                        .unwrap_or_else(|_| arguments.to_string());
+                    contents.extend(pretty.lines().map(|line| Line::from(line.to_string())));
+                    contents.push(Line::from(""));
+                }
+
+                contents.push(Line::from("Allow tool call?"));
+                contents.push(Line::from(""));
+
                 Paragraph::new(contents)
             }
         };
@@ -301,6 +332,10 @@ impl UserApprovalWidget<'_> {
                 id: id.clone(),
                 decision,
             },
+            ApprovalRequest::McpToolCall { id, .. } => Op::McpToolCallApproval {
+                id: id.clone(),
+                decision,
+            },
         };
 
         // Ignore feedback for now – the current `Op` variants do not carry it.